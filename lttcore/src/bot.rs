@@ -31,6 +31,9 @@ use std::panic::RefUnwindSafe;
 mod contender;
 mod context;
 pub(crate) mod defective;
+pub mod mcts;
+pub mod minimax;
+mod search;
 pub use contender::Contender;
 pub use context::{BotContext, BotContextBuilder};
 
@@ -0,0 +1,341 @@
+//! A general purpose Monte Carlo Tree Search bot
+//!
+//! [`MctsBot`] plays any game implementing [`EnumerateActions`] and [`Determinize`] without
+//! knowing anything else about its rules. It's meant as a reasonable baseline opponent for new
+//! games before anyone has written a game specific bot.
+//!
+//! # How it works
+//!
+//! Each search iteration samples a full [`GameState`] from the bot's [`PlayerPov`] with
+//! [`Determinize::determinize`], walks down the search tree picking an action for every acting
+//! player with UCB1, plays the rest of the game out with random legal actions and then feeds the
+//! result back up the tree. Every acting player picks from their own statistics, which lets the
+//! same tree handle simultaneous turns ("decoupled UCT").
+//!
+//! Finished games are scored by rank using [`Score`](crate::play::Score), a win against everyone
+//! is worth `1.0`, a loss against everyone `0.0` and ties split the difference. Games with no
+//! score at the end (like a drawn game of tic-tac-toe) are worth `0.5` to everyone.
+//!
+//! # Limitations
+//!
+//! * The legal actions at each node are taken from the first sampled game state to reach it, so
+//!   the bot assumes a player's legal actions don't depend on information hidden from them
+//! * Games with very large action spaces are sampled down to [`MctsBot::max_actions`] candidates
+//!   per player per node
+use super::search::{advance, rewards};
+use super::{Bot, BotContext, BotError};
+use crate::play::{ActionResponse, Determinize, EnumerateActions, GameState, Play, Player};
+use crate::pov::player::PlayerPov;
+use crate::utilities::PlayerIndexedData as PID;
+use rand::seq::IteratorRandom;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::marker::PhantomData;
+
+/// How many search iterations to run between each check of the time budget
+const ITERATIONS_PER_CHECKPOINT: u32 = 16;
+
+/// A [`Bot`] that picks actions using Monte Carlo Tree Search
+///
+/// ```
+/// use lttcore::bot::{Bot, BotContext, mcts::{MctsBot, MctsBotBuilder}};
+/// use lttcore::examples::{TicTacToe, tic_tac_toe::{Position, Settings}};
+/// use lttcore::play::{ActionResponse::Response, Player, seed::SEED_42};
+/// use lttcore::pov::game_progression::GameProgression;
+///
+/// let mut game: GameProgression<TicTacToe> = GameProgression::from_settings(Settings);
+///
+/// // X has two in a row, O needs to block
+/// for position in [Position::new(0, 0), Position::new(1, 1), Position::new(1, 0)] {
+///     let player = game.which_players_input_needed().next().unwrap();
+///     let update = game.resolve([(player, Response(position.into()))].into_iter().collect());
+///     game.update(update);
+/// }
+///
+/// let mut bot: MctsBot<TicTacToe> = MctsBotBuilder::default().iterations(2_000).build().unwrap();
+/// let action = bot.on_action_request(&game.player_pov(Player::new(1)), &BotContext::from(&SEED_42)).unwrap();
+/// assert_eq!(action.position, Position::new(2, 0));
+/// ```
+#[derive(Builder, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[builder(derive(Debug), build_fn(validate = "Self::validate"))]
+#[serde(bound = "")]
+pub struct MctsBot<T: Play> {
+    /// The most search iterations to run per action request, defaults to `1_000`. The search may
+    /// stop earlier if the [`BotContext`] runs out of time
+    #[builder(default = "1_000")]
+    iterations: u32,
+    /// The UCB1 exploration constant, higher values try more unpromising actions. Defaults to
+    /// `sqrt(2)`
+    #[builder(default = "std::f64::consts::SQRT_2")]
+    exploration: f64,
+    /// The most turns a random playout will run before giving up and scoring the game as is.
+    /// Defaults to `200`
+    #[builder(default = "200")]
+    max_rollout_turns: u32,
+    /// The most candidate actions considered for a player at each node, defaults to `64`
+    #[builder(default = "64")]
+    max_actions: usize,
+    #[builder(default, setter(skip))]
+    #[serde(skip)]
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Play> MctsBotBuilder<T> {
+    fn validate(&self) -> Result<(), String> {
+        if self.iterations == Some(0) {
+            return Err("iterations must be greater than zero".into());
+        }
+
+        if self.max_actions == Some(0) {
+            return Err("max_actions must be greater than zero".into());
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: Play> Default for MctsBot<T> {
+    fn default() -> Self {
+        MctsBotBuilder::default().build().unwrap()
+    }
+}
+
+impl<T: Play> Display for MctsBot<T> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(fmt, "MctsBot")
+    }
+}
+
+impl<T: Play> MctsBot<T> {
+    /// The most search iterations run per action request
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+
+    /// The most candidate actions considered for a player at each node
+    pub fn max_actions(&self) -> usize {
+        self.max_actions
+    }
+}
+
+impl<T: Play + EnumerateActions + Determinize> Bot for MctsBot<T> {
+    type Game = T;
+
+    fn on_action_request(
+        &mut self,
+        player_pov: &PlayerPov<'_, T>,
+        bot_context: &BotContext<'_, T>,
+    ) -> Result<T::Action, BotError<T>> {
+        let mut rng = bot_context.rng_for_turn();
+        let mut root = Node::default();
+        let mut best = T::random_legal_action(player_pov, &mut rng)
+            .ok_or_else(|| BotError::Custom("there are no legal actions".into()))?;
+
+        let perfect_information = T::is_perfect_information();
+        let mut game_state = T::determinize(player_pov, &mut rng);
+
+        for iteration in 0..self.iterations {
+            if !perfect_information && iteration > 0 {
+                game_state = T::determinize(player_pov, &mut rng);
+            }
+
+            self.search(&mut root, game_state.clone(), player_pov.settings, &mut rng);
+
+            if iteration % ITERATIONS_PER_CHECKPOINT == 0 {
+                if let Some(action) = root.most_visited(player_pov.player) {
+                    best = action.clone();
+                }
+
+                bot_context.checkpoint(&best)?;
+            }
+        }
+
+        Ok(root
+            .most_visited(player_pov.player)
+            .cloned()
+            .unwrap_or(best))
+    }
+
+    fn has_immutable_state(&self) -> bool {
+        true
+    }
+}
+
+impl<T: Play + EnumerateActions> MctsBot<T> {
+    /// Run a single search iteration from `node`, returning the reward for each player
+    fn search(
+        &self,
+        node: &mut Node<T>,
+        mut game_state: GameState<T>,
+        settings: &T::Settings,
+        rng: &mut impl rand::Rng,
+    ) -> PID<f64> {
+        let Some(acting) = game_state.action_requests.clone() else {
+            return rewards(&game_state);
+        };
+
+        if node.visits == 0 {
+            node.visits = 1;
+            node.arms = acting
+                .iter()
+                .map(|player| {
+                    (
+                        player,
+                        self.candidate_arms(&game_state, settings, player, rng),
+                    )
+                })
+                .collect();
+
+            return self.rollout(game_state, settings, rng);
+        }
+
+        let choices: PID<Option<usize>> = node
+            .arms
+            .iter()
+            .map(|(player, arms)| (player, self.select(arms, node.visits)))
+            .collect();
+
+        let actions: PID<ActionResponse<T>> = choices
+            .iter()
+            .map(|(player, choice)| {
+                let response = choice.map_or(ActionResponse::Timeout, |arm| {
+                    ActionResponse::Response(node.arms[player][arm].action.clone())
+                });
+
+                (player, response)
+            })
+            .collect();
+
+        advance(&mut game_state, settings, actions.clone(), rng);
+
+        let idx = node
+            .children
+            .iter()
+            .position(|(key, _)| key == &actions)
+            .unwrap_or_else(|| {
+                node.children.push((actions, Node::default()));
+                node.children.len() - 1
+            });
+        let child = &mut node.children[idx].1;
+
+        let rewards = self.search(child, game_state, settings, rng);
+
+        node.visits += 1;
+        for (player, choice) in choices.iter() {
+            if let Some(arm) = choice {
+                let arm = &mut node.arms[player][*arm];
+                arm.visits += 1;
+                arm.reward += rewards.get(player).copied().unwrap_or(0.5);
+            }
+        }
+
+        rewards
+    }
+
+    /// Pick an arm with UCB1, trying every arm once before revisiting any of them
+    fn select(&self, arms: &[Arm<T>], parent_visits: u32) -> Option<usize> {
+        let ln_parent_visits = f64::from(parent_visits).ln();
+
+        arms.iter()
+            .map(|arm| {
+                if arm.visits == 0 {
+                    f64::INFINITY
+                } else {
+                    let visits = f64::from(arm.visits);
+                    arm.reward / visits + self.exploration * (ln_parent_visits / visits).sqrt()
+                }
+            })
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(idx, _)| idx)
+    }
+
+    /// The actions a player will choose from at a node, sampled down to `max_actions`
+    fn candidate_arms(
+        &self,
+        game_state: &GameState<T>,
+        settings: &T::Settings,
+        player: Player,
+        rng: &mut impl rand::Rng,
+    ) -> Vec<Arm<T>> {
+        let legal_actions = T::legal_actions_for_game_state(game_state, settings, player);
+
+        let actions = match legal_actions.size_hint() {
+            (_, Some(upper)) if upper <= self.max_actions => legal_actions.collect(),
+            _ => legal_actions.choose_multiple(rng, self.max_actions),
+        };
+
+        actions
+            .into_iter()
+            .map(|action| Arm {
+                action,
+                visits: 0,
+                reward: 0.0,
+            })
+            .collect()
+    }
+
+    /// Play the game out with random legal actions
+    fn rollout(
+        &self,
+        mut game_state: GameState<T>,
+        settings: &T::Settings,
+        rng: &mut impl rand::Rng,
+    ) -> PID<f64> {
+        for _ in 0..self.max_rollout_turns {
+            let Some(acting) = game_state.action_requests.clone() else {
+                break;
+            };
+
+            let actions = acting
+                .iter()
+                .map(|player| {
+                    let pov = game_state.player_pov(settings, player);
+                    let response = T::random_legal_action(&pov, rng)
+                        .map_or(ActionResponse::Timeout, ActionResponse::Response);
+
+                    (player, response)
+                })
+                .collect();
+
+            advance(&mut game_state, settings, actions, rng);
+        }
+
+        rewards(&game_state)
+    }
+}
+
+#[derive(Debug)]
+struct Node<T: Play> {
+    visits: u32,
+    arms: PID<Vec<Arm<T>>>,
+    children: Vec<(PID<ActionResponse<T>>, Node<T>)>,
+}
+
+impl<T: Play> Default for Node<T> {
+    fn default() -> Self {
+        Self {
+            visits: 0,
+            arms: PID::default(),
+            children: Vec::new(),
+        }
+    }
+}
+
+impl<T: Play> Node<T> {
+    fn most_visited(&self, player: Player) -> Option<&T::Action> {
+        self.arms
+            .get(player)?
+            .iter()
+            .filter(|arm| arm.visits > 0)
+            .max_by_key(|arm| arm.visits)
+            .map(|arm| &arm.action)
+    }
+}
+
+#[derive(Debug)]
+struct Arm<T: Play> {
+    action: T::Action,
+    visits: u32,
+    reward: f64,
+}
@@ -0,0 +1,514 @@
+//! A general purpose minimax bot with alpha-beta pruning
+//!
+//! [`MinimaxBot`] plays any turn based, perfect information game implementing
+//! [`EnumerateActions`] and [`Determinize`]. It searches with iterative deepening, so it always
+//! has an answer ready when the [`BotContext`] runs out of time.
+//!
+//! Games too deep to search to the end need an [`Evaluate`] implementation that scores unfinished
+//! games, otherwise every unfinished position is treated as even.
+//!
+//! # Limitations
+//!
+//! * The search assumes one player acts per turn. Positions where several players act at once are
+//!   scored with [`Evaluate`] instead of searched
+//! * With more than two players every opponent is assumed to be playing against the bot
+//! * Hidden information games are searched as if the state sampled by [`Determinize::determinize`]
+//!   were the real one
+use super::search::{advance, rewards};
+use super::{Bot, BotContext, BotError};
+use crate::encoding::Encoding;
+use crate::play::{ActionResponse, Determinize, EnumerateActions, GameState, Play, Player};
+use crate::pov::player::PlayerPov;
+use bytes::Bytes;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use std::marker::PhantomData;
+use std::panic::RefUnwindSafe;
+
+/// The value of winning against every opponent on the current turn. Evaluations are clamped to
+/// `-1.0..=1.0` so they always rank between wins and losses
+const WIN: f64 = 1_000_000.0;
+
+/// How many positions to search between each check of the time budget
+const NODES_PER_TIME_CHECK: u64 = 256;
+
+/// Heuristic scoring of unfinished games for [`MinimaxBot`]
+///
+/// Values should be in `-1.0..=1.0`, where `1.0` means `player` is all but certain to win and
+/// `-1.0` means they're all but certain to lose. Values outside of that range are clamped.
+pub trait Evaluate<T: Play>:
+    Clone
+    + Debug
+    + Default
+    + PartialEq
+    + RefUnwindSafe
+    + Send
+    + Sync
+    + Serialize
+    + DeserializeOwned
+    + 'static
+{
+    /// How favorable the [`PublicInfo`](Play::PublicInfo) of an unfinished game is for `player`
+    fn evaluate(&self, public_info: &T::PublicInfo, settings: &T::Settings, player: Player) -> f64;
+}
+
+/// An [`Evaluate`] that considers every unfinished game to be even
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct NeutralEvaluation;
+
+impl<T: Play> Evaluate<T> for NeutralEvaluation {
+    fn evaluate(
+        &self,
+        _public_info: &T::PublicInfo,
+        _settings: &T::Settings,
+        _player: Player,
+    ) -> f64 {
+        0.0
+    }
+}
+
+/// A [`Bot`] that picks actions with depth limited minimax search
+///
+/// ```
+/// use lttcore::bot::{Bot, BotContext, minimax::{MinimaxBot, MinimaxBotBuilder, NeutralEvaluation}};
+/// use lttcore::examples::{TicTacToe, tic_tac_toe::{Position, Settings}};
+/// use lttcore::play::{ActionResponse::Response, Player, seed::SEED_42};
+/// use lttcore::pov::game_progression::GameProgression;
+///
+/// let mut game: GameProgression<TicTacToe> = GameProgression::from_settings(Settings);
+///
+/// // X can win by taking the last spot in the top row
+/// for position in [Position::new(0, 0), Position::new(1, 1), Position::new(1, 0), Position::new(2, 2)] {
+///     let player = game.which_players_input_needed().next().unwrap();
+///     let update = game.resolve([(player, Response(position.into()))].into_iter().collect());
+///     game.update(update);
+/// }
+///
+/// let mut bot: MinimaxBot<TicTacToe, NeutralEvaluation> = MinimaxBotBuilder::default().build().unwrap();
+/// let action = bot.on_action_request(&game.player_pov(Player::new(0)), &BotContext::from(&SEED_42)).unwrap();
+/// assert_eq!(action.position, Position::new(2, 0));
+/// ```
+#[derive(Builder, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[builder(derive(Debug), build_fn(validate = "Self::validate"))]
+#[serde(bound = "")]
+pub struct MinimaxBot<T: Play, E: Evaluate<T> = NeutralEvaluation> {
+    /// The deepest the iterative deepening search will go, in turns. Defaults to `9`
+    #[builder(default = "9")]
+    max_depth: u32,
+    /// Whether to remember already searched positions, defaults to `true`. This uses memory
+    /// proportional to the number of positions searched but saves a lot of work in games where
+    /// different move orders reach the same position
+    #[builder(default = "true")]
+    transposition_table: bool,
+    /// How unfinished games are scored when the search reaches `max_depth`
+    #[builder(default)]
+    evaluation: E,
+    #[builder(default, setter(skip))]
+    #[serde(skip)]
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Play, E: Evaluate<T>> MinimaxBotBuilder<T, E> {
+    fn validate(&self) -> Result<(), String> {
+        if self.max_depth == Some(0) {
+            return Err("max_depth must be greater than zero".into());
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: Play, E: Evaluate<T>> Default for MinimaxBot<T, E> {
+    fn default() -> Self {
+        MinimaxBotBuilder::default().build().unwrap()
+    }
+}
+
+impl<T: Play, E: Evaluate<T>> Display for MinimaxBot<T, E> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(fmt, "MinimaxBot")
+    }
+}
+
+impl<T: Play, E: Evaluate<T>> MinimaxBot<T, E> {
+    /// The deepest the search will go, in turns
+    pub fn max_depth(&self) -> u32 {
+        self.max_depth
+    }
+
+    /// The [`Evaluate`] used to score unfinished games
+    pub fn evaluation(&self) -> &E {
+        &self.evaluation
+    }
+}
+
+impl<T, E> Bot for MinimaxBot<T, E>
+where
+    T: Play + EnumerateActions + Determinize,
+    E: Evaluate<T>,
+{
+    type Game = T;
+
+    fn on_action_request(
+        &mut self,
+        player_pov: &PlayerPov<'_, T>,
+        bot_context: &BotContext<'_, T>,
+    ) -> Result<T::Action, BotError<T>> {
+        let mut rng = bot_context.rng_for_turn();
+        let game_state = T::determinize(player_pov, &mut rng);
+
+        if game_state
+            .action_requests
+            .as_ref()
+            .map(|acting| acting.count())
+            != Some(1)
+        {
+            return Err(BotError::Custom(
+                "minimax only supports turns with a single acting player".into(),
+            ));
+        }
+
+        let mut search = Search {
+            bot: self,
+            player: player_pov.player,
+            settings: player_pov.settings,
+            bot_context,
+            rng,
+            nodes: 0,
+            out_of_time: false,
+            reached_max_depth: false,
+            table: HashMap::new(),
+        };
+
+        let mut best = None;
+
+        for depth in 1..=self.max_depth {
+            search.reached_max_depth = false;
+
+            match search.root(&game_state, depth, best.as_ref()) {
+                Some((value, action)) => {
+                    best = Some(action);
+
+                    // Stop early if the game was searched to the end or the outcome is decided
+                    if !search.reached_max_depth || value.abs() > 1.0 {
+                        break;
+                    }
+                }
+                None => break,
+            }
+
+            if let Some(action) = &best {
+                bot_context.checkpoint(action)?;
+            }
+        }
+
+        match best {
+            Some(action) if search.out_of_time => Err(BotError::TimeExceeded(Some(action))),
+            Some(action) => Ok(action),
+            None if search.out_of_time => Err(BotError::TimeExceeded(None)),
+            None => Err(BotError::Custom("there are no legal actions".into())),
+        }
+    }
+
+    fn has_immutable_state(&self) -> bool {
+        true
+    }
+}
+
+/// Whether a transposition table value is exact or only a bound on the real value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+impl Bound {
+    fn new(value: f64, alpha: f64, beta: f64) -> Self {
+        if value <= alpha {
+            Bound::Upper
+        } else if value >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    depth: u32,
+    value: f64,
+    bound: Bound,
+    best: Option<usize>,
+}
+
+struct Search<'a, T: Play, E: Evaluate<T>, R: rand::Rng> {
+    bot: &'a MinimaxBot<T, E>,
+    player: Player,
+    settings: &'a T::Settings,
+    bot_context: &'a BotContext<'a, T>,
+    rng: R,
+    nodes: u64,
+    out_of_time: bool,
+    reached_max_depth: bool,
+    table: HashMap<Bytes, Entry>,
+}
+
+impl<T, E, R> Search<'_, T, E, R>
+where
+    T: Play + EnumerateActions,
+    E: Evaluate<T>,
+    R: rand::Rng,
+{
+    /// Search the root position to `depth`, returning its value and the best action. The best
+    /// action from the previous depth is searched first. Returns `None` if the search ran out of
+    /// time
+    fn root(
+        &mut self,
+        game_state: &GameState<T>,
+        depth: u32,
+        previous: Option<&T::Action>,
+    ) -> Option<(f64, T::Action)> {
+        let mut actions: Vec<T::Action> =
+            T::legal_actions_for_game_state(game_state, self.settings, self.player).collect();
+
+        if let Some(idx) = previous.and_then(|previous| actions.iter().position(|a| a == previous))
+        {
+            actions.swap(0, idx);
+        }
+
+        let mut alpha = -f64::INFINITY;
+        let mut best: Option<(f64, T::Action)> = None;
+
+        for action in actions {
+            let child = self.play(game_state, self.player, action.clone());
+            let value = self.alpha_beta(&child, depth - 1, 1, alpha, f64::INFINITY)?;
+
+            if best
+                .as_ref()
+                .is_none_or(|(best_value, _)| value > *best_value)
+            {
+                alpha = alpha.max(value);
+                best = Some((value, action));
+            }
+        }
+
+        best
+    }
+
+    /// Value of `game_state` for the bot's player, `None` if the search ran out of time
+    fn alpha_beta(
+        &mut self,
+        game_state: &GameState<T>,
+        depth: u32,
+        ply: u32,
+        mut alpha: f64,
+        mut beta: f64,
+    ) -> Option<f64> {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(NODES_PER_TIME_CHECK) && self.is_out_of_time() {
+            return None;
+        }
+
+        let Some(acting) = &game_state.action_requests else {
+            return Some(self.terminal_value(game_state, ply));
+        };
+
+        if acting.is_empty() {
+            // Nobody acts this turn but the game isn't over, so let it resolve
+            let mut game_state = game_state.clone();
+            advance(
+                &mut game_state,
+                self.settings,
+                Default::default(),
+                &mut self.rng,
+            );
+            return self.alpha_beta(&game_state, depth, ply, alpha, beta);
+        }
+
+        if depth == 0 || acting.count() != 1 {
+            self.reached_max_depth |= depth == 0;
+            let value =
+                self.bot
+                    .evaluation
+                    .evaluate(&game_state.public_info, self.settings, self.player);
+            return Some(value.clamp(-1.0, 1.0));
+        }
+
+        let key = self.key(game_state);
+        let (cutoff, first) = self.probe(key.as_ref(), depth, ply, &mut alpha, &mut beta);
+
+        if cutoff.is_some() {
+            return cutoff;
+        }
+
+        let (alpha_before, beta_before) = (alpha, beta);
+        let acting_player = acting.first().expect("there is one acting player");
+        let maximizing = acting_player == self.player;
+        let mut actions: Vec<T::Action> =
+            T::legal_actions_for_game_state(game_state, self.settings, acting_player).collect();
+
+        if let Some(first) = first.filter(|&first| first < actions.len()) {
+            actions.swap(0, first);
+        }
+
+        let mut best_value = if maximizing {
+            -f64::INFINITY
+        } else {
+            f64::INFINITY
+        };
+        let mut best_idx = None;
+
+        for (idx, action) in actions.into_iter().enumerate() {
+            let child = self.play(game_state, acting_player, action);
+            let value = self.alpha_beta(&child, depth - 1, ply + 1, alpha, beta)?;
+
+            let improved = if maximizing {
+                value > best_value
+            } else {
+                value < best_value
+            };
+
+            if improved {
+                best_value = value;
+                // Undo the swap so the index matches `legal_actions_for_game_state`
+                best_idx = Some(match first {
+                    Some(first) if idx == 0 => first,
+                    Some(first) if idx == first => 0,
+                    _ => idx,
+                });
+            }
+
+            if maximizing {
+                alpha = alpha.max(value);
+            } else {
+                beta = beta.min(value);
+            }
+
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        if best_idx.is_none() {
+            // No legal actions, let the game decide what happens when the player times out
+            let mut game_state = game_state.clone();
+            let actions = [(acting_player, ActionResponse::Timeout)]
+                .into_iter()
+                .collect();
+            advance(&mut game_state, self.settings, actions, &mut self.rng);
+            return self.alpha_beta(&game_state, depth - 1, ply + 1, alpha, beta);
+        }
+
+        if let Some(key) = key {
+            let entry = Entry {
+                depth,
+                value: to_table(best_value, ply),
+                bound: Bound::new(best_value, alpha_before, beta_before),
+                best: best_idx,
+            };
+            self.table.insert(key, entry);
+        }
+
+        Some(best_value)
+    }
+
+    /// Look up a position in the transposition table, narrowing `alpha` and `beta` with what's
+    /// known about it. Returns the position's value if no more searching is needed, along with the
+    /// index of the best action found last time
+    fn probe(
+        &mut self,
+        key: Option<&Bytes>,
+        depth: u32,
+        ply: u32,
+        alpha: &mut f64,
+        beta: &mut f64,
+    ) -> (Option<f64>, Option<usize>) {
+        let Some(entry) = key.and_then(|key| self.table.get(key)) else {
+            return (None, None);
+        };
+
+        let value = from_table(entry.value, ply);
+
+        if entry.depth >= depth {
+            // The entry can't tell us whether its search was cut short, so assume it was unless
+            // the outcome was decided
+            self.reached_max_depth |= value.abs() <= 1.0;
+
+            match entry.bound {
+                Bound::Exact => return (Some(value), entry.best),
+                Bound::Lower => *alpha = alpha.max(value),
+                Bound::Upper => *beta = beta.min(value),
+            }
+
+            if *alpha >= *beta {
+                return (Some(value), entry.best);
+            }
+        }
+
+        (None, entry.best)
+    }
+
+    fn play(
+        &mut self,
+        game_state: &GameState<T>,
+        player: Player,
+        action: T::Action,
+    ) -> GameState<T> {
+        let mut game_state = game_state.clone();
+        let actions = [(player, ActionResponse::Response(action))]
+            .into_iter()
+            .collect();
+        advance(&mut game_state, self.settings, actions, &mut self.rng);
+        game_state
+    }
+
+    /// Finished games are worth more than any evaluation, and sooner is better
+    fn terminal_value(&self, game_state: &GameState<T>, ply: u32) -> f64 {
+        let reward = rewards(game_state).get(self.player).copied().unwrap_or(0.0);
+        let value = (2.0 * reward - 1.0) * WIN;
+
+        if value == 0.0 {
+            value
+        } else {
+            value - value.signum() * f64::from(ply)
+        }
+    }
+
+    fn key(&self, game_state: &GameState<T>) -> Option<Bytes> {
+        if self.bot.transposition_table {
+            Encoding::Bincode.serialize(game_state).ok()
+        } else {
+            None
+        }
+    }
+
+    fn is_out_of_time(&mut self) -> bool {
+        self.out_of_time |= self
+            .bot_context
+            .time_remaining()
+            .is_some_and(|remaining| remaining.is_zero());
+
+        self.out_of_time
+    }
+}
+
+/// Decided game values are stored relative to the position they're found in, so the same entry
+/// can be reused at a different distance from the root
+fn to_table(value: f64, ply: u32) -> f64 {
+    if value.abs() > 1.0 {
+        value + value.signum() * f64::from(ply)
+    } else {
+        value
+    }
+}
+
+fn from_table(value: f64, ply: u32) -> f64 {
+    if value.abs() > 1.0 {
+        value - value.signum() * f64::from(ply)
+    } else {
+        value
+    }
+}
@@ -0,0 +1,49 @@
+//! Helpers shared by the search based bots, [`mcts`](super::mcts) and [`minimax`](super::minimax)
+use crate::play::{ActionResponse, GameState, Play, Score};
+use crate::utilities::PlayerIndexedData as PID;
+use std::borrow::Cow;
+
+/// Resolve `actions` and apply the update to `game_state`
+pub(super) fn advance<T: Play>(
+    game_state: &mut GameState<T>,
+    settings: &T::Settings,
+    actions: PID<ActionResponse<T>>,
+    rng: &mut impl rand::Rng,
+) {
+    let update = T::resolve(game_state, settings, Cow::Owned(actions), rng);
+    game_state.update(update);
+}
+
+/// Rank based rewards in `0.0..=1.0` for each player of a game. Players missing from the
+/// [`Score`] rank below everyone who has one
+pub(super) fn rewards<T: Play>(game_state: &GameState<T>) -> PID<f64> {
+    let players = || game_state.player_secret_info.players();
+    let opponents = game_state.player_secret_info.len().saturating_sub(1);
+
+    let Some(scores) = game_state.public_info.score().filter(|_| opponents > 0) else {
+        return players().map(|player| (player, 0.5)).collect();
+    };
+
+    let interpertation = <T::PublicInfo as Score>::score_interpertation();
+
+    players()
+        .map(|player| {
+            let beaten: f64 = players()
+                .filter(|&other| other != player)
+                .map(|other| match (scores.get(player), scores.get(other)) {
+                    (Some(a), Some(b)) => match interpertation.compare(*a, *b) {
+                        std::cmp::Ordering::Greater => 1.0,
+                        std::cmp::Ordering::Equal => 0.5,
+                        std::cmp::Ordering::Less => 0.0,
+                    },
+                    (Some(_), None) => 1.0,
+                    (None, Some(_)) => 0.0,
+                    (None, None) => 0.5,
+                })
+                .sum();
+
+            #[allow(clippy::cast_precision_loss)]
+            (player, beaten / opponents as f64)
+        })
+        .collect()
+}
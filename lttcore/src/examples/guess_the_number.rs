@@ -9,7 +9,11 @@ pub use public_info::{PublicInfo, PublicInfoUpdate};
 pub use settings::{Settings, SettingsBuilder, SettingsBuilderError};
 
 use crate::{
-    play::{view::NoSecretPlayerInfo, ActionResponse, GameState, GameStateUpdate, Play, View},
+    play::{
+        view::NoSecretPlayerInfo, ActionResponse, Determinize, EnumerateActions, GameState,
+        GameStateUpdate, Play, Player, View,
+    },
+    pov::player::PlayerPov,
     utilities::PlayerIndexedData as PID,
     LibTableTopIdentifier,
 };
@@ -58,6 +62,42 @@ impl View for GameSecretInfo {
     type Update = ();
 }
 
+impl EnumerateActions for GuessTheNumber {
+    fn legal_actions<'a>(
+        player_pov: &PlayerPov<'a, Self>,
+    ) -> impl Iterator<Item = Guess> + use<'a> {
+        // Keep the range exact sized so picking a random guess doesn't walk the whole range
+        let range = match player_pov.public_info {
+            PublicInfo::InProgress => player_pov.settings.range(),
+            PublicInfo::Completed { .. } => RangeInclusive::new(1, 0),
+        };
+
+        range.map(Guess)
+    }
+}
+
+impl Determinize for GuessTheNumber {
+    fn determinize(player_pov: &PlayerPov<'_, Self>, rng: &mut impl rand::Rng) -> GameState<Self> {
+        let settings = player_pov.settings;
+        let player_secret_info = settings
+            .number_of_players()
+            .player_indexed_data(|_player| NoSecretPlayerInfo);
+        let action_requests = match player_pov.public_info {
+            PublicInfo::InProgress => Some(settings.number_of_players().player_set()),
+            PublicInfo::Completed { .. } => None,
+        };
+
+        GameState {
+            player_secret_info,
+            public_info: player_pov.public_info.clone(),
+            game_secret_info: GameSecretInfo {
+                secret_number: rng.gen_range(settings.range()),
+            },
+            action_requests,
+        }
+    }
+}
+
 impl Play for GuessTheNumber {
     type Action = Guess;
     type ActionError = ActionError;
@@ -95,14 +135,10 @@ impl Play for GuessTheNumber {
             .as_ref()
             .iter()
             .filter_map(|(player, response)| {
-                if let Response(Guess(guess)) = response {
-                    (!settings.range().contains(guess)).then(|| {
-                        let err = GuessOutOfRange {
-                            guess: *guess,
-                            range: settings.range(),
-                        };
-                        (player, err)
-                    })
+                if let Response(guess) = response {
+                    Self::validate_action(game_state, settings, player, guess)
+                        .err()
+                        .map(|err| (player, err))
                 } else {
                     None
                 }
@@ -132,4 +168,20 @@ impl Play for GuessTheNumber {
             debug_msgs,
         }
     }
+
+    fn validate_action(
+        _game_state: &GameState<Self>,
+        settings: &Self::Settings,
+        _player: Player,
+        Guess(guess): &Guess,
+    ) -> Result<(), ActionError> {
+        if settings.range().contains(guess) {
+            Ok(())
+        } else {
+            Err(GuessOutOfRange {
+                guess: *guess,
+                range: settings.range(),
+            })
+        }
+    }
 }
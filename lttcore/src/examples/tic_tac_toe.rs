@@ -118,8 +118,9 @@ use crate::{
     play::{
         settings::NumPlayers,
         view::{NoSecretGameInfo, NoSecretGameInfoUpdate, NoSecretPlayerInfo},
-        ActionResponse, GameState, GameStateUpdate, Play, Player,
+        ActionResponse, Determinize, EnumerateActions, GameState, GameStateUpdate, Play, Player,
     },
+    pov::player::PlayerPov,
     utilities::{PlayerIndexedData as PID, PlayerSet},
     LibTableTopIdentifier,
 };
@@ -138,6 +139,48 @@ impl LibTableTopIdentifier for TicTacToe {
     }
 }
 
+impl EnumerateActions for TicTacToe {
+    fn legal_actions<'a>(
+        player_pov: &PlayerPov<'a, Self>,
+    ) -> impl Iterator<Item = Action> + use<'a> {
+        let is_their_turn = matches!(
+            player_pov.public_info.status(),
+            Status::InProgress { next_up } if Player::from(next_up) == player_pov.player
+        );
+
+        is_their_turn
+            .then(|| player_pov.public_info.board.empty_spaces())
+            .into_iter()
+            .flatten()
+            .map(Action::from)
+    }
+}
+
+impl Determinize for TicTacToe {
+    fn determinize(player_pov: &PlayerPov<'_, Self>, _rng: &mut impl rand::Rng) -> GameState<Self> {
+        let public_info = *player_pov.public_info;
+        let action_requests = match public_info.status() {
+            Status::InProgress { next_up } => Some(Player::from(next_up).into()),
+            Status::Win { .. } | Status::Draw | Status::WinByResignation { .. } => None,
+        };
+        let player_secret_info = player_pov
+            .settings
+            .number_of_players()
+            .player_indexed_data(|_player| NoSecretPlayerInfo);
+
+        GameState {
+            player_secret_info,
+            game_secret_info: NoSecretGameInfo,
+            public_info,
+            action_requests,
+        }
+    }
+
+    fn is_perfect_information() -> bool {
+        true
+    }
+}
+
 impl Play for TicTacToe {
     type Action = Action;
     type ActionError = ActionError;
@@ -168,7 +211,7 @@ impl Play for TicTacToe {
 
     fn resolve(
         game_state: &GameState<Self>,
-        _settings: &Self::Settings,
+        settings: &Self::Settings,
         actions: Cow<'_, PID<ActionResponse<Self>>>,
         _rng: &mut impl rand::Rng,
     ) -> GameStateUpdate<Self> {
@@ -195,16 +238,9 @@ impl Play for TicTacToe {
 
                 PublicInfoUpdate::Claim(marker, available)
             }
-            Response(Action { position }) => {
-                if game_state.public_info.board[*position].is_none() {
-                    PublicInfoUpdate::Claim(marker, *position)
-                } else {
-                    debug_msgs.insert(
-                        player,
-                        ActionError::SpaceIsTaken {
-                            attempted: *position,
-                        },
-                    );
+            Response(action) => {
+                if let Err(err) = Self::validate_action(game_state, settings, player, action) {
+                    debug_msgs.insert(player, err);
                     let available = game_state
                         .public_info
                         .board
@@ -213,6 +249,8 @@ impl Play for TicTacToe {
                         .expect("can't resolve a full board");
 
                     PublicInfoUpdate::Claim(marker, available)
+                } else {
+                    PublicInfoUpdate::Claim(marker, action.position)
                 }
             }
         };
@@ -243,4 +281,18 @@ impl Play for TicTacToe {
             game_secret_info_update: NoSecretGameInfoUpdate,
         }
     }
+
+    fn validate_action(
+        game_state: &GameState<Self>,
+        _settings: &Self::Settings,
+        _player: Player,
+        Action { position }: &Action,
+    ) -> Result<(), ActionError> {
+        match game_state.public_info.board[*position] {
+            None => Ok(()),
+            Some(_) => Err(ActionError::SpaceIsTaken {
+                attempted: *position,
+            }),
+        }
+    }
 }
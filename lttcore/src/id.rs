@@ -68,3 +68,4 @@ uuid_id!(UserId);
 uuid_id!(GameId);
 uuid_id!(SettingsId);
 uuid_id!(ScenarioId);
+uuid_id!(TokenId);
@@ -4,13 +4,19 @@ mod game_state;
 mod player;
 mod turn_num;
 
+pub mod determinize;
+pub mod enumerate_actions;
+pub mod migrate;
 pub mod number_of_players;
 pub mod score;
 pub mod seed;
 pub mod settings;
 pub mod view;
 
+pub use determinize::Determinize;
+pub use enumerate_actions::EnumerateActions;
 pub use game_state::{EnumeratedGameStateUpdate, GameState, GameStateUpdate};
+pub use migrate::{MigrationError, Stored};
 pub use number_of_players::NumberOfPlayers;
 pub use player::Player;
 pub use score::Score;
@@ -100,4 +106,37 @@ pub trait Play:
         actions: Cow<'_, PID<ActionResponse<Self>>>,
         rng: &mut impl rand::Rng,
     ) -> GameStateUpdate<Self>;
+
+    /// Check whether `player` may submit `action` against `game_state` before the turn is
+    /// resolved. This lets runtimes reject a bad action while the player still has time to retry.
+    ///
+    /// The default accepts every action, in which case any problems are only reported through
+    /// [`GameStateUpdate::debug_msgs`] after the turn resolves.
+    fn validate_action(
+        _game_state: &GameState<Self>,
+        _settings: &Self::Settings,
+        _player: Player,
+        _action: &Self::Action,
+    ) -> Result<(), Self::ActionError> {
+        Ok(())
+    }
+
+    /// The version of the serialized form of [`Play::Settings`] and [`Play::Action`]. Bump it when
+    /// a change to either stops previously stored values from deserializing, and upgrade the old
+    /// values in [`Play::migrate_settings`] and [`Play::migrate_action_response`].
+    const SCHEMA_VERSION: u32 = 0;
+
+    /// Upgrade settings stored by an older [`Play::SCHEMA_VERSION`]
+    ///
+    /// The default can't upgrade anything.
+    fn migrate_settings(stored: Stored<'_>) -> Result<Self::Settings, MigrationError> {
+        Err(MigrationError::unsupported::<Self>(stored.version))
+    }
+
+    /// Upgrade an action response stored by an older [`Play::SCHEMA_VERSION`]
+    ///
+    /// The default can't upgrade anything.
+    fn migrate_action_response(stored: Stored<'_>) -> Result<ActionResponse<Self>, MigrationError> {
+        Err(MigrationError::unsupported::<Self>(stored.version))
+    }
 }
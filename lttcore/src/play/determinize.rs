@@ -0,0 +1,34 @@
+//! Rebuilding a full [`GameState`] from what a single player can see
+use super::{GameState, Play};
+use crate::pov::player::PlayerPov;
+
+/// Optional companion to [`Play`] for games that can rebuild a plausible [`GameState`] from a
+/// [`PlayerPov`]
+///
+/// Search based bots need a full [`GameState`] to run [`Play::resolve`] against, but a bot only
+/// ever sees its own [`PlayerPov`]. For perfect information games the rebuilt state is exact. For
+/// hidden information games the parts the player can't see should be filled in at random, but
+/// consistently with everything the player *can* see (this is often called "determinization").
+///
+/// ```
+/// use lttcore::examples::{GuessTheNumber, guess_the_number::Settings};
+/// use lttcore::play::{Determinize, Player, seed::SEED_42};
+/// use lttcore::pov::game_progression::GameProgression;
+///
+/// let settings: Settings = (1..=10).try_into().unwrap();
+/// let game: GameProgression<GuessTheNumber> = GameProgression::from_settings(settings);
+///
+/// let sampled = GuessTheNumber::determinize(&game.player_pov(Player::new(0)), &mut SEED_42.rng_for_init());
+/// assert_eq!(&sampled.public_info, game.public_info());
+/// assert_eq!(sampled.action_requests, game.game_state().action_requests);
+/// ```
+pub trait Determinize: Play {
+    /// Sample a [`GameState`] consistent with the [`PlayerPov`]
+    fn determinize(player_pov: &PlayerPov<'_, Self>, rng: &mut impl rand::Rng) -> GameState<Self>;
+
+    /// Whether [`Determinize::determinize`] always rebuilds the true [`GameState`], defaults to
+    /// `false`. Bots can skip resampling the game state when this is `true`
+    fn is_perfect_information() -> bool {
+        false
+    }
+}
@@ -0,0 +1,51 @@
+//! Listing the legal actions of a [`Play`] compatible game
+use super::{GameState, Play, Player};
+use crate::pov::player::PlayerPov;
+use rand::seq::IteratorRandom;
+
+/// Optional companion to [`Play`] for games that can list the legal [`Action`](Play::Action)s
+/// available to a [`Player`]
+///
+/// Generic tooling (search bots, UIs, fuzzers, etc) uses this to know which moves are legal
+/// without understanding the rules of the game. A player who isn't being asked to act has no
+/// legal actions.
+///
+/// ```
+/// use lttcore::examples::{TicTacToe, tic_tac_toe::Settings};
+/// use lttcore::play::{EnumerateActions, Player};
+/// use lttcore::pov::game_progression::GameProgression;
+///
+/// let game: GameProgression<TicTacToe> = GameProgression::from_settings(Settings);
+///
+/// assert_eq!(TicTacToe::legal_actions(&game.player_pov(Player::new(0))).count(), 9);
+/// assert_eq!(TicTacToe::legal_actions(&game.player_pov(Player::new(1))).count(), 0);
+/// ```
+pub trait EnumerateActions: Play {
+    /// Iterate over the legal actions for the player described by the [`PlayerPov`]
+    fn legal_actions<'a>(
+        player_pov: &PlayerPov<'a, Self>,
+    ) -> impl Iterator<Item = Self::Action> + use<'a, Self>;
+
+    /// Iterate over the legal actions for a player given the full [`GameState`]
+    ///
+    /// By default this is the same as [`EnumerateActions::legal_actions`] for the player's
+    /// [`PlayerPov`]
+    fn legal_actions_for_game_state<'a>(
+        game_state: &'a GameState<Self>,
+        settings: &'a Self::Settings,
+        player: Player,
+    ) -> impl Iterator<Item = Self::Action> + 'a {
+        Self::legal_actions(&game_state.player_pov(settings, player))
+    }
+
+    /// Pick a legal action uniformly at random, returns [`None`] if there aren't any
+    ///
+    /// The default implementation is efficient as long as [`EnumerateActions::legal_actions`]
+    /// has an exact [`Iterator::size_hint`]
+    fn random_legal_action(
+        player_pov: &PlayerPov<'_, Self>,
+        rng: &mut impl rand::Rng,
+    ) -> Option<Self::Action> {
+        Self::legal_actions(player_pov).choose(rng)
+    }
+}
@@ -1,6 +1,9 @@
 use super::{ActionResponse, Play, Player, TurnNum, View};
 use crate::{
-    pov::{observer::ObserverUpdate, player::PlayerUpdate},
+    pov::{
+        observer::ObserverUpdate,
+        player::{PlayerPov, PlayerUpdate},
+    },
     utilities::{PlayerIndexedData as PID, PlayerSet},
 };
 use serde::{Deserialize, Serialize};
@@ -22,6 +25,16 @@ impl<T: Play> GameState<T> {
             .unwrap_or(false)
     }
 
+    /// What `player` can see of this game state
+    pub fn player_pov<'a>(&'a self, settings: &'a T::Settings, player: Player) -> PlayerPov<'a, T> {
+        PlayerPov {
+            player,
+            settings,
+            secret_info: &self.player_secret_info[player],
+            public_info: &self.public_info,
+        }
+    }
+
     pub fn update(&mut self, update: GameStateUpdate<T>) {
         for (player, update) in update.player_secret_info_updates {
             self.player_secret_info[player].update(Cow::Owned(update));
@@ -0,0 +1,130 @@
+//! Upgrading values stored by older versions of a game
+//!
+//! Stored [`Settings`](super::Play::Settings) and [`Action`](super::Play::Action)s are tagged with
+//! the [`Play::SCHEMA_VERSION`] of the game that wrote them. Values written by an older version
+//! are handed to [`Play::migrate_settings`] and [`Play::migrate_action_response`] when read.
+use super::{ActionResponse, Play};
+use crate::encoding::{Encoding, EncodingError};
+use bytes::Bytes;
+use serde::{de::DeserializeOwned, Deserialize};
+use thiserror::Error;
+
+/// A value as it was stored by an older [`Play::SCHEMA_VERSION`]
+#[derive(Debug, Clone, Copy)]
+pub struct Stored<'a> {
+    /// The [`Play::SCHEMA_VERSION`] the value was stored with
+    pub version: u32,
+    /// The [`Encoding`] of `bytes`
+    pub encoding: Encoding,
+    /// The encoded value
+    pub bytes: &'a Bytes,
+}
+
+/// Returned when a stored value can't be upgraded to the current [`Play::SCHEMA_VERSION`]
+#[derive(Error, Debug)]
+pub enum MigrationError {
+    /// The game doesn't know how to upgrade values from `version`
+    #[error("no migration from schema version {} to {}", version, current)]
+    UnsupportedVersion {
+        /// The version the value was stored with
+        version: u32,
+        /// The current [`Play::SCHEMA_VERSION`] of the game
+        current: u32,
+    },
+    /// The stored bytes don't decode as the old version of the value
+    #[error("stored value doesn't decode: {:?}", _0)]
+    Decoding(EncodingError),
+    /// The old value decoded but can't be represented in the current version
+    #[error("{}", _0)]
+    Invalid(String),
+}
+
+impl MigrationError {
+    /// A [`MigrationError::UnsupportedVersion`] for the current version of `T`
+    pub fn unsupported<T: Play>(version: u32) -> Self {
+        Self::UnsupportedVersion {
+            version,
+            current: T::SCHEMA_VERSION,
+        }
+    }
+}
+
+/// Mirrors the serialized form of [`ActionResponse`] with an arbitrary action type
+#[derive(Deserialize)]
+enum StoredActionResponse<A> {
+    Response(A),
+    Timeout,
+    Resign,
+}
+
+impl Stored<'_> {
+    /// Decode the value as `V`, typically the old definition of the stored type
+    pub fn decode<V: DeserializeOwned>(&self) -> Result<V, MigrationError> {
+        self.encoding
+            .deserialize(self.bytes)
+            .map_err(MigrationError::Decoding)
+    }
+
+    /// Decode an [`ActionResponse`] whose action was stored as an `A`, upgrading the action with
+    /// `migrate`
+    pub fn decode_action_response<T: Play, A: DeserializeOwned>(
+        &self,
+        migrate: impl FnOnce(A) -> Result<T::Action, MigrationError>,
+    ) -> Result<ActionResponse<T>, MigrationError> {
+        match self.decode::<StoredActionResponse<A>>()? {
+            StoredActionResponse::Response(action) => migrate(action).map(ActionResponse::Response),
+            StoredActionResponse::Timeout => Ok(ActionResponse::Timeout),
+            StoredActionResponse::Resign => Ok(ActionResponse::Resign),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examples::{guess_the_number::Guess, GuessTheNumber};
+    use serde::Serialize;
+
+    #[derive(Serialize, Deserialize)]
+    struct OldGuess {
+        guess: u64,
+    }
+
+    #[derive(Serialize)]
+    enum OldActionResponse {
+        Response(OldGuess),
+        Timeout,
+        Resign,
+    }
+
+    #[test]
+    fn test_decoding_old_action_responses() {
+        for encoding in [Encoding::Bincode, Encoding::Json] {
+            let decode = |old: OldActionResponse| {
+                let bytes = encoding.serialize(&old).unwrap();
+                let stored = Stored {
+                    version: 0,
+                    encoding,
+                    bytes: &bytes,
+                };
+                stored.decode_action_response::<GuessTheNumber, OldGuess>(|old| {
+                    u32::try_from(old.guess)
+                        .map(Guess)
+                        .map_err(|err| MigrationError::Invalid(err.to_string()))
+                })
+            };
+
+            let guess = decode(OldActionResponse::Response(OldGuess { guess: 7 }));
+            assert_eq!(guess.unwrap(), ActionResponse::Response(Guess(7)));
+
+            let timeout = decode(OldActionResponse::Timeout);
+            assert_eq!(timeout.unwrap(), ActionResponse::Timeout);
+
+            let resign = decode(OldActionResponse::Resign);
+            assert_eq!(resign.unwrap(), ActionResponse::Resign);
+
+            let too_big = decode(OldActionResponse::Response(OldGuess { guess: u64::MAX }));
+            assert!(matches!(too_big, Err(MigrationError::Invalid(_))));
+        }
+    }
+}
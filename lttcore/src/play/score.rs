@@ -1,5 +1,6 @@
 //! Functionality around machine readable scoring
 use crate::utilities::PlayerIndexedData as PID;
+use std::cmp::Ordering;
 
 /// Whether automated tooling should interpret a high score as being more favorable than a low
 /// score or vice versa. Defaults to `ScoreInterpertation::HigherIsBetter`
@@ -17,6 +18,26 @@ pub enum ScoreInterpertation {
     LowerIsBetter,
 }
 
+impl ScoreInterpertation {
+    /// Compare two scores by how favorable they are, [`Ordering::Greater`] means `a` is the better
+    /// score
+    ///
+    /// ```
+    /// use lttcore::play::score::ScoreInterpertation::*;
+    /// use std::cmp::Ordering::*;
+    ///
+    /// assert_eq!(HigherIsBetter.compare(2, 1), Greater);
+    /// assert_eq!(LowerIsBetter.compare(2, 1), Less);
+    /// assert_eq!(LowerIsBetter.compare(1, 1), Equal);
+    /// ```
+    pub fn compare(&self, a: i64, b: i64) -> Ordering {
+        match self {
+            ScoreInterpertation::HigherIsBetter => a.cmp(&b),
+            ScoreInterpertation::LowerIsBetter => b.cmp(&a),
+        }
+    }
+}
+
 /// Machine readable scores
 pub trait Score {
     /// See documentation for [`ScoreInterpertation`], defaults to [`ScoreInterpertation::HigherIsBetter`]
@@ -2,10 +2,15 @@
 mod support_from_settings;
 mod support_getters;
 mod support_povs;
+mod support_replay;
+mod support_rewind;
 mod support_scenarios;
 
+pub use support_replay::ReplayDivergence;
+pub use support_rewind::TurnNotInHistory;
+
 use crate::play::{
-    ActionResponse, EnumeratedGameStateUpdate, GameState, Play, Seed, SettingsPtr, TurnNum,
+    ActionResponse, EnumeratedGameStateUpdate, GameState, Play, Player, Seed, SettingsPtr, TurnNum,
 };
 use crate::utilities::PlayerIndexedData as PID;
 use im::Vector;
@@ -34,6 +39,16 @@ pub struct HistoryEvent<T: Play> {
     actions: PID<ActionResponse<T>>,
 }
 
+impl<T: Play> HistoryEvent<T> {
+    pub fn turn_num(&self) -> TurnNum {
+        self.turn_num
+    }
+
+    pub fn actions(&self) -> &PID<ActionResponse<T>> {
+        &self.actions
+    }
+}
+
 impl<T: Play> GameProgression<T> {
     #[must_use = "resolve only figures out the update, but does not apply it"]
     pub fn resolve(&self, actions: PID<ActionResponse<T>>) -> EnumeratedGameStateUpdate<T> {
@@ -57,6 +72,15 @@ impl<T: Play> GameProgression<T> {
         }
     }
 
+    /// Check an action with [`Play::validate_action`] against the current game state
+    pub fn validate_action(
+        &self,
+        player: Player,
+        action: &T::Action,
+    ) -> Result<(), T::ActionError> {
+        T::validate_action(&self.game_state, &self.settings, player, action)
+    }
+
     pub fn update(&mut self, update: EnumeratedGameStateUpdate<T>) {
         debug_assert_eq!(
             self.turn_num,
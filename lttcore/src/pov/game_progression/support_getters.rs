@@ -1,5 +1,7 @@
-use crate::play::{settings::NumPlayers, GameState, NumberOfPlayers, Play, Player, TurnNum};
-use crate::pov::game_progression::GameProgression;
+use crate::play::{
+    settings::NumPlayers, GameState, NumberOfPlayers, Play, Player, Seed, SettingsPtr, TurnNum,
+};
+use crate::pov::game_progression::{GameProgression, HistoryEvent};
 
 impl<T: Play> GameProgression<T> {
     pub fn is_concluded(&self) -> bool {
@@ -18,6 +20,14 @@ impl<T: Play> GameProgression<T> {
         &self.settings
     }
 
+    pub fn settings_ptr(&self) -> &SettingsPtr<T::Settings> {
+        &self.settings
+    }
+
+    pub fn seed(&self) -> &Seed {
+        &self.seed
+    }
+
     pub fn public_info(&self) -> &T::PublicInfo {
         &self.game_state.public_info
     }
@@ -34,6 +44,10 @@ impl<T: Play> GameProgression<T> {
         self.number_of_players().players()
     }
 
+    pub fn history(&self) -> impl Iterator<Item = &HistoryEvent<T>> + '_ {
+        self.history.iter()
+    }
+
     pub fn which_players_input_needed(&self) -> impl Iterator<Item = Player> + '_ {
         self.game_state
             .action_requests
@@ -0,0 +1,116 @@
+use super::GameProgression;
+use crate::play::{GameState, Play, TurnNum};
+use crate::utilities::PlayerSet;
+use thiserror::Error;
+
+/// The first place where replaying a [`GameProgression`] disagrees with what was recorded
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ReplayDivergence {
+    /// The players asked to act on `turn_num` don't match the players who have recorded actions
+    #[error(
+        "turn {:?} requested actions from {:?} but actions were recorded for {:?}",
+        turn_num,
+        replayed,
+        recorded
+    )]
+    ActionRequests {
+        /// The turn that diverged
+        turn_num: TurnNum,
+        /// The players with recorded actions for the turn
+        recorded: PlayerSet,
+        /// The players the replayed game asked to act
+        replayed: Option<PlayerSet>,
+    },
+    /// The [`GameState`] at the start of `turn_num` doesn't match the recorded one
+    #[error("the game state at the start of turn {:?} does not match", turn_num)]
+    GameState {
+        /// The turn that diverged
+        turn_num: TurnNum,
+    },
+}
+
+impl ReplayDivergence {
+    /// The first [`TurnNum`] that diverged
+    pub fn turn_num(&self) -> TurnNum {
+        match self {
+            Self::ActionRequests { turn_num, .. } | Self::GameState { turn_num } => *turn_num,
+        }
+    }
+}
+
+impl<T: Play> GameProgression<T> {
+    /// Rebuild the game from its seed, settings and recorded actions, checking that every turn
+    /// asks the same players to act and that the replay ends on the recorded [`GameState`].
+    ///
+    /// This is useful for detecting archived games that no longer replay the same way after a
+    /// change to [`Play::resolve`] or [`Play::initial_state_for_settings`].
+    ///
+    /// Only the final [`GameState`] is recorded in the [`GameProgression`], so a change that
+    /// alters the state without changing who acts is reported at the latest turn rather than the
+    /// turn it started at. Use [`GameProgression::verify_replay_against`] with the game states
+    /// of every turn to find that turn.
+    ///
+    /// ```
+    /// use lttcore::examples::{GuessTheNumber, guess_the_number::{Guess, Settings}};
+    /// use lttcore::play::{ActionResponse::Response, seed::SEED_42};
+    /// use lttcore::pov::game_progression::GameProgression;
+    ///
+    /// let mut game: GameProgression<GuessTheNumber> = GameProgression::from_settings_and_seed(Settings::default(), SEED_42);
+    /// let actions = game.which_players_input_needed().map(|player| (player, Response(Guess(42)))).collect();
+    /// let update = game.resolve(actions);
+    /// game.update(update);
+    ///
+    /// assert_eq!(game.verify_replay_final_state(), Ok(()));
+    /// ```
+    pub fn verify_replay_final_state(&self) -> Result<(), ReplayDivergence> {
+        self.verify_replay_against(std::iter::empty())
+    }
+
+    /// Like [`GameProgression::verify_replay_final_state`] but also checks the replay against intermediate
+    /// [`GameState`]s recorded outside of the [`GameProgression`].
+    ///
+    /// `recorded_game_states` yields the game state at the start of each turn beginning from
+    /// [`GameProgression::starting_turn_num`]. It may stop early, but must not have gaps.
+    pub fn verify_replay_against<'a>(
+        &self,
+        recorded_game_states: impl IntoIterator<Item = &'a GameState<T>>,
+    ) -> Result<(), ReplayDivergence> {
+        let mut recorded_game_states = recorded_game_states.into_iter();
+        let mut game = self.restart();
+
+        for event in &self.history {
+            if let Some(recorded) = recorded_game_states.next() {
+                if recorded != &game.game_state {
+                    return Err(ReplayDivergence::GameState {
+                        turn_num: game.turn_num,
+                    });
+                }
+            }
+
+            let recorded: PlayerSet = event.actions.players().collect();
+
+            if game.game_state.action_requests.as_ref() != Some(&recorded) {
+                return Err(ReplayDivergence::ActionRequests {
+                    turn_num: event.turn_num,
+                    recorded,
+                    replayed: game.game_state.action_requests.clone(),
+                });
+            }
+
+            let update = game.resolve(event.actions.clone());
+            game.update(update);
+        }
+
+        let final_matches = recorded_game_states
+            .next()
+            .is_none_or(|recorded| recorded == &game.game_state);
+
+        if final_matches && game.game_state == self.game_state {
+            Ok(())
+        } else {
+            Err(ReplayDivergence::GameState {
+                turn_num: game.turn_num,
+            })
+        }
+    }
+}
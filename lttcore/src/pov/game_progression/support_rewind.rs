@@ -0,0 +1,125 @@
+use super::{GameProgression, GameProgressionBuilder};
+use crate::play::{Play, TurnNum};
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Returned when asking a [`GameProgression`] about a [`TurnNum`] outside of its recorded history
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error(
+    "turn {:?} is outside of the recorded history ({:?}..={:?})",
+    attempted,
+    earliest,
+    latest
+)]
+pub struct TurnNotInHistory {
+    /// The turn that was asked for
+    pub attempted: TurnNum,
+    /// The earliest turn the [`GameProgression`] can be rewound to
+    pub earliest: TurnNum,
+    /// The current turn of the [`GameProgression`]
+    pub latest: TurnNum,
+}
+
+impl<T: Play> GameProgression<T> {
+    /// The [`TurnNum`] the [`GameProgression`] started from, before any of its history was applied
+    pub fn starting_turn_num(&self) -> TurnNum {
+        self.history
+            .front()
+            .map(|event| event.turn_num)
+            .unwrap_or(self.turn_num)
+    }
+
+    /// Rewind the [`GameProgression`] to the start of `turn_num` by replaying its history from
+    /// the initial game state. History after `turn_num` is discarded.
+    ///
+    /// ```
+    /// use lttcore::examples::{TicTacToe, tic_tac_toe::{Action, Position, Settings}};
+    /// use lttcore::play::{ActionResponse::Response, seed::SEED_42};
+    /// use lttcore::pov::game_progression::GameProgression;
+    ///
+    /// let mut game: GameProgression<TicTacToe> = GameProgression::from_settings_and_seed(Settings, SEED_42);
+    /// let fresh = game.clone();
+    ///
+    /// for position in [Position::new(0, 0), Position::new(1, 1), Position::new(2, 2)] {
+    ///     let player = game.which_players_input_needed().next().unwrap();
+    ///     let update = game.resolve([(player, Response(Action::from(position)))].into_iter().collect());
+    ///     game.update(update);
+    /// }
+    ///
+    /// game.rewind_to(0).unwrap();
+    /// assert_eq!(game, fresh);
+    /// ```
+    pub fn rewind_to(&mut self, turn_num: impl Into<TurnNum>) -> Result<(), TurnNotInHistory> {
+        *self = self.branch_at(turn_num)?;
+        Ok(())
+    }
+
+    /// Return an independent [`GameProgression`] as it was at the start of `turn_num`, leaving
+    /// `self` untouched. The branch can be played forward with different actions.
+    ///
+    /// ```
+    /// use lttcore::examples::{TicTacToe, tic_tac_toe::{Action, Marker, Position, Settings}};
+    /// use lttcore::play::{ActionResponse::Response, seed::SEED_42};
+    /// use lttcore::pov::game_progression::GameProgression;
+    ///
+    /// let mut game: GameProgression<TicTacToe> = GameProgression::from_settings_and_seed(Settings, SEED_42);
+    ///
+    /// for position in [Position::new(0, 0), Position::new(1, 1)] {
+    ///     let player = game.which_players_input_needed().next().unwrap();
+    ///     let update = game.resolve([(player, Response(Action::from(position)))].into_iter().collect());
+    ///     game.update(update);
+    /// }
+    ///
+    /// // What if the second player had taken the corner instead?
+    /// let mut branch = game.branch_at(1).unwrap();
+    /// let player = branch.which_players_input_needed().next().unwrap();
+    /// let update = branch.resolve([(player, Response(Action::from(Position::new(2, 2))))].into_iter().collect());
+    /// branch.update(update);
+    ///
+    /// assert_eq!(game.public_info().board.at((1, 1)), Ok(Some(Marker::O)));
+    /// assert_eq!(branch.public_info().board.at((1, 1)), Ok(None));
+    /// assert_eq!(branch.public_info().board.at((2, 2)), Ok(Some(Marker::O)));
+    /// ```
+    pub fn branch_at(&self, turn_num: impl Into<TurnNum>) -> Result<Self, TurnNotInHistory> {
+        let turn_num = turn_num.into();
+        let earliest = self.starting_turn_num();
+
+        if turn_num < earliest || turn_num > self.turn_num {
+            return Err(TurnNotInHistory {
+                attempted: turn_num,
+                earliest,
+                latest: self.turn_num,
+            });
+        }
+
+        let mut game = self.restart();
+
+        for event in self
+            .history
+            .iter()
+            .take_while(|event| event.turn_num < turn_num)
+        {
+            let update = game.resolve(event.actions.clone());
+            game.update(update);
+        }
+
+        Ok(game)
+    }
+
+    /// A copy of the [`GameProgression`] at its starting turn, before any history is applied
+    pub(super) fn restart(&self) -> Self {
+        let mut builder = GameProgressionBuilder::default();
+        builder
+            .seed(Arc::clone(&self.seed))
+            .settings(self.settings.clone())
+            .turn_num(self.starting_turn_num());
+
+        if let Some(initial_game_state) = &self.initial_game_state {
+            builder.initial_game_state(Arc::clone(initial_game_state));
+        }
+
+        builder
+            .build()
+            .expect("a game progression can always be rebuilt from its own parts")
+    }
+}
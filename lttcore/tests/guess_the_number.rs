@@ -1,16 +1,24 @@
+use lttcore::bot::{
+    mcts::{MctsBot, MctsBotBuilder},
+    Bot, BotContext, BotContextBuilder, BotError,
+};
 use lttcore::examples::GuessTheNumber;
 use lttcore::play::{
     number_of_players::{ONE_PLAYER, TWO_PLAYER},
     seed::SEED_42,
     view::NoSecretPlayerInfo,
     ActionResponse::*,
-    Player,
+    EnumerateActions, Player,
+};
+use lttcore::pov::{
+    game_progression::{GameProgression, ReplayDivergence},
+    player::GamePlayer,
 };
-use lttcore::pov::{game_progression::GameProgression, player::GamePlayer};
 use lttcore::{
     examples::guess_the_number::{ActionError::*, Guess, PublicInfo, Settings, SettingsBuilder},
     utilities::PlayerIndexedData as PID,
 };
+use std::time::Duration;
 
 #[test]
 fn test_building_default_settings() {
@@ -93,3 +101,94 @@ fn test_it_shows_the_player_the_correct_things() {
 
     game.update(update);
 }
+
+#[test]
+fn test_verifying_the_replay_of_a_game_progression() {
+    let mut game: GameProgression<GuessTheNumber> =
+        GameProgression::from_settings_and_seed(Settings::default(), SEED_42);
+
+    let actions = game
+        .which_players_input_needed()
+        .map(|player| (player, Response(Guess(5))))
+        .collect();
+    let update = game.resolve(actions);
+    game.update(update);
+
+    assert_eq!(game.verify_replay_final_state(), Ok(()));
+
+    // A game whose recorded outcome no longer matches what replaying produces
+    let mut tampered = serde_json::to_value(&game).unwrap();
+    tampered["seed"] = serde_json::json!(hex::encode([1u8; 32]));
+    let tampered: GameProgression<GuessTheNumber> = serde_json::from_value(tampered).unwrap();
+
+    assert_eq!(
+        tampered.verify_replay_final_state(),
+        Err(ReplayDivergence::GameState { turn_num: 1.into() })
+    );
+
+    // Intermediate game states can be checked too
+    let fresh = game.branch_at(0).unwrap();
+    assert_eq!(game.verify_replay_against([fresh.game_state()]), Ok(()));
+    assert_eq!(
+        game.verify_replay_against([game.game_state()])
+            .map_err(|divergence| divergence.turn_num()),
+        Err(0.into())
+    );
+}
+
+#[test]
+fn test_enumerating_legal_guesses() {
+    let settings = SettingsBuilder::default().range(1..=10).build().unwrap();
+    let mut game: GameProgression<GuessTheNumber> =
+        GameProgression::from_settings_and_seed(settings, SEED_42);
+
+    let guesses: Vec<Guess> = GuessTheNumber::legal_actions(&game.player_pov(0)).collect();
+    assert_eq!(guesses, (1..=10).map(Guess).collect::<Vec<_>>());
+
+    let mut rng = SEED_42.rng_for_init();
+    let guess = GuessTheNumber::random_legal_action(&game.player_pov(0), &mut rng).unwrap();
+    assert!((1..=10).contains(&guess.0));
+
+    let update = game.resolve([(Player::new(0), Response(guess))].into_iter().collect());
+    game.update(update);
+
+    assert!(game.is_concluded());
+    assert_eq!(
+        GuessTheNumber::legal_actions(&game.player_pov(0)).count(),
+        0
+    );
+    assert_eq!(
+        GuessTheNumber::random_legal_action(&game.player_pov(0), &mut rng),
+        None
+    );
+}
+
+#[test]
+fn test_mcts_bot_plays_guess_the_number_and_respects_time_budgets() {
+    let settings = SettingsBuilder::default().range(1..=100).build().unwrap();
+    let game: GameProgression<GuessTheNumber> =
+        GameProgression::from_settings_and_seed(settings, SEED_42);
+    let pov = game.player_pov(0);
+    let mut bot: MctsBot<GuessTheNumber> = MctsBotBuilder::default()
+        .iterations(200)
+        .max_actions(8)
+        .build()
+        .unwrap();
+
+    let Guess(guess) = bot
+        .on_action_request(&pov, &BotContext::from(&SEED_42))
+        .unwrap();
+    assert!((1..=100).contains(&guess));
+
+    let no_time = BotContextBuilder::default()
+        .seed(&SEED_42)
+        .turn_num(0)
+        .time_budget(Duration::ZERO)
+        .build()
+        .unwrap();
+
+    match bot.on_action_request(&pov, &no_time) {
+        Err(BotError::TimeExceeded(Some(Guess(guess)))) => assert!((1..=100).contains(&guess)),
+        other => panic!("expected the bot to run out of time, got {:?}", other),
+    }
+}
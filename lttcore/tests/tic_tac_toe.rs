@@ -1,8 +1,181 @@
-use lttcore::examples::TicTacToe;
-use lttcore::pov::game_progression::GameProgressionBuilder;
+use lttcore::bot::{
+    minimax::{MinimaxBot, MinimaxBotBuilder},
+    Bot, BotContextBuilder,
+};
+use lttcore::examples::{
+    tic_tac_toe::{Action, ActionError, Position, Settings, Status},
+    TicTacToe,
+};
+use lttcore::play::{seed::SEED_42, ActionResponse::Response, EnumerateActions, TurnNum};
+use lttcore::pov::game_progression::{GameProgression, GameProgressionBuilder, TurnNotInHistory};
 
 #[test]
 fn test_you_can_build_a_tic_tac_toe_game_progression() {
     let game_progression = GameProgressionBuilder::<TicTacToe>::default().build();
     assert!(game_progression.is_ok())
 }
+
+fn play(game: &mut GameProgression<TicTacToe>, positions: &[(usize, usize)]) {
+    for &(x, y) in positions {
+        let player = game.which_players_input_needed().next().unwrap();
+        let actions = [(player, Response(Action::from(Position::new(x, y))))]
+            .into_iter()
+            .collect();
+        let update = game.resolve(actions);
+        game.update(update);
+    }
+}
+
+#[test]
+fn test_rewinding_and_branching_a_game_progression() {
+    let mut game: GameProgression<TicTacToe> =
+        GameProgression::from_settings_and_seed(Settings, SEED_42);
+
+    let mut snapshots = vec![game.clone()];
+    for position in [(0, 0), (1, 1), (2, 2), (0, 2)] {
+        play(&mut game, &[position]);
+        snapshots.push(game.clone());
+    }
+
+    for (turn_num, snapshot) in snapshots.iter().enumerate() {
+        let branch = game.branch_at(turn_num as u64).unwrap();
+        assert_eq!(&branch, snapshot);
+    }
+
+    // Branches are independent of the original
+    let mut branch = game.branch_at(2).unwrap();
+    play(&mut branch, &[(2, 0)]);
+    assert_ne!(branch.public_info(), game.public_info());
+    assert_eq!(game, snapshots[4]);
+
+    assert_eq!(
+        game.branch_at(5),
+        Err(TurnNotInHistory {
+            attempted: TurnNum::from(5),
+            earliest: TurnNum::from(0),
+            latest: TurnNum::from(4),
+        })
+    );
+
+    game.rewind_to(1).unwrap();
+    assert_eq!(game, snapshots[1]);
+}
+
+#[test]
+fn test_branching_a_game_progression_started_from_a_scenario() {
+    let mut game: GameProgression<TicTacToe> =
+        GameProgression::from_settings_and_seed(Settings, SEED_42);
+    play(&mut game, &[(0, 0), (1, 1)]);
+
+    let mut from_scenario: GameProgression<TicTacToe> = game.scenario().into();
+    let start = from_scenario.clone();
+    play(&mut from_scenario, &[(2, 2)]);
+
+    assert_eq!(from_scenario.starting_turn_num(), TurnNum::from(2));
+    assert_eq!(from_scenario.branch_at(2).unwrap(), start);
+    assert!(from_scenario.branch_at(1).is_err());
+}
+
+#[test]
+fn test_replays_report_the_turn_they_diverged_at() {
+    let moves = [(0, 0), (1, 1), (2, 2), (0, 2), (2, 0)];
+    let mut game: GameProgression<TicTacToe> =
+        GameProgression::from_settings_and_seed(Settings, SEED_42);
+    play(&mut game, &moves);
+    assert_eq!(game.verify_replay_final_state(), Ok(()));
+
+    // A replay that went another way from turn 2 on first differs at the start of turn 3
+    let mut other = GameProgression::from_settings_and_seed(Settings, SEED_42);
+    let mut recorded = vec![other.game_state().clone()];
+    for position in [(0, 0), (1, 1), (1, 0), (0, 2), (2, 0)] {
+        play(&mut other, &[position]);
+        recorded.push(other.game_state().clone());
+    }
+
+    assert_eq!(
+        game.verify_replay_against(&recorded)
+            .map_err(|divergence| divergence.turn_num()),
+        Err(TurnNum::from(3))
+    );
+
+    let recorded: Vec<_> = (0..=moves.len() as u64)
+        .map(|turn_num| game.branch_at(turn_num).unwrap().game_state().clone())
+        .collect();
+    assert_eq!(game.verify_replay_against(&recorded), Ok(()));
+}
+
+#[test]
+fn test_random_legal_actions_are_always_accepted() {
+    let mut rng = SEED_42.rng_for_init();
+
+    for _ in 0..50 {
+        let mut game: GameProgression<TicTacToe> = GameProgression::from_settings(Settings);
+
+        while !game.is_concluded() {
+            let player = game.which_players_input_needed().next().unwrap();
+            let pov = game.player_pov(player);
+            let legal: Vec<Action> = TicTacToe::legal_actions(&pov).collect();
+            assert_eq!(legal.len(), game.public_info().board.empty_spaces().count());
+
+            let action = TicTacToe::random_legal_action(&pov, &mut rng).unwrap();
+            assert!(legal.contains(&action));
+
+            let position = action.position;
+            let update = game.resolve([(player, Response(action))].into_iter().collect());
+            game.update(update);
+            assert!(game.public_info().board[position].is_some());
+        }
+
+        for player in game.players() {
+            assert_eq!(
+                TicTacToe::legal_actions(&game.player_pov(player)).count(),
+                0
+            );
+        }
+    }
+}
+
+#[test]
+fn test_validating_actions_before_resolving() {
+    let mut game: GameProgression<TicTacToe> = GameProgression::from_settings(Settings);
+    play(&mut game, &[(1, 1)]);
+
+    let player = game.which_players_input_needed().next().unwrap();
+    let taken = Position::new(1, 1);
+
+    assert_eq!(
+        game.validate_action(player, &Action::from(taken)),
+        Err(ActionError::SpaceIsTaken { attempted: taken })
+    );
+    assert_eq!(
+        game.validate_action(player, &Action::from(Position::new(0, 0))),
+        Ok(())
+    );
+}
+
+#[test]
+fn test_minimax_bots_playing_each_other_draw() {
+    for transposition_table in [true, false] {
+        let mut bot: MinimaxBot<TicTacToe> = MinimaxBotBuilder::default()
+            .transposition_table(transposition_table)
+            .build()
+            .unwrap();
+        let mut game: GameProgression<TicTacToe> = GameProgression::from_settings(Settings);
+
+        while !game.is_concluded() {
+            let player = game.which_players_input_needed().next().unwrap();
+            let context = BotContextBuilder::default()
+                .seed(&SEED_42)
+                .turn_num(game.turn_num())
+                .build()
+                .unwrap();
+            let action = bot
+                .on_action_request(&game.player_pov(player), &context)
+                .unwrap();
+            let update = game.resolve([(player, Response(action))].into_iter().collect());
+            game.update(update);
+        }
+
+        assert_eq!(game.public_info().status(), Status::Draw);
+    }
+}
@@ -0,0 +1,147 @@
+use anyhow::{anyhow, Result};
+use chrono::{Duration, Utc};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use lttcore::encoding::Encoding;
+use lttcore::id::TokenId;
+use lttnetworking::auth::AccountStore;
+use lttstorage::accounts::AccountStorage;
+use lttstorage::db::sqlite_db::SqliteDB;
+use lttstorage::storage::StorageError;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Where the server and the account commands keep users and tokens unless told otherwise
+pub const DEFAULT_DATABASE: &str = "ltti.sqlite";
+
+pub fn sqlite_arg() -> Arg<'static, 'static> {
+    Arg::with_name("SQLITE")
+        .long("sqlite")
+        .takes_value(true)
+        .value_name("URL")
+        .help("SQLite database holding users and tokens (defaults to ltti.sqlite)")
+}
+
+pub async fn account_store(matches: &ArgMatches<'_>) -> Result<AccountStore<SqliteDB>> {
+    let url = matches.value_of("SQLITE").unwrap_or(DEFAULT_DATABASE);
+    // Accounts aren't encoded, so the encoding doesn't matter
+    let db = SqliteDB::connect(url, Encoding::Bincode)
+        .await
+        .map_err(storage_error)?;
+    Ok(AccountStore::new(Arc::new(db)))
+}
+
+pub fn subcommand() -> App<'static, 'static> {
+    let username = || {
+        Arg::with_name("USERNAME")
+            .required(true)
+            .help("Username of the user")
+    };
+
+    SubCommand::with_name("account")
+        .about("manages the users and tokens a lttserver accepts")
+        .arg(sqlite_arg())
+        .subcommand(
+            SubCommand::with_name("add-user")
+                .about("adds a user")
+                .arg(username()),
+        )
+        .subcommand(
+            SubCommand::with_name("issue-token")
+                .about("issues a token for a user and prints it")
+                .arg(username())
+                .arg(
+                    Arg::with_name("NAME")
+                        .short("n")
+                        .long("name")
+                        .takes_value(true)
+                        .help("Name to tell the token apart by (defaults to \"ltti\")"),
+                )
+                .arg(
+                    Arg::with_name("EXPIRES_IN")
+                        .long("expires-in")
+                        .takes_value(true)
+                        .value_name("DAYS")
+                        .help("Days until the token stops working (defaults to never)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("list-tokens")
+                .about("lists a user's tokens")
+                .arg(username()),
+        )
+        .subcommand(
+            SubCommand::with_name("revoke-token")
+                .about("stops a token from working")
+                .arg(
+                    Arg::with_name("TOKEN_ID")
+                        .required(true)
+                        .help("Id of the token, as printed by list-tokens"),
+                ),
+        )
+}
+
+pub async fn run(matches: &ArgMatches<'_>) -> Result<()> {
+    let accounts = account_store(matches).await?;
+
+    if let Some(matches) = matches.subcommand_matches("add-user") {
+        let username = matches.value_of("USERNAME").expect("USERNAME is required");
+        let user = accounts
+            .create_user(username)
+            .await
+            .map_err(storage_error)?;
+        println!("Added {} ({})", user.username, user.user_id);
+    }
+
+    if let Some(matches) = matches.subcommand_matches("issue-token") {
+        let user = read_user(&accounts, matches).await?;
+        let name = matches.value_of("NAME").unwrap_or("ltti");
+        let expires_at = matches
+            .value_of("EXPIRES_IN")
+            .map(|days| days.parse().map(|days| Utc::now() + Duration::days(days)))
+            .transpose()?;
+
+        let (id, token) = accounts
+            .issue_token(user.user_id, name, expires_at)
+            .await
+            .map_err(storage_error)?;
+        println!("Issued token {} for {}", id, user.username);
+        println!("{}", token);
+    }
+
+    if let Some(matches) = matches.subcommand_matches("list-tokens") {
+        let user = read_user(&accounts, matches).await?;
+        for token in accounts.tokens(user.user_id).await.map_err(storage_error)? {
+            let expires_at = token
+                .expires_at
+                .map_or_else(|| "never".to_owned(), |expires_at| expires_at.to_rfc3339());
+            println!("{} {} (expires {})", token.id, token.name, expires_at);
+        }
+    }
+
+    if let Some(matches) = matches.subcommand_matches("revoke-token") {
+        let id = matches.value_of("TOKEN_ID").expect("TOKEN_ID is required");
+        let id = Uuid::parse_str(id).map(TokenId::from)?;
+        accounts.revoke_token(id).await.map_err(storage_error)?;
+        println!("Revoked token {}", id);
+    }
+
+    Ok(())
+}
+
+async fn read_user(
+    accounts: &AccountStore<impl AccountStorage>,
+    matches: &ArgMatches<'_>,
+) -> Result<lttnetworking::User> {
+    let username = matches.value_of("USERNAME").expect("USERNAME is required");
+    accounts
+        .user_by_username(username)
+        .await
+        .map_err(|err| match err {
+            StorageError::NotFound => anyhow!("no user named {}", username),
+            err => storage_error(err),
+        })
+}
+
+fn storage_error(err: StorageError) -> anyhow::Error {
+    anyhow!("storage error: {:?}", err)
+}
@@ -0,0 +1,192 @@
+use anyhow::{anyhow, bail, Result};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use lttcore::encoding::Encoding;
+use lttcore::examples::GuessTheNumber;
+use lttcore::id::GameId;
+use lttnetworking::example_supported_games::ExampleSupportedGames as Games;
+use lttnetworking::SupportedGames;
+use lttstorage::archive::Archive;
+use lttstorage::db::{file_system_db::FileSystemDB, sqlite_db::SqliteDB};
+use lttstorage::query::GameQuery;
+use lttstorage::raw_storage::RawStorage;
+use lttstorage::storage::StorageError;
+use uuid::Uuid;
+
+pub fn subcommand() -> App<'static, 'static> {
+    let backend_args = || {
+        [
+            Arg::with_name("SQLITE")
+                .long("sqlite")
+                .takes_value(true)
+                .value_name("URL")
+                .required_unless("DIR")
+                .conflicts_with("DIR")
+                .help("Use the SQLite database at URL"),
+            Arg::with_name("DIR")
+                .long("dir")
+                .takes_value(true)
+                .value_name("PATH")
+                .help("Use the file system database under PATH"),
+            Arg::with_name("ENCODING")
+                .short("e")
+                .long("encoding")
+                .takes_value(true)
+                .possible_values(&["bincode", "json", "pretty-json"])
+                .help("Encoding the database is stored with"),
+        ]
+    };
+
+    SubCommand::with_name("archive")
+        .about("moves games between databases through portable archive files")
+        .subcommand(
+            SubCommand::with_name("export")
+                .about("writes games to an archive (defaults to every game in the database)")
+                .args(&backend_args())
+                .arg(
+                    Arg::with_name("GAME")
+                        .short("g")
+                        .long("game")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Id of a game to export"),
+                )
+                .arg(
+                    Arg::with_name("ARCHIVE")
+                        .required(true)
+                        .help("File to write the archive to"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("import")
+                .about("writes the games in an archive to a database (defaults to the archive's encoding)")
+                .args(&backend_args())
+                .arg(
+                    Arg::with_name("ARCHIVE")
+                        .required(true)
+                        .help("Archive file to read"),
+                ),
+        )
+}
+
+pub async fn run(matches: &ArgMatches<'_>) -> Result<()> {
+    if let Some(matches) = matches.subcommand_matches("export") {
+        let encoding = encoding(matches)?.unwrap_or(Encoding::Bincode);
+        let ids = matches
+            .values_of("GAME")
+            .into_iter()
+            .flatten()
+            .map(|id| Uuid::parse_str(id).map(GameId::from))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let archive = match backend(matches) {
+            Backend::Sqlite(url) => {
+                let db = SqliteDB::connect(url, encoding)
+                    .await
+                    .map_err(storage_error)?;
+                export(&db, ids).await?
+            }
+            Backend::Dir(path) => {
+                let db = FileSystemDB::open(path, encoding)
+                    .await
+                    .map_err(storage_error)?;
+                export(&db, ids).await?
+            }
+        };
+
+        let path = matches.value_of("ARCHIVE").expect("ARCHIVE is required");
+        tokio::fs::write(path, archive.to_bytes().map_err(storage_error)?).await?;
+        println!("Exported {} games to {}", archive.games.len(), path);
+    }
+
+    if let Some(matches) = matches.subcommand_matches("import") {
+        let path = matches.value_of("ARCHIVE").expect("ARCHIVE is required");
+        let archive =
+            Archive::from_bytes(&tokio::fs::read(path).await?.into()).map_err(storage_error)?;
+        let encoding = encoding(matches)?.unwrap_or(archive.encoding);
+        let archive = transcode(archive, encoding)?;
+
+        match backend(matches) {
+            Backend::Sqlite(url) => {
+                let db = SqliteDB::connect(url, encoding)
+                    .await
+                    .map_err(storage_error)?;
+                archive.import(&db).await
+            }
+            Backend::Dir(path) => {
+                let db = FileSystemDB::open(path, encoding)
+                    .await
+                    .map_err(storage_error)?;
+                archive.import(&db).await
+            }
+        }
+        .map_err(storage_error)?;
+        println!("Imported {} games from {}", archive.games.len(), path);
+    }
+
+    Ok(())
+}
+
+enum Backend<'a> {
+    Sqlite(&'a str),
+    Dir(&'a str),
+}
+
+fn backend<'a>(matches: &'a ArgMatches<'_>) -> Backend<'a> {
+    match matches.value_of("SQLITE") {
+        Some(url) => Backend::Sqlite(url),
+        None => Backend::Dir(matches.value_of("DIR").expect("SQLITE or DIR is required")),
+    }
+}
+
+fn encoding(matches: &ArgMatches<'_>) -> Result<Option<Encoding>> {
+    matches
+        .value_of("ENCODING")
+        .map(|encoding| match encoding {
+            "bincode" => Ok(Encoding::Bincode),
+            "json" => Ok(Encoding::Json),
+            "pretty-json" => Ok(Encoding::PrettyJson),
+            other => Err(anyhow!("unknown encoding {}", other)),
+        })
+        .transpose()
+}
+
+async fn export(db: &impl RawStorage, ids: Vec<GameId>) -> Result<Archive> {
+    if ids.is_empty() {
+        Archive::export_matching(db, &GameQuery::default()).await
+    } else {
+        Archive::export(db, ids).await
+    }
+    .map_err(storage_error)
+}
+
+/// Re-encode archives of the games ltti supports to the encoding of the database they're imported
+/// into
+fn transcode(archive: Archive, encoding: Encoding) -> Result<Archive> {
+    if archive.encoding == encoding {
+        return Ok(archive);
+    }
+
+    let game_types = archive.game_types();
+    let game_type = match game_types.iter().next() {
+        _ if game_types.len() > 1 => {
+            bail!("archives of more than one game type can't change encoding")
+        }
+        None => return Ok(Archive::new(encoding)),
+        Some(&game_type) => game_type,
+    };
+
+    match Games::try_from_str(game_type) {
+        Some(Games::GuessTheNumber) => archive
+            .transcode::<GuessTheNumber>(encoding)
+            .map_err(storage_error),
+        None => bail!(
+            "{} isn't a game ltti supports, so its archives can't change encoding",
+            game_type
+        ),
+    }
+}
+
+fn storage_error(err: StorageError) -> anyhow::Error {
+    anyhow!("storage error: {:?}", err)
+}
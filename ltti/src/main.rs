@@ -1,33 +1,20 @@
 #![allow(dead_code)]
 
+mod account;
+mod archive;
+
 use anyhow::Result;
-use async_trait::async_trait;
 use clap::{App, Arg, SubCommand};
-use lttcore::id::UserId;
-use lttnetworking::auth::Authenticate;
 use lttnetworking::example_supported_games::{
     ExampleSupportedGames as Games, ExampleSupportedGamesRuntimes as Runtimes,
 };
 use lttnetworking::messages::hello::ServerInfo;
 use lttnetworking::ws::client::run_jobs;
 use lttnetworking::ws::server::accept_connection;
-use lttnetworking::{Token, User};
 use std::sync::Arc;
 use tokio::net::TcpListener;
 use url::Url;
 
-struct Auth;
-
-#[async_trait]
-impl Authenticate for Auth {
-    async fn authenticate(&self, _token: &Token) -> Option<User> {
-        Some(User {
-            username: "GrantPowell".into(),
-            user_id: UserId::new(),
-        })
-    }
-}
-
 #[tokio::main]
 async fn main() -> Result<()> {
     let matches = App::new("LibTableTop Interactive (ltti)")
@@ -57,6 +44,8 @@ async fn main() -> Result<()> {
                         .about("Connects to server and prints version info"),
                 ),
         )
+        .subcommand(account::subcommand())
+        .subcommand(archive::subcommand())
         .subcommand(
             SubCommand::with_name("server")
                 .about("runs ltti as a lttserver")
@@ -67,7 +56,8 @@ async fn main() -> Result<()> {
                         .takes_value(true)
                         .value_name("PORT")
                         .help("Sets the port to start the server on"),
-                ),
+                )
+                .arg(account::sqlite_arg()),
         )
         .get_matches();
 
@@ -90,6 +80,14 @@ async fn main() -> Result<()> {
         };
     };
 
+    if let Some(matches) = matches.subcommand_matches("account") {
+        account::run(matches).await?;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("archive") {
+        archive::run(matches).await?;
+    }
+
     if let Some(matches) = matches.subcommand_matches("server") {
         let port = matches
             .value_of("PORT")
@@ -99,6 +97,7 @@ async fn main() -> Result<()> {
 
         println!("Starting server on port {}", port);
 
+        let accounts = account::account_store(matches).await?;
         let runtimes = Runtimes::init();
         let listener = TcpListener::bind(("localhost", port)).await?;
 
@@ -110,7 +109,7 @@ async fn main() -> Result<()> {
             println!("Accepted Connection {:?}", remote_addr);
 
             tokio::spawn(accept_connection::<Games, _>(
-                Auth,
+                accounts.clone(),
                 server_info.clone(),
                 runtimes.clone(),
                 stream,
@@ -1,7 +1,156 @@
 use crate::{Token, User};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use lttcore::id::{TokenId, UserId};
+use lttstorage::accounts::{AccountStorage, StoredToken, StoredUser, TokenHash};
+use lttstorage::storage::StorageError;
+use std::sync::Arc;
 
 #[async_trait]
 pub trait Authenticate: Send + Sync + 'static {
     async fn authenticate(&self, token: &Token) -> Option<User>;
 }
+
+/// [`Authenticate`]s against users and tokens kept in [`AccountStorage`]
+///
+/// Only the hash of each token is stored, so the [`Token`] returned by
+/// [`AccountStore::issue_token`] is the only time it's seen in full.
+pub struct AccountStore<S> {
+    storage: Arc<S>,
+}
+
+// Derived `Clone` would require `S: Clone`
+impl<S> Clone for AccountStore<S> {
+    fn clone(&self) -> Self {
+        Self {
+            storage: self.storage.clone(),
+        }
+    }
+}
+
+impl<S: AccountStorage> AccountStore<S> {
+    pub fn new(storage: Arc<S>) -> Self {
+        Self { storage }
+    }
+
+    pub async fn create_user(&self, username: impl Into<String>) -> Result<User, StorageError> {
+        let user = StoredUser {
+            user_id: UserId::new(),
+            username: username.into(),
+            created_at: Utc::now(),
+        };
+        self.storage.write_user(user.clone()).await?;
+
+        Ok(User {
+            username: user.username,
+            user_id: user.user_id,
+        })
+    }
+
+    pub async fn user_by_username(&self, username: &str) -> Result<User, StorageError> {
+        let user = self.storage.read_user_by_username(username).await?;
+        Ok(User {
+            username: user.username,
+            user_id: user.user_id,
+        })
+    }
+
+    /// Issue a new token for the user, which stops working at `expires_at` if given
+    pub async fn issue_token(
+        &self,
+        user_id: UserId,
+        name: impl Into<String>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(TokenId, Token), StorageError> {
+        let token = Token::random();
+        let id = TokenId::new();
+        self.storage
+            .write_token(StoredToken {
+                id,
+                hash: TokenHash::of(token.bytes()),
+                user_id,
+                name: name.into(),
+                created_at: Utc::now(),
+                expires_at,
+            })
+            .await?;
+
+        Ok((id, token))
+    }
+
+    pub async fn revoke_token(&self, id: TokenId) -> Result<(), StorageError> {
+        self.storage.delete_token(id).await
+    }
+
+    /// The user's tokens, without their secrets
+    pub async fn tokens(&self, user_id: UserId) -> Result<Vec<StoredToken>, StorageError> {
+        self.storage.list_tokens(user_id).await
+    }
+}
+
+#[async_trait]
+impl<S: AccountStorage> Authenticate for AccountStore<S> {
+    async fn authenticate(&self, token: &Token) -> Option<User> {
+        let stored = self
+            .storage
+            .read_token_by_hash(&TokenHash::of(token.bytes()))
+            .await;
+        let stored = found(stored, "token")?;
+        if stored.is_expired(Utc::now()) {
+            return None;
+        }
+
+        let user = found(self.storage.read_user(stored.user_id).await, "user")?;
+        Some(User {
+            username: user.username,
+            user_id: user.user_id,
+        })
+    }
+}
+
+/// The record if it was found, logging failures other than it not existing
+fn found<T>(result: Result<T, StorageError>, record: &str) -> Option<T> {
+    match result {
+        Ok(value) => Some(value),
+        Err(StorageError::NotFound) => None,
+        Err(err) => {
+            log::error!("Failed to read {} while authenticating: {:?}", record, err);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use lttcore::encoding::Encoding;
+    use lttstorage::db::hash_map_db::HashMapDB;
+
+    #[tokio::test]
+    async fn test_authenticating_tokens() {
+        let accounts = AccountStore::new(Arc::new(HashMapDB::new(Encoding::Json)));
+        let user = accounts.create_user("alice").await.unwrap();
+
+        let (_, token) = accounts
+            .issue_token(user.user_id, "laptop", None)
+            .await
+            .unwrap();
+        assert_eq!(accounts.authenticate(&token).await, Some(user.clone()));
+
+        let (_, expired) = accounts
+            .issue_token(user.user_id, "old", Some(Utc::now() - Duration::seconds(1)))
+            .await
+            .unwrap();
+        assert_eq!(accounts.authenticate(&expired).await, None);
+
+        let (id, revoked) = accounts
+            .issue_token(user.user_id, "lost", None)
+            .await
+            .unwrap();
+        accounts.revoke_token(id).await.unwrap();
+        assert_eq!(accounts.authenticate(&revoked).await, None);
+
+        assert_eq!(accounts.authenticate(&Token::random()).await, None);
+    }
+}
@@ -1,9 +1,22 @@
 pub use hex::{FromHex, FromHexError};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Token(#[serde(with = "hex")] [u8; 32]);
 
+impl Token {
+    /// A new, unguessable token
+    pub fn random() -> Self {
+        Self(rand::thread_rng().gen())
+    }
+
+    pub fn bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
 impl std::str::FromStr for Token {
     type Err = FromHexError;
 
@@ -11,3 +24,9 @@ impl std::str::FromStr for Token {
         <[u8; 32]>::from_hex(s).map(Token)
     }
 }
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
@@ -0,0 +1,58 @@
+use chrono::Utc;
+use lttcore::{id::GameId, play::Play, pov::game_progression::GameProgression};
+use lttstorage::storage::{MetaData, Storage, StorageError};
+use std::fmt;
+use std::sync::Arc;
+
+/// Saves a running game to storage so it can be recovered after a restart
+pub struct Checkpoint<T: Play> {
+    storage: Arc<dyn Storage<T>>,
+    game_id: GameId,
+    /// The game's stored metadata, kept as is apart from `updated_at`
+    meta: MetaData,
+}
+
+impl<T: Play> Checkpoint<T> {
+    pub fn new(storage: Arc<dyn Storage<T>>, game_id: GameId, meta: MetaData) -> Self {
+        Self {
+            storage,
+            game_id,
+            meta,
+        }
+    }
+
+    pub fn game_id(&self) -> GameId {
+        self.game_id
+    }
+
+    /// Write the whole game
+    pub async fn save(&self, game: &GameProgression<T>) -> Result<(), StorageError> {
+        let meta = MetaData {
+            updated_at: Utc::now(),
+            ..self.meta.clone()
+        };
+
+        self.storage
+            .write_game_progression((self.game_id, game.clone(), meta))
+            .await
+    }
+
+    /// Write the latest turn of the game, falling back to writing the whole game if the stored
+    /// history can't be appended to
+    pub async fn save_latest_turn(&self, game: &GameProgression<T>) -> Result<(), StorageError> {
+        match self.storage.append_latest_turn(self.game_id, game).await {
+            Ok(()) => Ok(()),
+            Err(StorageError::NotFound | StorageError::InvalidHistory(_)) => self.save(game).await,
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl<T: Play> fmt::Debug for Checkpoint<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Checkpoint")
+            .field("game_id", &self.game_id)
+            .field("meta", &self.meta)
+            .finish_non_exhaustive()
+    }
+}
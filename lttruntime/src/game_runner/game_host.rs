@@ -1,5 +1,8 @@
 use super::channels::{ToGameHostMsgReceiver, ToObserverMsgSender, ToPlayerMsgSender};
-use crate::messages::{ToGameHostMsg::*, ToObserverMsg, ToPlayerMsg};
+use super::checkpoint::Checkpoint;
+use crate::messages::{
+    SubmitActionErrorKind::InvalidAction, ToGameHostMsg::*, ToObserverMsg, ToPlayerMsg,
+};
 use lttcore::play::{ActionResponse, Play};
 use lttcore::pov::game_progression::GameProgression;
 use lttcore::utilities::{PlayerIndexedData as PID, PlayerItemCollector as PIC, PlayerSet};
@@ -9,7 +12,14 @@ pub async fn game_host<T: Play>(
     mut mailbox: ToGameHostMsgReceiver<T>,
     to_players: PID<ToPlayerMsgSender<T>>,
     to_observer: ToObserverMsgSender<T>,
+    checkpoint: Option<Checkpoint<T>>,
 ) -> GameProgression<T> {
+    if let Some(checkpoint) = &checkpoint {
+        if let Err(err) = checkpoint.save(&game).await {
+            log::error!("Failed to save game {:?}: {:?}", checkpoint.game_id(), err);
+        }
+    }
+
     while !game.is_concluded() {
         let mut returned_actions: PIC<ActionResponse<T>> = game
             .which_players_input_needed()
@@ -29,12 +39,24 @@ pub async fn game_host<T: Play>(
                     RequestPlayerState { player } => {
                         let game_player = game.game_player(player);
                         to_players[player]
-                            .send(ToPlayerMsg::SyncState(game_player))
+                            .send(ToPlayerMsg::SyncState(game_player, None))
                             .expect("player connections multiplexer is still alive");
                     }
                     SubmitActionResponse { player, response } => {
+                        if let ActionResponse::Response(action) = &response {
+                            if let Err(error) = game.validate_action(player, action) {
+                                let msg = ToPlayerMsg::SubmitActionError(InvalidAction {
+                                    turn_num: game.turn_num(),
+                                    error,
+                                });
+                                let _maybe_send_error = to_players[player].send(msg);
+                                continue;
+                            }
+                        }
+
                         returned_actions.add(player, response);
                     }
+                    Abandon => return game,
                 },
             }
         }
@@ -46,10 +68,26 @@ pub async fn game_host<T: Play>(
 
         for (player, to_player) in to_players.iter() {
             let player_update = update.player_update(player).into_owned();
-            let _maybe_send_error = to_player.send(ToPlayerMsg::Update(player_update));
+            let _maybe_send_error = to_player.send(ToPlayerMsg::Update(player_update, None));
         }
 
         game.update(update);
+
+        if let Some(checkpoint) = &checkpoint {
+            if let Err(err) = checkpoint.save_latest_turn(&game).await {
+                log::error!(
+                    "Failed to save turn {:?} of game {:?}: {:?}",
+                    game.turn_num(),
+                    checkpoint.game_id(),
+                    err
+                );
+            }
+        }
+    }
+
+    let _maybe_send_error = to_observer.send(ToObserverMsg::GameOver);
+    for (_player, to_player) in to_players.iter() {
+        let _maybe_send_error = to_player.send(ToPlayerMsg::GameOver);
     }
 
     game
@@ -58,12 +96,21 @@ pub async fn game_host<T: Play>(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::Utc;
+    use lttcore::encoding::Encoding;
     use lttcore::examples::{
-        guess_the_number::{Guess, Settings},
+        guess_the_number::{ActionError::GuessOutOfRange, Guess, Settings},
         GuessTheNumber,
     };
+    use lttcore::id::GameId;
+    use lttcore::id::UserId;
     use lttcore::play::ActionResponse::Response;
     use lttcore::utilities::PlayerIndexedData;
+    use lttstorage::{
+        db::hash_map_db::HashMapDB,
+        storage::{MetaData, Storage},
+    };
+    use std::sync::Arc;
     use tokio::sync::mpsc::unbounded_channel;
 
     #[tokio::test]
@@ -87,6 +134,7 @@ mod tests {
             mailbox,
             to_players,
             to_observer,
+            None,
         ));
 
         drop(to_mailbox);
@@ -120,6 +168,7 @@ mod tests {
             mailbox,
             to_players,
             to_observer,
+            None,
         ));
 
         assert_eq!(handle.await.unwrap(), game);
@@ -127,4 +176,98 @@ mod tests {
         // because that signals to the game to stop
         drop(to_mailbox);
     }
+
+    #[tokio::test]
+    async fn test_game_host_checkpoints_every_turn() {
+        let storage = Arc::new(HashMapDB::new(Encoding::Bincode));
+        let game_id = GameId::new();
+        let meta = MetaData {
+            owner: Some(UserId::new()),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let checkpoint = Checkpoint::new(storage.clone(), game_id, meta.clone());
+
+        let settings: Settings = (1..=10).try_into().unwrap();
+        let game: GameProgression<GuessTheNumber> = GameProgression::from_settings(settings);
+        let player = game.players().next().unwrap();
+
+        let (to_mailbox, mailbox) = unbounded_channel();
+        let (to_observer, _observer_mailbox) = unbounded_channel();
+        let (to_player, _player_mailbox) = unbounded_channel();
+        let to_players: PlayerIndexedData<_> = [(player, to_player)].into_iter().collect();
+
+        let handle = tokio::spawn(game_host::<GuessTheNumber>(
+            game,
+            mailbox,
+            to_players,
+            to_observer,
+            Some(checkpoint),
+        ));
+
+        to_mailbox
+            .send(SubmitActionResponse {
+                player,
+                response: Response(Guess(4)),
+            })
+            .unwrap();
+
+        let game = handle.await.unwrap();
+        let (_, stored, stored_meta) =
+            Storage::<GuessTheNumber>::read_game_progression(&*storage, game_id)
+                .await
+                .unwrap();
+        assert!(stored.is_concluded());
+        assert_eq!(stored, game);
+        assert_eq!(stored_meta.owner, meta.owner);
+        assert_eq!(stored_meta.created_at, meta.created_at);
+    }
+
+    #[tokio::test]
+    async fn test_game_host_rejects_invalid_actions_before_resolving() {
+        let settings: Settings = (1..=10).try_into().unwrap();
+        let game: GameProgression<GuessTheNumber> = GameProgression::from_settings(settings);
+        let player = game.players().next().unwrap();
+
+        let (to_mailbox, mailbox) = unbounded_channel();
+        let (to_observer, _observer_mailbox) = unbounded_channel();
+        let (to_player, mut player_mailbox) = unbounded_channel();
+        let to_players: PlayerIndexedData<_> = [(player, to_player)].into_iter().collect();
+
+        let handle = tokio::spawn(game_host::<GuessTheNumber>(
+            game.clone(),
+            mailbox,
+            to_players,
+            to_observer,
+            None,
+        ));
+
+        to_mailbox
+            .send(SubmitActionResponse {
+                player,
+                response: Response(Guess(42)),
+            })
+            .unwrap();
+
+        assert_eq!(
+            player_mailbox.recv().await,
+            Some(ToPlayerMsg::SubmitActionError(InvalidAction {
+                turn_num: game.turn_num(),
+                error: GuessOutOfRange {
+                    guess: 42,
+                    range: 1..=10
+                }
+            }))
+        );
+
+        to_mailbox
+            .send(SubmitActionResponse {
+                player,
+                response: Response(Guess(4)),
+            })
+            .unwrap();
+
+        let game = handle.await.unwrap();
+        assert!(game.is_concluded());
+    }
 }
@@ -2,17 +2,21 @@ use super::channels::{
     bytes_channels, AddConnectionSender, BytesReceiver, FromPlayerMsgWithConnectionIdSender,
 };
 use super::id::{ConnectionId, ConnectionIdSource};
+use super::lifecycle::{completion, CompletionSender, Connected, Connections, GameCompletion};
 use crate::error::GameNotFound;
 use crate::messages::FromPlayerMsg;
 use bytes::Bytes;
 use lttcore::play::{Play, Player};
+use lttcore::pov::game_progression::GameProgression;
 use lttcore::{encoding::Encoding, utilities::PlayerIndexedData as PID};
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub struct PlayerConnection<T: Play> {
     sender: FromPlayerMsgWithConnectionIdSender<T>,
     receiver: BytesReceiver,
     connection_id: ConnectionId,
+    _connected: Connected,
 }
 
 impl<T: Play> PlayerConnection<T> {
@@ -31,6 +35,7 @@ impl<T: Play> PlayerConnection<T> {
 pub struct ObserverConnection {
     receiver: BytesReceiver,
     connection_id: ConnectionId,
+    _connected: Connected,
 }
 
 impl ObserverConnection {
@@ -45,6 +50,9 @@ pub struct GameMeta<T: Play> {
     add_observer_connection_sender: AddConnectionSender,
     add_player_connections_senders: PID<AddConnectionSender>,
     player_inputs: PID<FromPlayerMsgWithConnectionIdSender<T>>,
+    connections: Connections,
+    completion_sender: CompletionSender<T>,
+    completion: GameCompletion<T>,
 }
 
 impl<T: Play> GameMeta<T> {
@@ -53,14 +61,34 @@ impl<T: Play> GameMeta<T> {
         add_player_connections_senders: PID<AddConnectionSender>,
         player_inputs: PID<FromPlayerMsgWithConnectionIdSender<T>>,
     ) -> Self {
+        let (completion_sender, completion) = completion();
+
         Self {
             add_observer_connection_sender,
             add_player_connections_senders,
             player_inputs,
             connection_id_source: Default::default(),
+            connections: Connections::new(),
+            completion_sender,
+            completion,
         }
     }
 
+    pub fn completion(&self) -> GameCompletion<T> {
+        self.completion.clone()
+    }
+
+    /// Resolve every [`GameCompletion`] of the game with the game it ended with
+    pub fn complete(self, game_progression: GameProgression<T>) {
+        let _maybe_nobody_waiting = self
+            .completion_sender
+            .send(Some(Arc::new(game_progression)));
+    }
+
+    pub fn connections(&self) -> &Connections {
+        &self.connections
+    }
+
     pub fn add_observer(&self, encoding: Encoding) -> ObserverConnection {
         let connection_id = self.connection_id_source.next();
         let (bytes_sender, bytes_receiver) = bytes_channels(encoding);
@@ -71,6 +99,7 @@ impl<T: Play> GameMeta<T> {
         ObserverConnection {
             receiver: bytes_receiver,
             connection_id,
+            _connected: self.connections.connect(),
         }
     }
 
@@ -88,6 +117,7 @@ impl<T: Play> GameMeta<T> {
             connection_id,
             sender,
             receiver: bytes_receiver,
+            _connected: self.connections.connect(),
         })
     }
 }
@@ -0,0 +1,98 @@
+use super::channels::ToGameHostMsgSender;
+use crate::messages::ToGameHostMsg;
+use lttcore::play::Play;
+use lttcore::pov::game_progression::GameProgression;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::sleep;
+
+/// How long games nobody is connected to keep running by default
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+type Completed<T> = Option<Arc<GameProgression<T>>>;
+
+pub type CompletionSender<T> = watch::Sender<Completed<T>>;
+
+/// Resolves with the game as it was when it stopped running, which is when it concluded or was
+/// abandoned. Check [`GameProgression::is_concluded`] to tell the two apart.
+#[derive(Debug, Clone)]
+pub struct GameCompletion<T: Play>(watch::Receiver<Completed<T>>);
+
+impl<T: Play> GameCompletion<T> {
+    /// Wait for the game to stop, returns `None` if the runtime shuts down first
+    pub async fn wait(mut self) -> Option<Arc<GameProgression<T>>> {
+        loop {
+            if let Some(game) = self.0.borrow().as_ref() {
+                return Some(Arc::clone(game));
+            }
+
+            self.0.changed().await.ok()?;
+        }
+    }
+}
+
+pub fn completion<T: Play>() -> (CompletionSender<T>, GameCompletion<T>) {
+    let (sender, receiver) = watch::channel(None);
+    (sender, GameCompletion(receiver))
+}
+
+/// Counts the connections to a game, every connection holds a [`Connected`] until it's dropped
+#[derive(Debug)]
+pub struct Connections(Arc<watch::Sender<usize>>);
+
+impl Connections {
+    pub fn new() -> Self {
+        let (sender, _receiver) = watch::channel(0);
+        Self(Arc::new(sender))
+    }
+
+    pub fn connect(&self) -> Connected {
+        self.0.send_modify(|count| *count += 1);
+        Connected(Arc::clone(&self.0))
+    }
+
+    /// Watch the number of connections, which stops changing once the game stopped running and
+    /// every connection to it was dropped
+    pub fn subscribe(&self) -> watch::Receiver<usize> {
+        self.0.subscribe()
+    }
+}
+
+#[derive(Debug)]
+pub struct Connected(Arc<watch::Sender<usize>>);
+
+impl Drop for Connected {
+    fn drop(&mut self) {
+        self.0.send_modify(|count| *count -= 1);
+    }
+}
+
+/// Tell the game host to stop once nobody has been connected to the game for `idle_timeout`
+///
+/// `connections` watches the game's [`Connections`]. Returns once the game is abandoned or stops
+/// running.
+pub async fn abandon_when_idle<T: Play>(
+    mut connections: watch::Receiver<usize>,
+    idle_timeout: Duration,
+    to_game_host: ToGameHostMsgSender<T>,
+) {
+    loop {
+        while *connections.borrow_and_update() > 0 {
+            tokio::select! {
+                changed = connections.changed() => if changed.is_err() { return },
+                _ = to_game_host.closed() => return,
+            }
+        }
+
+        // Start over whenever anyone connects before the game is abandoned
+        tokio::select! {
+            _ = sleep(idle_timeout) => {
+                let _maybe_stopped = to_game_host.send(ToGameHostMsg::Abandon);
+                return;
+            }
+            changed = connections.changed() => if changed.is_err() { return },
+            _ = to_game_host.closed() => return,
+        }
+    }
+}
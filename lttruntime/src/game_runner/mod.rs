@@ -1,35 +1,196 @@
 mod channels;
+mod checkpoint;
 mod game_host;
 mod game_meta;
 mod id;
+mod lifecycle;
 mod observer_connections;
 mod player_connections;
 
+use crate::time_control::{TimeControl, DEFAULT_TIME_CONTROL};
+use checkpoint::Checkpoint;
+use chrono::Utc;
 use dashmap::DashMap;
 use game_meta::GameMeta;
 pub use game_meta::{ObserverConnection, PlayerConnection};
+pub use lifecycle::{GameCompletion, DEFAULT_IDLE_TIMEOUT};
 use lttcore::encoding::Encoding;
 use lttcore::{
     id::GameId,
     play::{Play, Player},
     pov::game_progression::GameProgression,
 };
+use lttstorage::{
+    query::GameQuery,
+    storage::{MetaData, Storage, StorageError},
+};
+use std::fmt;
+use std::sync::Arc;
 use std::time::Duration;
 
-#[derive(Debug)]
+/// Runs games until they conclude or are abandoned, see [`GameRunner::with_idle_timeout`]
+///
+/// Games are forgotten as soon as they stop running, use [`GameRunner::completion`] to get the
+/// game they ended with.
 pub struct GameRunner<T: Play> {
-    games: DashMap<GameId, GameMeta<T>>,
+    games: Arc<DashMap<GameId, GameMeta<T>>>,
+    storage: Option<Arc<dyn Storage<T>>>,
+    idle_timeout: Option<Duration>,
+    time_control: TimeControl,
+}
+
+impl<T: Play> fmt::Debug for GameRunner<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GameRunner")
+            .field("games", &self.games)
+            .field("has_storage", &self.storage.is_some())
+            .field("idle_timeout", &self.idle_timeout)
+            .field("time_control", &self.time_control)
+            .finish()
+    }
+}
+
+impl<T: Play> Default for GameRunner<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<T: Play> GameRunner<T> {
     pub fn new() -> Self {
         Self {
             games: Default::default(),
+            storage: None,
+            idle_timeout: Some(DEFAULT_IDLE_TIMEOUT),
+            time_control: DEFAULT_TIME_CONTROL,
+        }
+    }
+
+    /// A game runner that saves every game to `storage` after each turn, see
+    /// [`GameRunner::recover_games`]
+    pub fn with_storage(storage: Arc<dyn Storage<T>>) -> Self {
+        Self {
+            storage: Some(storage),
+            ..Self::new()
         }
     }
 
+    /// Abandon games nobody has been connected to for `idle_timeout` instead of
+    /// [`DEFAULT_IDLE_TIMEOUT`], `None` to keep them running forever. Abandoned games stay in
+    /// storage, so [`GameRunner::recover_games`] picks them back up.
+    pub fn with_idle_timeout(mut self, idle_timeout: Option<Duration>) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Give players `time_control` to act in games spawned without one instead of
+    /// [`DEFAULT_TIME_CONTROL`], including recovered games
+    pub fn with_time_control(mut self, time_control: TimeControl) -> Self {
+        self.time_control = time_control;
+        self
+    }
+
+    /// The time control of games spawned without one
+    pub fn time_control(&self) -> TimeControl {
+        self.time_control
+    }
+
+    /// Re-spawn every unfinished game in storage under its original [`GameId`], so players can
+    /// reconnect after a restart. Games that are already running are left alone. Returns the
+    /// ids of the games that were re-spawned.
+    ///
+    /// Time controls aren't stored, so recovered games use [`GameRunner::time_control`] and every
+    /// player starts over with a full clock.
+    pub async fn recover_games(&self) -> Result<Vec<GameId>, StorageError> {
+        let storage = match &self.storage {
+            Some(storage) => Arc::clone(storage),
+            None => return Ok(Vec::new()),
+        };
+
+        // Collect every listing before spawning any games, since games concluding while
+        // listing would shift the pages after them
+        let mut game_ids = Vec::new();
+        let mut query = Some(GameQuery::default().concluded(false));
+        while let Some(page_query) = query {
+            let page = storage.list_game_progressions(&page_query).await?;
+            game_ids.extend(page.games.into_iter().map(|listing| listing.id));
+            query = page.next;
+        }
+
+        let mut recovered = Vec::new();
+        for game_id in game_ids {
+            if self.games.contains_key(&game_id) {
+                continue;
+            }
+
+            // Games only need their history since the latest snapshot to carry on, and writing
+            // them keeps the rest of the stored history
+            let (game_id, game_progression, meta) =
+                storage.read_game_progression_at(game_id, None).await?;
+            let checkpoint = Checkpoint::new(Arc::clone(&storage), game_id, meta);
+            self.spawn(
+                game_id,
+                game_progression,
+                Some(checkpoint),
+                self.time_control,
+            );
+            recovered.push(game_id);
+        }
+
+        Ok(recovered)
+    }
+
     pub fn spawn_game(&self, game_progression: GameProgression<T>) -> GameId {
+        let (game_id, _completion) = self.spawn_game_with_completion(game_progression);
+        game_id
+    }
+
+    /// Like [`GameRunner::spawn_game`], but also returns the [`GameCompletion`] of the game, which
+    /// can't be missed by the game stopping before [`GameRunner::completion`] is called
+    pub fn spawn_game_with_completion(
+        &self,
+        game_progression: GameProgression<T>,
+    ) -> (GameId, GameCompletion<T>) {
+        self.spawn_timed_game(game_progression, self.time_control)
+    }
+
+    /// Like [`GameRunner::spawn_game_with_completion`], with players getting `time_control` to act
+    pub fn spawn_timed_game(
+        &self,
+        game_progression: GameProgression<T>,
+        time_control: TimeControl,
+    ) -> (GameId, GameCompletion<T>) {
         let game_id = GameId::new();
+        let checkpoint = self.storage.as_ref().map(|storage| {
+            let meta = MetaData {
+                owner: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            };
+            Checkpoint::new(Arc::clone(storage), game_id, meta)
+        });
+
+        let completion = self.spawn(game_id, game_progression, checkpoint, time_control);
+        (game_id, completion)
+    }
+
+    /// Wait for a running game to stop, `None` if the game isn't running
+    pub fn completion(&self, game_id: GameId) -> Option<GameCompletion<T>> {
+        self.games.get(&game_id).map(|meta| meta.completion())
+    }
+
+    /// Whether the game is still running
+    pub fn is_running(&self, game_id: GameId) -> bool {
+        self.games.contains_key(&game_id)
+    }
+
+    fn spawn(
+        &self,
+        game_id: GameId,
+        game_progression: GameProgression<T>,
+        checkpoint: Option<Checkpoint<T>>,
+        time_control: TimeControl,
+    ) -> GameCompletion<T> {
         let (to_game_host_msg_sender, to_game_host_msg_receiver) = channels::to_game_host();
         let (to_observer_msg_sender, to_observer_msg_receiver) = channels::to_observer();
         let (add_observer_connection_sender, add_observer_connection_receiver) =
@@ -57,7 +218,7 @@ impl<T: Play> GameRunner<T> {
         for player in game_progression.players() {
             tokio::spawn(player_connections::player_connections::<T>(
                 player,
-                Duration::from_millis(1000),
+                time_control,
                 player_connections::Inbox {
                     from_player_msg_receiver: from_player_msg_receivers.remove(player).unwrap(),
                     to_player_msg_receiver: to_player_msg_receivers.remove(player).unwrap(),
@@ -71,23 +232,41 @@ impl<T: Play> GameRunner<T> {
             ));
         }
 
-        tokio::spawn(game_host::game_host(
-            game_progression,
-            to_game_host_msg_receiver,
-            to_player_msg_senders,
-            to_observer_msg_sender,
-        ));
-
-        self.games.insert(
-            game_id,
-            GameMeta::new(
-                add_observer_connection_sender,
-                add_player_connection_senders,
-                from_player_msg_senders,
-            ),
+        let meta = GameMeta::new(
+            add_observer_connection_sender,
+            add_player_connection_senders,
+            from_player_msg_senders,
         );
+        let completion = meta.completion();
 
-        game_id
+        if let Some(idle_timeout) = self.idle_timeout {
+            tokio::spawn(lifecycle::abandon_when_idle(
+                meta.connections().subscribe(),
+                idle_timeout,
+                to_game_host_msg_sender.clone(),
+            ));
+        }
+
+        // Insert before spawning the host so a game that stops right away is still removed
+        self.games.insert(game_id, meta);
+
+        let games = Arc::clone(&self.games);
+        tokio::spawn(async move {
+            let game_progression = game_host::game_host(
+                game_progression,
+                to_game_host_msg_receiver,
+                to_player_msg_senders,
+                to_observer_msg_sender,
+                checkpoint,
+            )
+            .await;
+
+            if let Some((_, meta)) = games.remove(&game_id) {
+                meta.complete(game_progression);
+            }
+        });
+
+        completion
     }
 
     pub fn observe_game(&self, game_id: GameId, encoding: Encoding) -> Option<ObserverConnection> {
@@ -107,3 +286,143 @@ impl<T: Play> GameRunner<T> {
             .and_then(|meta| meta.add_player(player, encoding))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::ToPlayerMsg;
+    use lttcore::examples::{guess_the_number::Guess, GuessTheNumber};
+    use lttcore::play::ActionResponse::Response;
+    use lttstorage::db::hash_map_db::HashMapDB;
+
+    #[tokio::test]
+    async fn test_recovering_unfinished_games_from_storage() {
+        let storage = Arc::new(HashMapDB::new(Encoding::Bincode));
+        let game_id = GameId::new();
+        let game: GameProgression<GuessTheNumber> =
+            GameProgression::from_settings(lttcore::play::SettingsPtr::default());
+        let meta = MetaData {
+            owner: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        storage
+            .write_game_progression((game_id, game.clone(), meta))
+            .await
+            .unwrap();
+
+        let game_runner: GameRunner<GuessTheNumber> = GameRunner::with_storage(storage);
+        let player = game.players().next().unwrap();
+        assert!(game_runner
+            .play_game(game_id, player, Encoding::Json)
+            .is_none());
+
+        assert_eq!(game_runner.recover_games().await.unwrap(), vec![game_id]);
+        assert!(game_runner
+            .play_game(game_id, player, Encoding::Json)
+            .is_some());
+
+        // Running games aren't spawned twice
+        assert_eq!(game_runner.recover_games().await.unwrap(), vec![]);
+    }
+
+    #[tokio::test]
+    async fn test_recovered_games_start_with_full_clocks() {
+        let storage = Arc::new(HashMapDB::new(Encoding::Bincode));
+        let game_id = GameId::new();
+        let game: GameProgression<GuessTheNumber> =
+            GameProgression::from_settings(lttcore::play::SettingsPtr::default());
+        let meta = MetaData {
+            owner: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        storage
+            .write_game_progression((game_id, game.clone(), meta))
+            .await
+            .unwrap();
+
+        let time_control = TimeControl::Fischer {
+            initial: Duration::from_secs(300),
+            increment: Duration::from_secs(5),
+        };
+        let game_runner: GameRunner<GuessTheNumber> =
+            GameRunner::with_storage(storage).with_time_control(time_control);
+        game_runner.recover_games().await.unwrap();
+
+        let player = game.players().next().unwrap();
+        let mut connection = game_runner
+            .play_game(game_id, player, Encoding::Json)
+            .unwrap();
+        let msg = connection.next_bytes().await.unwrap();
+        let decoded: ToPlayerMsg<GuessTheNumber> = Encoding::Json.deserialize(&msg).unwrap();
+        assert_eq!(
+            decoded,
+            ToPlayerMsg::SyncState(game.game_player(player), Some(time_control.clock()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_games_are_removed_once_they_conclude() {
+        let mut game: GameProgression<GuessTheNumber> =
+            GameProgression::from_settings(lttcore::play::SettingsPtr::default());
+        let actions = game
+            .which_players_input_needed()
+            .map(|player| (player, Response(Guess(1))))
+            .collect();
+        let update = game.resolve(actions);
+        game.update(update);
+
+        let game_runner: GameRunner<GuessTheNumber> = GameRunner::new();
+        let (game_id, completion) = game_runner.spawn_game_with_completion(game.clone());
+
+        assert_eq!(*completion.wait().await.unwrap(), game);
+        assert!(!game_runner.is_running(game_id));
+        assert!(game_runner.completion(game_id).is_none());
+        assert!(game_runner.observe_game(game_id, Encoding::Json).is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_idle_games_are_abandoned() {
+        let idle_timeout = Duration::from_secs(60);
+        let game_runner: GameRunner<GuessTheNumber> =
+            GameRunner::new().with_idle_timeout(Some(idle_timeout));
+        let game_id = game_runner.spawn_game(GameProgression::from_settings(
+            lttcore::play::SettingsPtr::default(),
+        ));
+        let completion = game_runner.completion(game_id).unwrap();
+
+        // Connected games keep running
+        let observer = game_runner.observe_game(game_id, Encoding::Json).unwrap();
+        tokio::time::sleep(idle_timeout * 2).await;
+        assert!(game_runner.is_running(game_id));
+
+        // Games are abandoned as soon as they have been idle for the timeout
+        drop(observer);
+        tokio::time::sleep(idle_timeout - Duration::from_millis(1)).await;
+        assert!(game_runner.is_running(game_id));
+        tokio::time::sleep(Duration::from_millis(2)).await;
+        let game = completion.wait().await.unwrap();
+        assert!(!game.is_concluded());
+        assert!(!game_runner.is_running(game_id));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_idle_checks_stop_with_their_game() {
+        let connections = lifecycle::Connections::new();
+        let _connected = connections.connect();
+        let (to_game_host, to_game_host_receiver) = channels::to_game_host::<GuessTheNumber>();
+        let idle_check = tokio::spawn(lifecycle::abandon_when_idle(
+            connections.subscribe(),
+            Duration::from_secs(60),
+            to_game_host,
+        ));
+
+        // The game host stopping ends the check, even while someone is still connected
+        drop(to_game_host_receiver);
+        tokio::time::timeout(Duration::from_secs(1), idle_check)
+            .await
+            .expect("the idle check stopped")
+            .unwrap();
+    }
+}
@@ -64,7 +64,13 @@ pub async fn observer_connections<T: Play>(
                     in_sync: false
                 })
             }
-            Some(msg) = inbox.to_observer_msg_receiver.recv() => {
+            // The game host stops sending once the game stops running
+            msg = inbox.to_observer_msg_receiver.recv() => {
+                 let msg = match msg {
+                     Some(msg) => msg,
+                     None => break,
+                 };
+
                  match msg {
                      SyncState(_) => {
                          state.send_to(&msg, |conn| {
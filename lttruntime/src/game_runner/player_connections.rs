@@ -9,12 +9,13 @@ use crate::messages::{
     ToGameHostMsg::*,
     ToPlayerMsg::{self, *},
 };
+use crate::time_control::{Clock, TimeControl};
 use lttcore::play::{ActionResponse, Play, Player, TurnNum};
 use serde::Serialize;
 use smallvec::SmallVec;
-use std::time::Duration;
 use tokio::select;
 use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio::time::Instant;
 
 #[derive(Debug)]
 struct Conn {
@@ -28,8 +29,16 @@ struct Conn {
 struct State {
     conns: SmallVec<[Conn; 1]>,
     awaiting_turn: Option<TurnNum>,
+    /// When the current turn times out, `None` if it's too far off to happen
+    deadline: Option<Instant>,
     player: Player,
-    timeout: Duration,
+    time_control: TimeControl,
+    /// The clock as of the start of the current turn, or the end of the last one
+    clock: Clock,
+    /// When the turn the player is acting on started
+    turn_started: Option<Instant>,
+    /// When the player submitted their action for the current turn
+    acted_at: Option<Instant>,
     timeout_tx: UnboundedSender<TurnNum>,
 }
 
@@ -54,6 +63,29 @@ impl State {
     fn are_all_in_sync(&self) -> bool {
         self.conns.iter().all(|conn| conn.in_sync)
     }
+
+    /// The player's clock right now, counting down while they're acting
+    fn current_clock(&self) -> Clock {
+        match self.turn_started {
+            Some(turn_started) => {
+                let elapsed = self.acted_at.unwrap_or_else(Instant::now) - turn_started;
+                self.clock.running(&self.time_control, elapsed)
+            }
+            None => self.clock,
+        }
+    }
+
+    /// Charge the player for the turn they acted on, once the game moves past it
+    fn settle_turn(&mut self) {
+        if let (Some(turn_started), Some(acted_at)) = (self.turn_started, self.acted_at) {
+            self.clock = self
+                .clock
+                .acted(&self.time_control, acted_at - turn_started);
+        }
+
+        self.turn_started = None;
+        self.acted_at = None;
+    }
 }
 
 pub struct Inbox<T: Play> {
@@ -68,7 +100,7 @@ pub struct Outbox<T: Play> {
 
 pub async fn player_connections<T: Play>(
     player: Player,
-    timeout: Duration,
+    time_control: TimeControl,
     mut inbox: Inbox<T>,
     outbox: Outbox<T>,
 ) -> anyhow::Result<()> {
@@ -76,9 +108,13 @@ pub async fn player_connections<T: Play>(
 
     let mut state = State {
         player,
-        timeout,
+        time_control,
+        clock: time_control.clock(),
+        turn_started: None,
+        acted_at: None,
         timeout_tx,
         awaiting_turn: None,
+        deadline: None,
         conns: Default::default(),
     };
 
@@ -100,9 +136,12 @@ pub async fn player_connections<T: Play>(
                 process_from_connection::<T>(msg, &mut state, &outbox)?;
             }
 
-            // Messages from the game host
-            Some(msg) = inbox.to_player_msg_receiver.recv() => {
-                let is_game_over = process_from_game_host(msg, &mut state)?;
+            // Messages from the game host, which stops sending once the game stops running
+            msg = inbox.to_player_msg_receiver.recv() => {
+                let is_game_over = match msg {
+                    Some(msg) => process_from_game_host(msg, &mut state, &outbox)?,
+                    None => true,
+                };
 
                 if is_game_over {
                     break
@@ -113,17 +152,7 @@ pub async fn player_connections<T: Play>(
             // Note: Since we hold a sender for this channel it will never return `None`
             // so this `select!` block will never yield to an `else` clause
             Some(turn_num) = timeout_rx.recv() => {
-                if state.awaiting_turn == Some(turn_num) {
-                    state.awaiting_turn = None;
-
-                    let msg: ToPlayerMsg<T> = SubmitActionError(Timeout { turn_num });
-                    state.send_to(&msg, |conn| conn.in_sync);
-
-                    outbox.to_game_host_msg_sender.send(SubmitActionResponse {
-                        player: state.player,
-                        response: ActionResponse::Timeout,
-                    })?;
-                }
+                process_timeout(turn_num, &mut state, &outbox)?;
             }
         }
     }
@@ -131,6 +160,29 @@ pub async fn player_connections<T: Play>(
     Ok(())
 }
 
+fn process_timeout<T: Play>(
+    turn_num: TurnNum,
+    state: &mut State,
+    outbox: &Outbox<T>,
+) -> anyhow::Result<()> {
+    if state.awaiting_turn == Some(turn_num) {
+        state.awaiting_turn = None;
+        state.clock = state.clock.timed_out(&state.time_control);
+        state.turn_started = None;
+        state.acted_at = None;
+
+        let msg: ToPlayerMsg<T> = SubmitActionError(Timeout { turn_num });
+        state.send_to(&msg, |conn| conn.in_sync);
+
+        outbox.to_game_host_msg_sender.send(SubmitActionResponse {
+            player: state.player,
+            response: ActionResponse::Timeout,
+        })?;
+    }
+
+    Ok(())
+}
+
 fn process_from_connection<T: Play>(
     (from, msg): (ConnectionId, FromPlayerMsg<T>),
     state: &mut State,
@@ -142,7 +194,10 @@ fn process_from_connection<T: Play>(
             state.send_to(&msg, |conn| std::mem::replace(&mut conn.primary, false));
 
             let msg: ToPlayerMsg<T> = SetPrimaryStatus(true);
-            state.send_to(&msg, |conn| conn.id == from);
+            state.send_to(&msg, |conn| {
+                conn.primary = conn.id == from;
+                conn.primary
+            });
         }
         SubmitAction { action, turn } => {
             let is_correct_turn = state.awaiting_turn == Some(turn);
@@ -155,6 +210,7 @@ fn process_from_connection<T: Play>(
                 })?;
 
                 state.awaiting_turn = None;
+                state.acted_at = Some(Instant::now());
             }
 
             if !is_connection_primary {
@@ -175,9 +231,14 @@ fn process_from_connection<T: Play>(
     Ok(())
 }
 
-fn process_from_game_host<T: Play>(msg: ToPlayerMsg<T>, state: &mut State) -> anyhow::Result<bool> {
+fn process_from_game_host<T: Play>(
+    msg: ToPlayerMsg<T>,
+    state: &mut State,
+    outbox: &Outbox<T>,
+) -> anyhow::Result<bool> {
     match msg {
-        SyncState(_) => {
+        SyncState(game_player, _) => {
+            let msg: ToPlayerMsg<T> = SyncState(game_player, Some(state.current_clock()));
             state.send_to(&msg, |conn| {
                 if conn.in_sync {
                     false
@@ -187,25 +248,48 @@ fn process_from_game_host<T: Play>(msg: ToPlayerMsg<T>, state: &mut State) -> an
                 }
             });
         }
-        Update(ref player_update) => {
+        Update(player_update, _) => {
+            state.settle_turn();
+
             if player_update.player_should_act() {
                 let turn_num = player_update.turn_num();
+                let now = Instant::now();
+                let timeout = state.clock.time_to_act(&state.time_control);
                 state.awaiting_turn = Some(turn_num);
-                let sender = state.timeout_tx.clone();
-                let timeout = state.timeout;
-
-                tokio::spawn(async move {
-                    tokio::time::sleep(timeout).await;
-                    let _ = sender.send(turn_num);
-                });
+                state.turn_started = Some(now);
+                state.deadline = now.checked_add(timeout);
+
+                if state.deadline.is_some() {
+                    let sender = state.timeout_tx.clone();
+                    tokio::spawn(async move {
+                        tokio::time::sleep(timeout).await;
+                        let _ = sender.send(turn_num);
+                    });
+                }
             }
 
+            let msg: ToPlayerMsg<T> = Update(player_update, Some(state.clock));
             state.send_to(&msg, |conn| conn.in_sync);
         }
         GameOver => {
             state.send_to(&msg, |_conn| true);
             return Ok(true);
         }
+        SubmitActionError(InvalidAction { turn_num, .. }) => {
+            state.send_to(&msg, |conn| conn.in_sync);
+
+            // The game host rejected the action, so the player can try again until the original
+            // timer runs out. If it ran out while the action was in flight, time out right away
+            state.awaiting_turn = Some(turn_num);
+            state.acted_at = None;
+
+            if state
+                .deadline
+                .is_some_and(|deadline| deadline <= Instant::now())
+            {
+                process_timeout(turn_num, state, outbox)?;
+            }
+        }
         SetPrimaryStatus(_) | SubmitActionError(_) => {
             panic!("The game host generated a player message it shouldn't have")
         }
@@ -227,12 +311,14 @@ mod tests {
     use lttcore::encoding::Encoding;
     use lttcore::examples::{
         guess_the_number::{Guess, Settings},
-        GuessTheNumber,
+        tic_tac_toe::{self, Action, ActionError::SpaceIsTaken, Position},
+        GuessTheNumber, TicTacToe,
     };
     use lttcore::pov::{
         game_progression::GameProgression,
         player::{GamePlayer, PlayerUpdate},
     };
+    use std::time::Duration;
     use tokio::sync::mpsc::error::TryRecvError;
     use tokio::time::sleep;
 
@@ -256,9 +342,12 @@ mod tests {
             })
             .unzip();
 
+        let time_control = TimeControl::PerTurn(Duration::from_millis(50));
+        let clock = Some(time_control.clock());
+
         let _handle = tokio::spawn(player_connections::<GuessTheNumber>(
             player,
-            Duration::from_millis(50),
+            time_control,
             inbox,
             outbox,
         ));
@@ -303,7 +392,7 @@ mod tests {
         for stream in connection_streams.iter_mut().take(2) {
             let msg = stream.next_bytes().await.unwrap();
             let decoded: ToPlayerMsg<GuessTheNumber> = Encoding::Json.deserialize(&msg).unwrap();
-            assert_eq!(decoded, SyncState(game_player.clone()));
+            assert_eq!(decoded, SyncState(game_player.clone(), clock));
         }
 
         // Add connection id 3 which doesn't have the state yet
@@ -324,7 +413,7 @@ mod tests {
         for stream in connection_streams.iter_mut().take(2) {
             let msg = stream.next_bytes().await.unwrap();
             let decoded: ToPlayerMsg<GuessTheNumber> = Encoding::Json.deserialize(&msg).unwrap();
-            assert_eq!(decoded, Update(player_update.clone()))
+            assert_eq!(decoded, Update(player_update.clone(), clock))
         }
 
         // Once the state is sent, only connections waiting on it get it
@@ -340,7 +429,276 @@ mod tests {
 
         let msg = connection_streams[2].next_bytes().await.unwrap();
         let decoded: ToPlayerMsg<GuessTheNumber> = Encoding::Json.deserialize(&msg).unwrap();
-        assert_eq!(decoded, SyncState(game_player.clone()));
+        assert_eq!(decoded, SyncState(game_player.clone(), clock));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_actions_can_be_retried_until_the_timeout() {
+        let (inbox, outbox, mut mailbox_handles) = setup_test_infra::<TicTacToe>();
+        let player: Player = 1.into();
+        let mut game_progression: GameProgression<TicTacToe> =
+            GameProgression::from_settings(tic_tac_toe::Settings);
+        let taken = Position::new(0, 0);
+        let actions = [(
+            Player::new(0),
+            ActionResponse::Response(Action::from(taken)),
+        )]
+        .into_iter()
+        .collect();
+        let update = game_progression.resolve(actions);
+        let player_update = update.player_update(player).into_owned();
+        assert!(player_update.player_should_act());
+        game_progression.update(update);
+        let game_player = game_progression.game_player(player);
+        let turn = player_update.turn_num();
+        let error = SpaceIsTaken { attempted: taken };
+        let (conn, mut stream) = {
+            let (sender, receiver) = bytes_channels(Encoding::Json);
+            ((ConnectionIdSource::new().next(), sender), receiver)
+        };
+        let conn_id = conn.0;
+
+        let _handle = tokio::spawn(player_connections::<TicTacToe>(
+            player,
+            TimeControl::PerTurn(Duration::from_millis(200)),
+            inbox,
+            outbox,
+        ));
+
+        mailbox_handles
+            .add_player_connection_sender
+            .send(conn)
+            .unwrap();
+        assert_eq!(
+            mailbox_handles.to_game_host_msg_receiver.recv().await,
+            Some(RequestPlayerState { player })
+        );
+
+        mailbox_handles
+            .to_player_msg_sender
+            .send(game_player.into())
+            .unwrap();
+        mailbox_handles
+            .from_player_msg_sender
+            .send((conn_id, RequestPrimary))
+            .unwrap();
+        sleep(Duration::from_millis(20)).await;
+        mailbox_handles
+            .to_player_msg_sender
+            .send(player_update.into())
+            .unwrap();
+
+        let _sync_state = stream.next_bytes().await.unwrap();
+        let _primary_status = stream.next_bytes().await.unwrap();
+        let _update = stream.next_bytes().await.unwrap();
+
+        // The first submission is forwarded and rejected by the game host
+        let submit = SubmitAction {
+            action: Action::from(taken),
+            turn,
+        };
+        let expected = SubmitActionResponse {
+            player,
+            response: ActionResponse::Response(Action::from(taken)),
+        };
+
+        for _ in 0..2 {
+            mailbox_handles
+                .from_player_msg_sender
+                .send((conn_id, submit.clone()))
+                .unwrap();
+            assert_eq!(
+                mailbox_handles.to_game_host_msg_receiver.recv().await,
+                Some(expected.clone())
+            );
+
+            let rejection: ToPlayerMsg<TicTacToe> = SubmitActionError(InvalidAction {
+                turn_num: turn,
+                error: error.clone(),
+            });
+            mailbox_handles
+                .to_player_msg_sender
+                .send(rejection.clone())
+                .unwrap();
+
+            let msg = stream.next_bytes().await.unwrap();
+            let decoded: ToPlayerMsg<TicTacToe> = Encoding::Json.deserialize(&msg).unwrap();
+            assert_eq!(decoded, rejection);
+        }
+
+        // Once the timer runs out the player times out
+        assert_eq!(
+            mailbox_handles.to_game_host_msg_receiver.recv().await,
+            Some(SubmitActionResponse {
+                player,
+                response: ActionResponse::Timeout
+            })
+        );
+
+        let msg = stream.next_bytes().await.unwrap();
+        let decoded: ToPlayerMsg<TicTacToe> = Encoding::Json.deserialize(&msg).unwrap();
+        assert_eq!(decoded, SubmitActionError(Timeout { turn_num: turn }));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_clocks_are_charged_for_turns() {
+        let (inbox, outbox, mut mailbox_handles) = setup_test_infra::<TicTacToe>();
+        let player: Player = 1.into();
+        let mut game_progression: GameProgression<TicTacToe> =
+            GameProgression::from_settings(tic_tac_toe::Settings);
+        let actions = [(
+            Player::new(0),
+            ActionResponse::Response(Action::from(Position::new(0, 0))),
+        )]
+        .into_iter()
+        .collect();
+        let update = game_progression.resolve(actions);
+        let player_update = update.player_update(player).into_owned();
+        game_progression.update(update);
+        let game_player = game_progression.game_player(player);
+        let turn = player_update.turn_num();
+        let (conn, mut stream) = {
+            let (sender, receiver) = bytes_channels(Encoding::Json);
+            ((ConnectionIdSource::new().next(), sender), receiver)
+        };
+        let conn_id = conn.0;
+        let time_control = TimeControl::Fischer {
+            initial: Duration::from_secs(1),
+            increment: Duration::from_millis(100),
+        };
+
+        let _handle = tokio::spawn(player_connections::<TicTacToe>(
+            player,
+            time_control,
+            inbox,
+            outbox,
+        ));
+
+        mailbox_handles
+            .add_player_connection_sender
+            .send(conn)
+            .unwrap();
+        assert_eq!(
+            mailbox_handles.to_game_host_msg_receiver.recv().await,
+            Some(RequestPlayerState { player })
+        );
+        mailbox_handles
+            .to_player_msg_sender
+            .send(game_player.clone().into())
+            .unwrap();
+
+        let msg = stream.next_bytes().await.unwrap();
+        let decoded: ToPlayerMsg<TicTacToe> = Encoding::Json.deserialize(&msg).unwrap();
+        assert_eq!(decoded, SyncState(game_player, Some(time_control.clock())));
+
+        mailbox_handles
+            .from_player_msg_sender
+            .send((conn_id, RequestPrimary))
+            .unwrap();
+        let _primary_status = stream.next_bytes().await.unwrap();
+
+        mailbox_handles
+            .to_player_msg_sender
+            .send(player_update.clone().into())
+            .unwrap();
+        let msg = stream.next_bytes().await.unwrap();
+        let decoded: ToPlayerMsg<TicTacToe> = Encoding::Json.deserialize(&msg).unwrap();
+        assert_eq!(decoded, Update(player_update, Some(time_control.clock())));
+
+        // The player acts 300ms into their turn
+        sleep(Duration::from_millis(300)).await;
+        let action = Action::from(Position::new(1, 1));
+        mailbox_handles
+            .from_player_msg_sender
+            .send((
+                conn_id,
+                SubmitAction {
+                    action: action.clone(),
+                    turn,
+                },
+            ))
+            .unwrap();
+        assert_eq!(
+            mailbox_handles.to_game_host_msg_receiver.recv().await,
+            Some(SubmitActionResponse {
+                player,
+                response: ActionResponse::Response(action.clone()),
+            })
+        );
+
+        // The next update shows the time taken off and the increment added
+        let actions = [(player, ActionResponse::Response(action))]
+            .into_iter()
+            .collect();
+        let update = game_progression.resolve(actions);
+        let player_update = update.player_update(player).into_owned();
+        mailbox_handles
+            .to_player_msg_sender
+            .send(player_update.clone().into())
+            .unwrap();
+
+        let msg = stream.next_bytes().await.unwrap();
+        let decoded: ToPlayerMsg<TicTacToe> = Encoding::Json.deserialize(&msg).unwrap();
+        let clock = Clock {
+            remaining: Duration::from_millis(800),
+            periods: 0,
+        };
+        assert_eq!(decoded, Update(player_update, Some(clock)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_turns_without_a_reachable_deadline() {
+        let (inbox, outbox, mut mailbox_handles) = setup_test_infra::<TicTacToe>();
+        let player: Player = 1.into();
+        let mut game_progression: GameProgression<TicTacToe> =
+            GameProgression::from_settings(tic_tac_toe::Settings);
+        let actions = [(
+            Player::new(0),
+            ActionResponse::Response(Action::from(Position::new(0, 0))),
+        )]
+        .into_iter()
+        .collect();
+        let update = game_progression.resolve(actions);
+        let player_update = update.player_update(player).into_owned();
+        game_progression.update(update);
+        let (conn, mut stream) = {
+            let (sender, receiver) = bytes_channels(Encoding::Json);
+            ((ConnectionIdSource::new().next(), sender), receiver)
+        };
+        let time_control = TimeControl::PerTurn(Duration::MAX);
+
+        let _handle = tokio::spawn(player_connections::<TicTacToe>(
+            player,
+            time_control,
+            inbox,
+            outbox,
+        ));
+
+        mailbox_handles
+            .add_player_connection_sender
+            .send(conn)
+            .unwrap();
+        let _request_player_state = mailbox_handles.to_game_host_msg_receiver.recv().await;
+        mailbox_handles
+            .to_player_msg_sender
+            .send(game_progression.game_player(player).into())
+            .unwrap();
+        let _sync_state = stream.next_bytes().await.unwrap();
+
+        mailbox_handles
+            .to_player_msg_sender
+            .send(player_update.clone().into())
+            .unwrap();
+        let msg = stream.next_bytes().await.unwrap();
+        let decoded: ToPlayerMsg<TicTacToe> = Encoding::Json.deserialize(&msg).unwrap();
+        assert_eq!(decoded, Update(player_update, Some(time_control.clock())));
+
+        // The turn never times out
+        sleep(Duration::from_secs(365 * 24 * 60 * 60)).await;
+        assert!(mailbox_handles
+            .to_game_host_msg_receiver
+            .try_recv()
+            .is_err());
     }
 
     // #[tokio::test]
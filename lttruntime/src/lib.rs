@@ -1,7 +1,9 @@
 #![allow(dead_code)]
 
 mod game_runner;
-pub use game_runner::{ObserverConnection, PlayerConnection};
+pub use game_runner::{
+    GameCompletion, GameRunner, ObserverConnection, PlayerConnection, DEFAULT_IDLE_TIMEOUT,
+};
 
 mod runtime;
 pub use runtime::Runtime;
@@ -9,3 +11,5 @@ pub use runtime::Runtime;
 pub mod error;
 mod match_maker;
 pub mod messages;
+pub mod ratings;
+pub mod time_control;
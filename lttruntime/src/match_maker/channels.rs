@@ -2,10 +2,12 @@ use crate::messages::MatchMakerRequest;
 use lttcore::{id::GameId, play::Player};
 use tokio::sync::{mpsc, oneshot};
 
+/// Resolves with the game and seat once the request is matched. Dropping the ticket cancels the
+/// request, and the ticket errors if the request times out.
 pub type GameRequestTicket = oneshot::Receiver<(GameId, Player)>;
 pub type GameRequestTicketResolver = oneshot::Sender<(GameId, Player)>;
 
-pub type MatchMakerRequestReceiver =
-    mpsc::UnboundedReceiver<(MatchMakerRequest, GameRequestTicketResolver)>;
-pub type MatchMakerRequestSender =
-    mpsc::UnboundedSender<(MatchMakerRequest, GameRequestTicketResolver)>;
+pub type MatchMakerRequestReceiver<T> =
+    mpsc::UnboundedReceiver<(MatchMakerRequest<T>, GameRequestTicketResolver)>;
+pub type MatchMakerRequestSender<T> =
+    mpsc::UnboundedSender<(MatchMakerRequest<T>, GameRequestTicketResolver)>;
@@ -1,12 +1,284 @@
 mod channels;
+mod queue;
+
 use crate::game_runner::GameRunner;
+use crate::ratings::{RatingKey, Ratings};
 pub use channels::{GameRequestTicket, MatchMakerRequestReceiver, MatchMakerRequestSender};
 use lttcore::play::Play;
+use lttcore::pov::game_progression::GameProgression;
+use lttcore::utilities::PlayerIndexedData as PID;
+use queue::{Queue, Waiting};
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{sleep_until, Instant};
+
+/// How often to try again to seat users who are waiting for their rating windows to widen
+const REMATCH_INTERVAL: Duration = Duration::from_secs(1);
 
+/// Seat users asking for games with the same settings together
+///
+/// Requests wait in a queue per [`SettingsPtr`](lttcore::play::SettingsPtr) and
+/// [`TimeControl`](crate::time_control::TimeControl) until enough users
+/// with close [`Ratings`] are waiting to fill every seat, then a game is spawned and each ticket
+/// resolves with the new game and its seat. How close ratings need to be widens the longer users
+/// wait, see [`RATING_WINDOW`](queue::RATING_WINDOW). Ratings are updated once the game
+/// concludes. Returns once every sender is dropped.
 pub async fn run_match_maker<T: Play>(
-    _mailbox: MatchMakerRequestReceiver,
-    _game_runner: Arc<GameRunner<T>>,
+    mut mailbox: MatchMakerRequestReceiver<T>,
+    game_runner: Arc<GameRunner<T>>,
+    ratings: Arc<Ratings>,
+) {
+    let mut queues: Vec<Queue<T>> = Vec::new();
+
+    loop {
+        let next_deadline = queues.iter().filter_map(Queue::next_deadline).min();
+        let next_rematch = queues
+            .iter()
+            .any(Queue::has_enough_players)
+            .then(|| Instant::now() + REMATCH_INTERVAL);
+        let next_wake = next_deadline.into_iter().chain(next_rematch).min();
+
+        tokio::select! {
+            msg = mailbox.recv() => {
+                let (request, resolver) = match msg {
+                    Some(msg) => msg,
+                    None => return,
+                };
+
+                let now = Instant::now();
+                let waiting = Waiting {
+                    user_id: request.user_id,
+                    rating: ratings.rating(&RatingKey::new::<T>(request.user_id, &request.settings)),
+                    since: now,
+                    deadline: request.timeout.map(|timeout| now + timeout),
+                    resolver,
+                };
+
+                let index = queues.iter().position(|queue| {
+                    queue.settings == request.settings
+                        && queue.time_control == request.time_control
+                });
+                let queue = match index {
+                    Some(index) => &mut queues[index],
+                    None => {
+                        queues.push(Queue::new(request.settings, request.time_control));
+                        queues.last_mut().expect("a queue was just pushed")
+                    }
+                };
+                queue.push(waiting);
+            }
+            _ = sleep_until(next_wake.unwrap_or_else(Instant::now)), if next_wake.is_some() => {
+                let now = Instant::now();
+                for queue in &mut queues {
+                    queue.remove_expired(now);
+                }
+            }
+        }
+
+        let now = Instant::now();
+        for queue in &mut queues {
+            while let Some(seats) = queue.pop_seats(now) {
+                spawn_game(queue, seats, &game_runner, &ratings);
+            }
+        }
+        queues.retain(|queue| !queue.is_empty());
+    }
+}
+
+/// Spawn a game for the seated users, resolve their tickets, and rate the game once it
+/// concludes
+fn spawn_game<T: Play>(
+    queue: &Queue<T>,
+    seats: Vec<Waiting>,
+    game_runner: &GameRunner<T>,
+    ratings: &Arc<Ratings>,
 ) {
-    todo!()
+    let settings = queue.settings.clone();
+    let game_progression = GameProgression::from_settings(settings.clone());
+    let players: Vec<_> = game_progression.players().collect();
+    let (game_id, completion) = match queue.time_control {
+        Some(time_control) => game_runner.spawn_timed_game(game_progression, time_control),
+        None => game_runner.spawn_game_with_completion(game_progression),
+    };
+
+    let mut user_ids: PID<_> = PID::default();
+    for (player, waiting) in players.into_iter().zip(seats) {
+        user_ids.insert(player, waiting.user_id);
+        let _maybe_cancelled = waiting.resolver.send((game_id, player));
+    }
+
+    let ratings = Arc::clone(ratings);
+    tokio::spawn(async move {
+        if let Some(game) = completion.wait().await {
+            if game.is_concluded() {
+                ratings
+                    .record_game::<T>(&settings, &user_ids, game.public_info())
+                    .await;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::queue::{Queue, Waiting};
+    use crate::messages::MatchMakerRequest;
+    use crate::ratings::{RatingKey, DEFAULT_RATING};
+    use crate::time_control::TimeControl;
+    use crate::Runtime;
+    use lttcore::encoding::Encoding;
+    use lttcore::examples::{guess_the_number::Settings, GuessTheNumber};
+    use lttcore::id::UserId;
+    use lttcore::play::{settings::VerifiedBuiltin, Player, SettingsPtr};
+    use std::time::Duration;
+    use tokio::sync::oneshot;
+    use tokio::time::Instant;
+
+    fn two_players() -> SettingsPtr<Settings> {
+        "players-2-range-1-10"
+            .parse::<VerifiedBuiltin<Settings>>()
+            .unwrap()
+            .into()
+    }
+
+    fn request(settings: SettingsPtr<Settings>) -> MatchMakerRequest<GuessTheNumber> {
+        MatchMakerRequest::new(UserId::new(), settings)
+    }
+
+    #[tokio::test]
+    async fn test_match_making_fills_every_seat() {
+        let runtime: Runtime<GuessTheNumber> = Runtime::start();
+        let first = runtime.match_make(request(two_players()));
+        let other_settings = runtime.match_make(request(SettingsPtr::default()));
+        let second = runtime.match_make(request(two_players()));
+
+        let (game_id, first_player) = first.await.unwrap();
+        let (second_game_id, second_player) = second.await.unwrap();
+        assert_eq!(game_id, second_game_id);
+        assert_eq!(first_player, Player::new(0));
+        assert_eq!(second_player, Player::new(1));
+        assert!(runtime
+            .play_game(game_id, first_player, Encoding::Json)
+            .is_some());
+
+        // The default settings are for one player, so that request gets its own game
+        let (solo_game_id, _) = other_settings.await.unwrap();
+        assert_ne!(solo_game_id, game_id);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_requests_are_not_seated() {
+        let runtime: Runtime<GuessTheNumber> = Runtime::start();
+        drop(runtime.match_make(request(two_players())));
+        let first = runtime.match_make(request(two_players()));
+        let second = runtime.match_make(request(two_players()));
+
+        assert_eq!(first.await.unwrap().1, Player::new(0));
+        assert_eq!(second.await.unwrap().1, Player::new(1));
+    }
+
+    #[tokio::test]
+    async fn test_asking_again_replaces_the_earlier_request() {
+        let runtime: Runtime<GuessTheNumber> = Runtime::start();
+        let user_id = UserId::new();
+        let earlier = runtime.match_make(MatchMakerRequest::new(user_id, two_players()));
+        let later = runtime.match_make(MatchMakerRequest::new(user_id, two_players()));
+        let other = runtime.match_make(request(two_players()));
+
+        assert!(earlier.await.is_err());
+        assert_eq!(later.await.unwrap().1, Player::new(0));
+        assert_eq!(other.await.unwrap().1, Player::new(1));
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_seated_with_the_same_time_control() {
+        let runtime: Runtime<GuessTheNumber> = Runtime::start();
+        let blitz = TimeControl::Fischer {
+            initial: Duration::from_secs(180),
+            increment: Duration::from_secs(2),
+        };
+        let first = runtime.match_make(request(two_players()).with_time_control(blitz));
+        let mut other_time_control = runtime.match_make(request(two_players()));
+        let second = runtime.match_make(request(two_players()).with_time_control(blitz));
+
+        let (game_id, _) = first.await.unwrap();
+        assert_eq!(second.await.unwrap().0, game_id);
+        assert!(other_time_control.try_recv().is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_requests_time_out() {
+        let runtime: Runtime<GuessTheNumber> = Runtime::start();
+        let timeout = Duration::from_secs(30);
+        let ticket = runtime.match_make(request(two_players()).with_timeout(timeout));
+
+        tokio::time::sleep(timeout + Duration::from_secs(1)).await;
+        assert!(ticket.await.is_err());
+
+        // The timed out request isn't seated with later ones
+        let first = runtime.match_make(request(two_players()).with_timeout(timeout));
+        let second = runtime.match_make(request(two_players()));
+        assert_eq!(first.await.unwrap().1, Player::new(0));
+        assert_eq!(second.await.unwrap().1, Player::new(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_requests_arriving_as_others_expire_are_not_seated_with_them() {
+        let timeout = Duration::from_secs(30);
+        let mut queue: Queue<GuessTheNumber> = Queue::new(two_players(), None);
+        let waiting = |deadline| {
+            let (resolver, ticket) = oneshot::channel();
+            let waiting = Waiting {
+                user_id: UserId::new(),
+                rating: DEFAULT_RATING,
+                since: Instant::now(),
+                deadline,
+                resolver,
+            };
+            (waiting, ticket)
+        };
+
+        let (expiring, expiring_ticket) = waiting(Some(Instant::now() + timeout));
+        queue.push(expiring);
+        tokio::time::sleep(timeout).await;
+
+        let (first, _first_ticket) = waiting(None);
+        queue.push(first);
+        assert!(queue.pop_seats(Instant::now()).is_none());
+        assert!(expiring_ticket.await.is_err());
+
+        let (second, _second_ticket) = waiting(None);
+        queue.push(second);
+        assert_eq!(
+            queue.pop_seats(Instant::now()).map(|seats| seats.len()),
+            Some(2)
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_players_are_paired_by_rating() {
+        let runtime: Runtime<GuessTheNumber> = Runtime::start();
+        let expert = request(two_players());
+        runtime.ratings().set_rating(
+            RatingKey::new::<GuessTheNumber>(expert.user_id, &expert.settings),
+            1900.0,
+        );
+
+        let mut expert = runtime.match_make(expert);
+        let first = runtime.match_make(request(two_players()));
+        let second = runtime.match_make(request(two_players()));
+        assert_eq!(first.await.unwrap().1, Player::new(0));
+        assert_eq!(second.await.unwrap().1, Player::new(1));
+        assert!(expert.try_recv().is_err());
+
+        // The expert's window widens to reach newcomers after
+        // (1900 - 1500 - RATING_WINDOW) / RATING_WINDOW_GROWTH_PER_SECOND seconds
+        let newcomer = runtime.match_make(request(two_players()));
+        tokio::time::sleep(Duration::from_secs(29)).await;
+        assert!(expert.try_recv().is_err());
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert_eq!(expert.await.unwrap().1, Player::new(0));
+        assert_eq!(newcomer.await.unwrap().1, Player::new(1));
+    }
 }
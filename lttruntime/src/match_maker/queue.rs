@@ -0,0 +1,146 @@
+use super::channels::GameRequestTicketResolver;
+use crate::time_control::TimeControl;
+use lttcore::id::UserId;
+use lttcore::play::{settings::NumPlayers, Play, SettingsPtr};
+use std::collections::VecDeque;
+use tokio::time::Instant;
+
+/// How far apart the ratings of players can be when they start waiting
+pub const RATING_WINDOW: f64 = 100.0;
+
+/// How much further apart the ratings of players can be for every second they've waited
+pub const RATING_WINDOW_GROWTH_PER_SECOND: f64 = 10.0;
+
+/// A request waiting to be seated
+#[derive(Debug)]
+pub struct Waiting {
+    pub user_id: UserId,
+    pub rating: f64,
+    pub since: Instant,
+    pub deadline: Option<Instant>,
+    pub resolver: GameRequestTicketResolver,
+}
+
+impl Waiting {
+    /// How far the rating of an opponent can be from this player's rating at `now`
+    fn rating_window(&self, now: Instant) -> f64 {
+        let waited = now.saturating_duration_since(self.since).as_secs_f64();
+        RATING_WINDOW + RATING_WINDOW_GROWTH_PER_SECOND * waited
+    }
+
+    /// Whether both players' rating windows include the other's rating
+    fn is_compatible_with(&self, other: &Waiting, now: Instant) -> bool {
+        let difference = (self.rating - other.rating).abs();
+        difference <= self.rating_window(now) && difference <= other.rating_window(now)
+    }
+}
+
+/// Users waiting for a game with the same settings and time control, in the order they asked
+#[derive(Debug)]
+pub struct Queue<T: Play> {
+    pub settings: SettingsPtr<T::Settings>,
+    pub time_control: Option<TimeControl>,
+    waiting: VecDeque<Waiting>,
+}
+
+impl<T: Play> Queue<T> {
+    pub fn new(settings: SettingsPtr<T::Settings>, time_control: Option<TimeControl>) -> Self {
+        Self {
+            settings,
+            time_control,
+            waiting: VecDeque::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.waiting.is_empty()
+    }
+
+    /// Add a request to the back of the queue. A user only waits once per queue, so an earlier
+    /// request from the same user is cancelled.
+    pub fn push(&mut self, waiting: Waiting) {
+        self.waiting
+            .retain(|other| other.user_id != waiting.user_id);
+        self.waiting.push_back(waiting);
+    }
+
+    /// Whether enough users are waiting to fill a game, whether or not their ratings are close
+    pub fn has_enough_players(&self) -> bool {
+        self.waiting.len() >= self.seats()
+    }
+
+    /// Take the requests for a full game of players within each other's rating windows, in the
+    /// order they asked. Players who have waited longest get seated first, with the opponents
+    /// closest to their rating.
+    pub fn pop_seats(&mut self, now: Instant) -> Option<Vec<Waiting>> {
+        self.remove_cancelled();
+        self.remove_expired(now);
+        let seats = self.seats();
+        if self.waiting.len() < seats {
+            return None;
+        }
+
+        for anchor in 0..self.waiting.len() {
+            let rating = self.waiting[anchor].rating;
+            let mut candidates: Vec<usize> = (0..self.waiting.len())
+                .filter(|&candidate| candidate != anchor)
+                .collect();
+            // Stable, so players with the same rating keep the order they asked in
+            candidates.sort_by(|&a, &b| {
+                let a = (self.waiting[a].rating - rating).abs();
+                let b = (self.waiting[b].rating - rating).abs();
+                a.total_cmp(&b)
+            });
+
+            let mut group: Vec<usize> = vec![anchor];
+            for candidate in candidates {
+                if group.len() == seats {
+                    break;
+                }
+
+                let waiting = &self.waiting[candidate];
+                if group
+                    .iter()
+                    .all(|&member| self.waiting[member].is_compatible_with(waiting, now))
+                {
+                    group.push(candidate);
+                }
+            }
+
+            if group.len() == seats {
+                group.sort_unstable();
+                let mut taken: Vec<Waiting> = group
+                    .into_iter()
+                    .rev()
+                    .filter_map(|index| self.waiting.remove(index))
+                    .collect();
+                taken.reverse();
+                return Some(taken);
+            }
+        }
+
+        None
+    }
+
+    fn seats(&self) -> usize {
+        self.settings.number_of_players().into()
+    }
+
+    /// Drop requests whose ticket was dropped
+    pub fn remove_cancelled(&mut self) {
+        self.waiting.retain(|waiting| !waiting.resolver.is_closed());
+    }
+
+    /// Drop requests that have waited past their deadline, which errors their tickets
+    pub fn remove_expired(&mut self, now: Instant) {
+        self.waiting
+            .retain(|waiting| waiting.deadline.is_none_or(|deadline| deadline > now));
+    }
+
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.waiting
+            .iter()
+            .filter_map(|waiting| waiting.deadline)
+            .min()
+    }
+}
@@ -10,4 +10,6 @@ pub enum ToGameHostMsg<T: Play> {
         player: Player,
         response: ActionResponse<T>,
     },
+    /// Stop running the game before it concludes, because nobody is connected to it
+    Abandon,
 }
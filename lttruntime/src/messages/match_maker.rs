@@ -1,6 +1,36 @@
+use crate::time_control::TimeControl;
 use lttcore::id::UserId;
+use lttcore::play::{Play, SettingsPtr};
+use std::time::Duration;
 
+/// A request to be seated in the next game played with `settings` and `time_control`
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct MatchMakerRequest {
-    user_id: UserId,
+pub struct MatchMakerRequest<T: Play> {
+    pub user_id: UserId,
+    pub settings: SettingsPtr<T::Settings>,
+    /// How long to wait for enough players before giving up, forever if `None`
+    pub timeout: Option<Duration>,
+    /// The time control of the game, the game runner's if `None`
+    pub time_control: Option<TimeControl>,
+}
+
+impl<T: Play> MatchMakerRequest<T> {
+    pub fn new(user_id: UserId, settings: impl Into<SettingsPtr<T::Settings>>) -> Self {
+        Self {
+            user_id,
+            settings: settings.into(),
+            timeout: None,
+            time_control: None,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_time_control(mut self, time_control: TimeControl) -> Self {
+        self.time_control = Some(time_control);
+        self
+    }
 }
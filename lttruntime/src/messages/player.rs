@@ -1,3 +1,4 @@
+use crate::time_control::Clock;
 use lttcore::{
     play::{Play, TurnNum},
     pov::player::{GamePlayer, PlayerUpdate},
@@ -14,15 +15,18 @@ pub enum FromPlayerMsg<T: Play> {
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(bound = "")]
 pub enum ToPlayerMsg<T: Play> {
-    SyncState(GamePlayer<T>),
-    Update(PlayerUpdate<'static, T>),
+    /// The player's state, with the time they have left. The game host sends `None` for the
+    /// clock, which the player's connections fill in.
+    SyncState(GamePlayer<T>, Option<Clock>),
+    Update(PlayerUpdate<'static, T>, Option<Clock>),
     SetPrimaryStatus(bool),
-    SubmitActionError(SubmitActionErrorKind),
+    SubmitActionError(SubmitActionErrorKind<T>),
     GameOver,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub enum SubmitActionErrorKind {
+#[serde(bound = "")]
+pub enum SubmitActionErrorKind<T: Play> {
     NotPrimary,
     Timeout {
         turn_num: TurnNum,
@@ -31,16 +35,20 @@ pub enum SubmitActionErrorKind {
         attempted: TurnNum,
         correct: Option<TurnNum>,
     },
+    InvalidAction {
+        turn_num: TurnNum,
+        error: T::ActionError,
+    },
 }
 
 impl<T: Play> From<PlayerUpdate<'static, T>> for ToPlayerMsg<T> {
     fn from(update: PlayerUpdate<'static, T>) -> Self {
-        Self::Update(update)
+        Self::Update(update, None)
     }
 }
 
 impl<T: Play> From<GamePlayer<T>> for ToPlayerMsg<T> {
     fn from(state: GamePlayer<T>) -> Self {
-        Self::SyncState(state)
+        Self::SyncState(state, None)
     }
 }
@@ -0,0 +1,310 @@
+//! Elo skill ratings, used by the match maker to pair players of similar skill
+//!
+//! Users have a separate rating for every game type and settings mode they play, starting at
+//! [`DEFAULT_RATING`]. Multiplayer games are rated as if every pair of players had played each
+//! other, with wins and losses decided by [`Score::score`] and
+//! [`Score::score_interpertation`].
+use chrono::Utc;
+use dashmap::DashMap;
+use lttcore::id::UserId;
+use lttcore::play::{Play, Score, SettingsPtr};
+use lttcore::utilities::PlayerIndexedData as PID;
+use lttstorage::ratings::{RatingStorage, StoredRating};
+use lttstorage::storage::StorageError;
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// The rating of users who haven't played yet
+pub const DEFAULT_RATING: f64 = 1500.0;
+
+/// How far a rating moves after a game against a single opponent, at most
+const K_FACTOR: f64 = 32.0;
+
+/// Settings modes of unnamed custom settings
+const CUSTOM_MODE: &str = "custom";
+
+/// Which rating of a user to use
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RatingKey {
+    pub user_id: UserId,
+    /// The [`LibTableTopIdentifier`](lttcore::LibTableTopIdentifier) of the game
+    pub game_type: String,
+    /// The name of the builtin or custom settings, or `"custom"` for unnamed custom settings
+    pub mode: String,
+}
+
+impl RatingKey {
+    pub fn new<T: Play>(user_id: UserId, settings: &SettingsPtr<T::Settings>) -> Self {
+        Self {
+            user_id,
+            game_type: T::lib_table_top_identifier().to_owned(),
+            mode: SettingsPtr::name(settings)
+                .unwrap_or(CUSTOM_MODE)
+                .to_owned(),
+        }
+    }
+}
+
+impl RatingKey {
+    fn from_stored(stored: StoredRating) -> (Self, f64) {
+        let key = Self {
+            user_id: stored.user_id,
+            game_type: stored.game_type,
+            mode: stored.mode,
+        };
+        (key, stored.rating)
+    }
+
+    fn to_stored(&self, rating: f64) -> StoredRating {
+        StoredRating {
+            user_id: self.user_id,
+            game_type: self.game_type.clone(),
+            mode: self.mode.clone(),
+            rating,
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+/// Every user's ratings, see [`Ratings::with_storage`] to keep them across restarts
+#[derive(Default)]
+pub struct Ratings {
+    ratings: DashMap<RatingKey, f64>,
+    storage: Option<Arc<dyn RatingStorage>>,
+    /// Held while recording a game, so games concluding at the same time don't overwrite each
+    /// other's updates
+    recording: Mutex<()>,
+}
+
+impl fmt::Debug for Ratings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ratings")
+            .field("ratings", &self.ratings)
+            .field("has_storage", &self.storage.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Ratings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ratings starting from the ones in `storage`, with every recorded game written back to it
+    pub async fn with_storage(storage: Arc<dyn RatingStorage>) -> Result<Self, StorageError> {
+        let ratings = storage
+            .read_ratings()
+            .await?
+            .into_iter()
+            .map(RatingKey::from_stored)
+            .collect();
+
+        Ok(Self {
+            ratings,
+            storage: Some(storage),
+            recording: Mutex::new(()),
+        })
+    }
+
+    pub fn rating(&self, key: &RatingKey) -> f64 {
+        self.ratings
+            .get(key)
+            .map_or(DEFAULT_RATING, |rating| *rating)
+    }
+
+    /// Overwrite a rating, for example to restore ratings saved by [`Ratings::all`]. Unlike
+    /// recorded games, this isn't written to storage.
+    pub fn set_rating(&self, key: RatingKey, rating: f64) {
+        self.ratings.insert(key, rating);
+    }
+
+    /// Every rating that was recorded or set, users without one are at [`DEFAULT_RATING`]
+    pub fn all(&self) -> Vec<(RatingKey, f64)> {
+        self.ratings
+            .iter()
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect()
+    }
+
+    /// Update the ratings of the users seated in a concluded game. Games without a score don't
+    /// change any ratings. Failing to write the new ratings to storage is logged, they're still
+    /// used until the next restart.
+    pub async fn record_game<T: Play>(
+        &self,
+        settings: &SettingsPtr<T::Settings>,
+        seats: &PID<UserId>,
+        public_info: &T::PublicInfo,
+    ) {
+        let scores = match public_info.score() {
+            Some(scores) => scores,
+            None => return,
+        };
+
+        let _recording = self.recording.lock().await;
+
+        let players: Vec<(RatingKey, f64, i64)> = seats
+            .iter()
+            .filter_map(|(player, user_id)| {
+                let key = RatingKey::new::<T>(*user_id, settings);
+                let rating = self.rating(&key);
+                scores.get(player).map(|score| (key, rating, *score))
+            })
+            .collect();
+
+        if players.len() < 2 {
+            return;
+        }
+
+        // Split K between opponents so every game moves ratings by about the same amount
+        let k = K_FACTOR / (players.len() - 1) as f64;
+        let interpertation = <T::PublicInfo as Score>::score_interpertation();
+
+        let new_ratings: Vec<(RatingKey, f64)> = players
+            .iter()
+            .map(|(key, rating, score)| {
+                let change: f64 = players
+                    .iter()
+                    .filter(|(other, _, _)| other != key)
+                    .map(|(_, other_rating, other_score)| {
+                        let actual = match interpertation.compare(*score, *other_score) {
+                            Ordering::Greater => 1.0,
+                            Ordering::Equal => 0.5,
+                            Ordering::Less => 0.0,
+                        };
+                        k * (actual - expected_score(*rating, *other_rating))
+                    })
+                    .sum();
+
+                (key.clone(), rating + change)
+            })
+            .collect();
+
+        if let Some(storage) = &self.storage {
+            let stored = new_ratings
+                .iter()
+                .map(|(key, rating)| key.to_stored(*rating))
+                .collect();
+            if let Err(err) = storage.write_ratings(stored).await {
+                log::error!("failed to save ratings: {:?}", err);
+            }
+        }
+
+        for (key, rating) in new_ratings {
+            self.set_rating(key, rating);
+        }
+    }
+}
+
+/// The chance of a player rated `rating` beating one rated `other`, counting ties as half
+fn expected_score(rating: f64, other: f64) -> f64 {
+    1.0 / (1.0 + 10_f64.powf((other - rating) / 400.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lttcore::examples::{
+        guess_the_number::{Guess, PublicInfo, Settings},
+        GuessTheNumber,
+    };
+    use lttcore::play::Player;
+    use lttstorage::db::hash_map_db::HashMapDB;
+
+    fn seats(users: &[UserId]) -> PID<UserId> {
+        (0..).map(Player::new).zip(users.iter().copied()).collect()
+    }
+
+    /// Player 0 wins, since lower is better in guess the number
+    fn completed() -> PublicInfo {
+        PublicInfo::Completed {
+            secret_number: 5,
+            guesses: [(Player::new(0), Guess(5)), (Player::new(1), Guess(9))]
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recording_games() {
+        let ratings = Ratings::new();
+        let settings = SettingsPtr::default();
+        let (winner, loser) = (UserId::new(), UserId::new());
+        let seats = seats(&[winner, loser]);
+        let winner_key = RatingKey::new::<GuessTheNumber>(winner, &settings);
+        let loser_key = RatingKey::new::<GuessTheNumber>(loser, &settings);
+
+        ratings
+            .record_game::<GuessTheNumber>(&settings, &seats, &PublicInfo::InProgress)
+            .await;
+        assert_eq!(ratings.rating(&winner_key), DEFAULT_RATING);
+
+        ratings
+            .record_game::<GuessTheNumber>(&settings, &seats, &completed())
+            .await;
+        assert_eq!(ratings.rating(&winner_key), DEFAULT_RATING + K_FACTOR / 2.0);
+        assert_eq!(ratings.rating(&loser_key), DEFAULT_RATING - K_FACTOR / 2.0);
+
+        // Beating a lower rated player is worth less
+        ratings
+            .record_game::<GuessTheNumber>(&settings, &seats, &completed())
+            .await;
+        assert!(ratings.rating(&winner_key) < DEFAULT_RATING + K_FACTOR);
+        assert!(ratings.rating(&winner_key) > DEFAULT_RATING + K_FACTOR / 2.0);
+
+        // Other settings modes are rated separately
+        let other_settings: SettingsPtr<Settings> = Settings::try_from(1..=3).unwrap().into();
+        let other_key = RatingKey::new::<GuessTheNumber>(winner, &other_settings);
+        assert_eq!(other_key.mode, "custom");
+        assert_eq!(ratings.rating(&other_key), DEFAULT_RATING);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_games_are_all_recorded() {
+        let ratings = Arc::new(Ratings::new());
+        let settings = SettingsPtr::default();
+        let winner = UserId::new();
+        let key = RatingKey::new::<GuessTheNumber>(winner, &settings);
+
+        let games: Vec<_> = (0..10)
+            .map(|_| {
+                let ratings = Arc::clone(&ratings);
+                let settings = settings.clone();
+                let seats = seats(&[winner, UserId::new()]);
+                tokio::spawn(async move {
+                    ratings
+                        .record_game::<GuessTheNumber>(&settings, &seats, &completed())
+                        .await;
+                })
+            })
+            .collect();
+        for game in games {
+            game.await.unwrap();
+        }
+
+        // Every win after the first is against a lower rated player, so is worth less
+        let rating = ratings.rating(&key);
+        assert!(rating > DEFAULT_RATING + 9.0 * K_FACTOR / 4.0);
+        assert!(rating < DEFAULT_RATING + 10.0 * K_FACTOR / 2.0);
+    }
+
+    #[tokio::test]
+    async fn test_ratings_are_kept_in_storage() {
+        let storage = Arc::new(HashMapDB::new(lttcore::encoding::Encoding::Bincode));
+        let settings = SettingsPtr::default();
+        let (winner, loser) = (UserId::new(), UserId::new());
+        let winner_key = RatingKey::new::<GuessTheNumber>(winner, &settings);
+
+        let ratings = Ratings::with_storage(storage.clone()).await.unwrap();
+        ratings
+            .record_game::<GuessTheNumber>(&settings, &seats(&[winner, loser]), &completed())
+            .await;
+        let rating = ratings.rating(&winner_key);
+        assert!(rating > DEFAULT_RATING);
+
+        let restarted = Ratings::with_storage(storage).await.unwrap();
+        assert_eq!(restarted.rating(&winner_key), rating);
+        assert_eq!(restarted.all().len(), 2);
+    }
+}
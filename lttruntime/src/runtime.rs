@@ -1,7 +1,8 @@
 use super::game_runner::GameRunner;
 use super::match_maker::{run_match_maker, GameRequestTicket, MatchMakerRequestSender};
 use crate::messages::MatchMakerRequest;
-use crate::{ObserverConnection, PlayerConnection};
+use crate::ratings::Ratings;
+use crate::{GameCompletion, ObserverConnection, PlayerConnection};
 use lttcore::encoding::Encoding;
 use lttcore::{
     id::GameId,
@@ -12,26 +13,52 @@ use tokio::sync::{mpsc, oneshot};
 
 pub struct Runtime<T: Play> {
     game_runner: Arc<GameRunner<T>>,
-    match_maker_request_sender: MatchMakerRequestSender,
+    match_maker_request_sender: MatchMakerRequestSender<T>,
+    ratings: Arc<Ratings>,
 }
 
 impl<T: Play> Runtime<T> {
     pub fn start() -> Self {
-        let game_runner = Arc::new(GameRunner::new());
+        Self::start_with_game_runner(GameRunner::new(), Ratings::new())
+    }
+
+    /// Start a runtime running games with `game_runner`, which is where storage, idle timeouts
+    /// and time controls are set, with the match maker pairing players by `ratings`
+    ///
+    /// Unfinished games in storage aren't picked back up until
+    /// [`GameRunner::recover_games`] is called, so call it before starting the runtime:
+    ///
+    /// ```ignore
+    /// let game_runner = GameRunner::with_storage(storage).with_time_control(time_control);
+    /// game_runner.recover_games().await?;
+    /// let ratings = Ratings::with_storage(rating_storage).await?;
+    /// let runtime = Runtime::start_with_game_runner(game_runner, ratings);
+    /// ```
+    pub fn start_with_game_runner(game_runner: GameRunner<T>, ratings: Ratings) -> Self {
+        let game_runner = Arc::new(game_runner);
+        let ratings = Arc::new(ratings);
         let (match_maker_request_sender, match_maker_request_receiver) = mpsc::unbounded_channel();
 
         tokio::spawn(run_match_maker::<T>(
             match_maker_request_receiver,
             Arc::clone(&game_runner),
+            Arc::clone(&ratings),
         ));
 
         Self {
             game_runner,
             match_maker_request_sender,
+            ratings,
         }
     }
 
-    pub fn match_make(&self, request: MatchMakerRequest) -> GameRequestTicket {
+    /// The ratings the match maker pairs players by
+    pub fn ratings(&self) -> &Ratings {
+        &self.ratings
+    }
+
+    /// Queue for a game, see [`GameRequestTicket`] for how the request resolves
+    pub fn match_make(&self, request: MatchMakerRequest<T>) -> GameRequestTicket {
         let (resolver, ticket) = oneshot::channel();
 
         self.match_maker_request_sender
@@ -50,6 +77,11 @@ impl<T: Play> Runtime<T> {
         self.game_runner.play_game(game_id, player, encoding)
     }
 
+    /// Wait for a running game to conclude or be abandoned, `None` if the game isn't running
+    pub fn game_completion(&self, game_id: GameId) -> Option<GameCompletion<T>> {
+        self.game_runner.completion(game_id)
+    }
+
     pub fn observe_game(&self, game_id: GameId, encoding: Encoding) -> Option<ObserverConnection> {
         self.game_runner.observe_game(game_id, encoding)
     }
@@ -0,0 +1,317 @@
+//! How long players have to act on their turns
+//!
+//! Every player in a game has their own [`Clock`], which is run by the player's connections and
+//! sent along with the game state and updates. Players who don't act before their clock runs out
+//! respond with [`ActionResponse::Timeout`](lttcore::play::ActionResponse::Timeout). With
+//! [`TimeControl::Fischer`] and [`TimeControl::ByoYomi`], players who ran out of time have none
+//! left, so they time out on every turn after.
+//!
+//! Neither the time control nor the clocks of a game are stored with it, so games recovered from
+//! storage give players the [`GameRunner`](crate::GameRunner)'s time control with full clocks.
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// The time control of games that aren't given one
+pub const DEFAULT_TIME_CONTROL: TimeControl = TimeControl::PerTurn(Duration::from_secs(1));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TimeControl {
+    /// Every turn has the same deadline, time left over isn't kept
+    PerTurn(Duration),
+    /// Players start with `initial` on their clock, and gain `increment` after every turn they act
+    /// on
+    Fischer {
+        initial: Duration,
+        increment: Duration,
+    },
+    /// Players start with `main_time` on their clock. Once it runs out, they have `periods`
+    /// periods of `period` each. Acting before a period runs out keeps it for the next turn, every
+    /// period used up is lost.
+    ByoYomi {
+        main_time: Duration,
+        periods: u32,
+        period: Duration,
+    },
+}
+
+impl TimeControl {
+    /// The clock players start the game with
+    pub fn clock(&self) -> Clock {
+        match *self {
+            TimeControl::PerTurn(remaining) => Clock {
+                remaining,
+                periods: 0,
+            },
+            TimeControl::Fischer { initial, .. } => Clock {
+                remaining: initial,
+                periods: 0,
+            },
+            // Games without main time start in their first period
+            TimeControl::ByoYomi {
+                main_time,
+                periods,
+                period,
+            } if main_time.is_zero() && periods > 0 => Clock {
+                remaining: period,
+                periods: periods - 1,
+            },
+            TimeControl::ByoYomi {
+                main_time, periods, ..
+            } => Clock {
+                remaining: main_time,
+                periods,
+            },
+        }
+    }
+}
+
+/// The time a player has left
+///
+/// Durations too long to add up saturate at [`Duration::MAX`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Clock {
+    /// Time left in the turn for [`TimeControl::PerTurn`], on the main clock, or in the current
+    /// byo-yomi period once the main clock runs out
+    pub remaining: Duration,
+    /// Byo-yomi periods left after the main clock or the current period, so every period while
+    /// the main clock is running
+    pub periods: u32,
+}
+
+impl Clock {
+    /// How long the player can take on their turn before timing out
+    pub fn time_to_act(&self, time_control: &TimeControl) -> Duration {
+        match *time_control {
+            TimeControl::PerTurn(_) | TimeControl::Fischer { .. } => self.remaining,
+            TimeControl::ByoYomi { period, .. } => period
+                .saturating_mul(self.periods)
+                .saturating_add(self.remaining),
+        }
+    }
+
+    /// The clock `elapsed` into a turn the player hasn't acted on yet
+    pub fn running(&self, time_control: &TimeControl, elapsed: Duration) -> Clock {
+        match *time_control {
+            TimeControl::PerTurn(_) | TimeControl::Fischer { .. } => Clock {
+                remaining: self.remaining.saturating_sub(elapsed),
+                periods: self.periods,
+            },
+            TimeControl::ByoYomi { period, .. } => {
+                if elapsed < self.remaining {
+                    return Clock {
+                        remaining: self.remaining - elapsed,
+                        periods: self.periods,
+                    };
+                }
+
+                // Which of the periods left the player is in, if they haven't run out of time
+                let over = elapsed - self.remaining;
+                let current = over
+                    .as_nanos()
+                    .checked_div(period.as_nanos())
+                    .and_then(|current| u32::try_from(current).ok())
+                    .filter(|current| *current < self.periods);
+
+                match current {
+                    Some(current) => Clock {
+                        remaining: period - (over - period * current),
+                        periods: self.periods - current - 1,
+                    },
+                    None => Clock {
+                        remaining: Duration::ZERO,
+                        periods: 0,
+                    },
+                }
+            }
+        }
+    }
+
+    /// The clock after the player acted `elapsed` into their turn
+    pub fn acted(&self, time_control: &TimeControl, elapsed: Duration) -> Clock {
+        match *time_control {
+            TimeControl::PerTurn(_) => *self,
+            TimeControl::Fischer { increment, .. } => Clock {
+                remaining: self
+                    .remaining
+                    .saturating_sub(elapsed)
+                    .saturating_add(increment),
+                periods: 0,
+            },
+            TimeControl::ByoYomi {
+                periods, period, ..
+            } => {
+                let running = self.running(time_control, elapsed);
+                // The main clock only runs while every period is left, see `Clock::periods`
+                if running.periods >= periods || running.remaining.is_zero() {
+                    running
+                } else {
+                    // The period the player acted in starts over
+                    Clock {
+                        remaining: period,
+                        ..running
+                    }
+                }
+            }
+        }
+    }
+
+    /// The clock after the player ran out of time
+    pub fn timed_out(&self, time_control: &TimeControl) -> Clock {
+        match *time_control {
+            TimeControl::PerTurn(_) => *self,
+            TimeControl::Fischer { .. } | TimeControl::ByoYomi { .. } => Clock {
+                remaining: Duration::ZERO,
+                periods: 0,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_per_turn_clocks() {
+        let time_control = TimeControl::PerTurn(secs(30));
+        let clock = time_control.clock();
+        assert_eq!(clock.time_to_act(&time_control), secs(30));
+        assert_eq!(clock.running(&time_control, secs(10)).remaining, secs(20));
+        assert_eq!(clock.acted(&time_control, secs(10)), clock);
+        assert_eq!(clock.timed_out(&time_control), clock);
+    }
+
+    #[test]
+    fn test_fischer_clocks() {
+        let time_control = TimeControl::Fischer {
+            initial: secs(60),
+            increment: secs(5),
+        };
+        let clock = time_control.clock();
+        assert_eq!(clock.running(&time_control, secs(10)).remaining, secs(50));
+
+        let clock = clock.acted(&time_control, secs(10));
+        assert_eq!(clock.remaining, secs(55));
+        assert_eq!(clock.time_to_act(&time_control), secs(55));
+
+        let clock = clock.timed_out(&time_control);
+        assert_eq!(clock.time_to_act(&time_control), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_byo_yomi_clocks() {
+        let time_control = TimeControl::ByoYomi {
+            main_time: secs(60),
+            periods: 3,
+            period: secs(10),
+        };
+        let clock = time_control.clock();
+        assert_eq!(clock.time_to_act(&time_control), secs(90));
+
+        // Acting within the main time takes from the main time
+        let clock = clock.acted(&time_control, secs(40));
+        assert_eq!(
+            clock,
+            Clock {
+                remaining: secs(20),
+                periods: 3
+            }
+        );
+
+        // Running into the first period shows the time left in it
+        assert_eq!(
+            clock.running(&time_control, secs(25)),
+            Clock {
+                remaining: secs(5),
+                periods: 2
+            }
+        );
+
+        // Acting within a period keeps it, and the clock looks the same as one running into it
+        let clock = clock.acted(&time_control, secs(25));
+        assert_eq!(
+            clock,
+            Clock {
+                remaining: secs(10),
+                periods: 2
+            }
+        );
+        assert_eq!(clock.time_to_act(&time_control), secs(30));
+        assert_eq!(clock.running(&time_control, Duration::ZERO), clock);
+
+        // Acting within the current period again keeps it
+        let clock = clock.acted(&time_control, secs(3));
+        assert_eq!(
+            clock,
+            Clock {
+                remaining: secs(10),
+                periods: 2
+            }
+        );
+
+        // Periods used up are lost
+        assert_eq!(
+            clock.running(&time_control, secs(15)),
+            Clock {
+                remaining: secs(5),
+                periods: 1
+            }
+        );
+        let clock = clock.acted(&time_control, secs(15));
+        assert_eq!(
+            clock,
+            Clock {
+                remaining: secs(10),
+                periods: 1
+            }
+        );
+
+        let clock = clock.timed_out(&time_control);
+        assert_eq!(clock.time_to_act(&time_control), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_byo_yomi_without_main_time() {
+        let time_control = TimeControl::ByoYomi {
+            main_time: Duration::ZERO,
+            periods: 3,
+            period: secs(10),
+        };
+        let clock = time_control.clock();
+        assert_eq!(
+            clock,
+            Clock {
+                remaining: secs(10),
+                periods: 2
+            }
+        );
+        assert_eq!(clock.acted(&time_control, secs(4)), clock);
+    }
+
+    #[test]
+    fn test_clocks_saturate() {
+        let per_turn = TimeControl::PerTurn(Duration::MAX);
+        assert_eq!(per_turn.clock().time_to_act(&per_turn), Duration::MAX);
+
+        let fischer = TimeControl::Fischer {
+            initial: Duration::MAX,
+            increment: secs(5),
+        };
+        let clock = fischer.clock().acted(&fischer, secs(1));
+        assert_eq!(clock.remaining, Duration::MAX);
+
+        let byo_yomi = TimeControl::ByoYomi {
+            main_time: secs(60),
+            periods: u32::MAX,
+            period: Duration::MAX,
+        };
+        let clock = byo_yomi.clock();
+        assert_eq!(clock.time_to_act(&byo_yomi), Duration::MAX);
+        assert_eq!(clock.running(&byo_yomi, secs(10)).periods, u32::MAX);
+        assert_eq!(clock.acted(&byo_yomi, secs(90)).remaining, Duration::MAX);
+    }
+}
@@ -3,15 +3,23 @@
 #[macro_use]
 extern crate derive_builder;
 
+mod report;
+mod seating;
+mod tournament;
+pub use report::{ContenderRecord, FightCardReport, BASE_RATING};
+pub use seating::SeatingPolicy;
+pub use tournament::{Standing, Tournament, TournamentBuilder, TournamentFormat, TournamentReport};
+
 use lttcore::{bot::Contender, play::ActionResponse};
 use lttcore::{
     bot::{BotContextBuilder, BotError},
     utilities::PlayerIndexedData as PID,
 };
 use lttcore::{
-    play::{settings::NumPlayers, Play, Seed, SettingsPtr},
+    play::{settings::NumPlayers, Play, Player, Seed, SettingsPtr},
     pov::game_progression::GameProgression,
 };
+use rand::Rng;
 use rayon::prelude::*;
 use std::{
     panic::{catch_unwind, AssertUnwindSafe},
@@ -19,6 +27,7 @@ use std::{
 };
 
 #[derive(Builder, Clone)]
+#[builder(build_fn(validate = "Self::validate"))]
 pub struct FightCard<T: Play> {
     contenders: PID<Contender<T>>,
     #[builder(default, setter(into))]
@@ -27,81 +36,182 @@ pub struct FightCard<T: Play> {
     iterations: usize,
     #[builder(default = "Duration::from_millis(500)")]
     bot_action_duration: Duration,
+    /// Master seed every iteration's game and bot seeds are derived from, random by default
+    #[builder(default = "Seed::random()")]
+    seed: Seed,
+    #[builder(default)]
+    seating_policy: SeatingPolicy,
+}
+
+impl<T: Play> FightCardBuilder<T> {
+    fn validate(&self) -> Result<(), String> {
+        let seats = self
+            .settings
+            .clone()
+            .unwrap_or_default()
+            .number_of_players()
+            .players()
+            .count();
+
+        match &self.contenders {
+            Some(contenders) if contenders.len() != seats => Err(format!(
+                "a fight card needs exactly {} contenders, got {}",
+                seats,
+                contenders.len()
+            )),
+            _ => Ok(()),
+        }
+    }
 }
 
 impl<T: Play> FightCard<T> {
     pub fn run(&self, callback: impl Fn((usize, GameProgression<T>)) + Send + Sync) {
         (0..self.iterations)
             .into_par_iter()
-            .map(|i| {
-                let game_seed = Seed::random();
-
-                let mut bots = self
-                    .settings
-                    .number_of_players()
-                    .player_indexed_data(|player| self.contenders[player].make_bot_instance());
-
-                let bot_seeds = self
-                    .settings
-                    .number_of_players()
-                    .player_indexed_data(|_| Seed::random());
-
-                let mut game: GameProgression<T> =
-                    GameProgression::from_settings_and_seed(self.settings.clone(), game_seed);
-
-                while !game.is_concluded() {
-                    let actions =
-                        game.which_players_input_needed()
-                            .map(|player| {
-                                let pov = &game.player_pov(player);
-
-                                let context = BotContextBuilder::default()
-                                    .seed(&bot_seeds[player])
-                                    .time_budget(self.bot_action_duration)
-                                    .turn_num(game.turn_num())
-                                    .build()
-                                    .unwrap();
-
-                                // # Safety
-                                //
-                                // It's _probably_ not technically "unsafe" to reuse a bot who's is
-                                // potentially in a weird state after it's panicked. For good measure,
-                                // we resign and don't continue to reuse the bot state.
-                                let mut bot_wrapper = AssertUnwindSafe(&mut bots[player]);
-                                let action = catch_unwind(move || {
-                                    bot_wrapper.on_action_request(pov, &context)
-                                })
-                                .map(|result| match result {
-                                    // bot completed successfully, or checkpointed out
-                                    Ok(action) | Err(BotError::TimeExceeded(Some(action))) => {
-                                        ActionResponse::Response(action)
-                                    }
-                                    // Bot exceeded time without providing an intermediate
-                                    Err(BotError::TimeExceeded(None)) => ActionResponse::Timeout,
-                                    // Other errors
-                                    Err(_) => ActionResponse::Timeout,
-                                })
-                                // If the bot panics while executing
-                                .unwrap_or_else(|_| ActionResponse::Resign);
-
-                                (player, action)
-                            })
-                            .collect();
-
-                    let update = game.resolve(actions);
-                    game.update(update);
-                }
-
-                (i, game)
-            })
+            .map(|i| (i, self.play(i)))
             .for_each(callback)
     }
+
+    /// Run every iteration and tally the results into a [`FightCardReport`]
+    pub fn report(&self) -> FightCardReport {
+        let empty = FightCardReport::new(
+            self.contenders
+                .iter()
+                .map(|(player, contender)| (player, contender.name())),
+        );
+
+        (0..self.iterations)
+            .into_par_iter()
+            .map(|i| (i, self.play(i)))
+            .fold(
+                || empty.clone(),
+                |mut report, (i, game)| {
+                    let seating = self.seating(i);
+                    report.record_seated(&game, |seat| seating[seat]);
+                    report
+                },
+            )
+            .reduce(|| empty.clone(), FightCardReport::merge)
+    }
+
+    /// The contender sitting in each seat during an iteration, according to the
+    /// [`SeatingPolicy`]
+    ///
+    /// Games passed to the [`FightCard::run`] callback are indexed by seat, use this to find out
+    /// which contender played each seat.
+    pub fn seating(&self, iteration: usize) -> PID<Player> {
+        let contenders: Vec<Player> = self.contenders.players().collect();
+        let seats = self.settings.number_of_players().players();
+
+        seats
+            .zip(self.seating_policy.seating(contenders.len(), iteration))
+            .map(|(seat, idx)| (seat, contenders[idx]))
+            .collect()
+    }
+
+    /// The master seed of the fight card, building a new fight card with the same seed replays
+    /// the same games
+    pub fn seed(&self) -> &Seed {
+        &self.seed
+    }
+
+    /// Replay a single iteration of [`FightCard::run`]
+    ///
+    /// The game and bots are seeded exactly as they were during the run, so games between
+    /// deterministic bots play out the same way. Bots whose choices depend on how much of their
+    /// time budget is left (like search bots that stop early) may still play differently.
+    pub fn run_iteration(&self, iteration: usize) -> GameProgression<T> {
+        self.play(iteration)
+    }
+
+    /// The game seed and bot seeds for an iteration, derived from the master seed
+    fn seeds(&self, iteration: usize) -> (Seed, PID<Seed>) {
+        let mut rng = self
+            .seed
+            .rng_for_turn(u64::try_from(iteration).expect("fewer than u64::MAX iterations"));
+
+        let game_seed = rng.gen::<[u8; 32]>().into();
+        let bot_seeds = self
+            .settings
+            .number_of_players()
+            .player_indexed_data(|_| rng.gen::<[u8; 32]>().into());
+
+        (game_seed, bot_seeds)
+    }
+
+    fn play(&self, iteration: usize) -> GameProgression<T> {
+        let (game_seed, bot_seeds) = self.seeds(iteration);
+        let seating = self.seating(iteration);
+
+        let mut bots = self
+            .settings
+            .number_of_players()
+            .player_indexed_data(|seat| self.contenders[seating[seat]].make_bot_instance());
+
+        let mut game: GameProgression<T> =
+            GameProgression::from_settings_and_seed(self.settings.clone(), game_seed);
+
+        while !game.is_concluded() {
+            let actions = game
+                .which_players_input_needed()
+                .map(|player| {
+                    let pov = &game.player_pov(player);
+
+                    let context = BotContextBuilder::default()
+                        .seed(&bot_seeds[player])
+                        .time_budget(self.bot_action_duration)
+                        .turn_num(game.turn_num())
+                        .build()
+                        .unwrap();
+
+                    // # Safety
+                    //
+                    // It's _probably_ not technically "unsafe" to reuse a bot who's is
+                    // potentially in a weird state after it's panicked. For good measure,
+                    // we resign and don't continue to reuse the bot state.
+                    let mut bot_wrapper = AssertUnwindSafe(&mut bots[player]);
+                    let action = catch_unwind(move || bot_wrapper.on_action_request(pov, &context))
+                        .map(|result| match result {
+                            // bot completed successfully, or checkpointed out
+                            Ok(action) | Err(BotError::TimeExceeded(Some(action))) => {
+                                ActionResponse::Response(action)
+                            }
+                            // Bot exceeded time without providing an intermediate
+                            Err(BotError::TimeExceeded(None)) => ActionResponse::Timeout,
+                            // Other errors
+                            Err(_) => ActionResponse::Timeout,
+                        })
+                        // If the bot panics while executing
+                        .unwrap_or_else(|_| ActionResponse::Resign);
+
+                    (player, action)
+                })
+                .collect();
+
+            let update = game.resolve(actions);
+            game.update(update);
+        }
+
+        game
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use lttcore::{examples::tic_tac_toe::bot::prebuilt::TicTacToePanicBot, play::Player};
+    use lttcore::{
+        examples::{
+            tic_tac_toe::{
+                bot::prebuilt::{ExpertSkill, RandomSelector, TicTacToePanicBot},
+                TicTacToeBot,
+            },
+            TicTacToe,
+        },
+        play::{
+            seed::{SEED_0, SEED_42},
+            Player,
+        },
+    };
 
     #[test]
     fn handles_panicking_bots() {
@@ -118,4 +228,79 @@ mod tests {
 
         fight_card.run(|_| {})
     }
+
+    #[test]
+    fn iterations_can_be_replayed_from_the_master_seed() {
+        let contenders: PID<Contender<TicTacToe>> = [
+            (Player::new(0), Contender::new(RandomSelector.into_bot())),
+            (Player::new(1), Contender::new(RandomSelector.into_bot())),
+        ]
+        .into_iter()
+        .collect();
+
+        let fight_card = |seed: Seed| {
+            FightCardBuilder::default()
+                .iterations(20)
+                .contenders(contenders.clone())
+                .seed(seed)
+                .build()
+                .unwrap()
+        };
+
+        let games = std::sync::Mutex::new(Vec::new());
+        fight_card(SEED_42).run(|game| games.lock().unwrap().push(game));
+        let games = games.into_inner().unwrap();
+        assert_eq!(games.len(), 20);
+
+        let replay = fight_card(SEED_42);
+        for (iteration, game) in &games {
+            assert_eq!(&replay.run_iteration(*iteration), game);
+        }
+
+        let other = fight_card(SEED_0);
+        assert!((0..20).any(|i| other.run_iteration(i) != replay.run_iteration(i)));
+    }
+
+    #[test]
+    fn results_are_reported_per_contender_whatever_their_seat() {
+        let expert = Player::new(0);
+        let panic = Player::new(1);
+        let contenders: PID<Contender<TicTacToe>> = [
+            (expert, Contender::new(ExpertSkill.into_bot())),
+            (panic, Contender::new(TicTacToePanicBot)),
+        ]
+        .into_iter()
+        .collect();
+
+        for policy in [SeatingPolicy::Rotate, SeatingPolicy::AllPermutations] {
+            let fight_card = FightCardBuilder::default()
+                .iterations(10)
+                .contenders(contenders.clone())
+                .seating_policy(policy)
+                .build()
+                .unwrap();
+
+            assert_eq!(fight_card.seating(0)[Player::new(0)], expert);
+            assert_eq!(fight_card.seating(1)[Player::new(0)], panic);
+
+            let report = fight_card.report();
+            let record = report.record_for(expert).unwrap();
+            assert_eq!((record.games, record.wins), (10, 10), "{:?}", policy);
+            assert_eq!(report.record_for(panic).unwrap().resignations, 10);
+            assert_eq!(report.head_to_head(expert, panic), 10.0);
+        }
+    }
+
+    #[test]
+    fn fight_cards_need_a_contender_for_every_seat() {
+        let result: Result<FightCard<TicTacToe>, _> = FightCardBuilder::default()
+            .contenders(
+                [(Player::new(0), Contender::new(TicTacToePanicBot))]
+                    .into_iter()
+                    .collect(),
+            )
+            .build();
+
+        assert!(result.is_err());
+    }
 }
@@ -0,0 +1,335 @@
+use lttcore::{
+    play::{score::ScoreInterpertation, ActionResponse, Play, Player, Score},
+    pov::game_progression::GameProgression,
+    utilities::PlayerIndexedData as PID,
+};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+/// The rating every contender starts from, and the average rating of a report
+pub const BASE_RATING: f64 = 1500.0;
+
+const RATING_ITERATIONS: usize = 200;
+
+/// Running tally of one contender's games
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContenderRecord {
+    pub name: String,
+    pub games: u64,
+    pub wins: u64,
+    pub losses: u64,
+    pub draws: u64,
+    pub timeouts: u64,
+    pub resignations: u64,
+    total_score: i64,
+    scored_games: u64,
+}
+
+impl ContenderRecord {
+    /// Mean of the contender's [`Score`] over the games that gave them one
+    pub fn average_score(&self) -> Option<f64> {
+        #[allow(clippy::cast_precision_loss)]
+        (self.scored_games > 0).then(|| self.total_score as f64 / self.scored_games as f64)
+    }
+
+    fn merge(&mut self, other: &ContenderRecord) {
+        self.games += other.games;
+        self.wins += other.wins;
+        self.losses += other.losses;
+        self.draws += other.draws;
+        self.timeouts += other.timeouts;
+        self.resignations += other.resignations;
+        self.total_score += other.total_score;
+        self.scored_games += other.scored_games;
+    }
+}
+
+/// Aggregated results of the games played by a [`FightCard`](crate::FightCard)
+///
+/// A contender wins a game by finishing ahead of every other player, draws by tying for first
+/// and otherwise loses. Games that end without a [`Score`] (like a drawn game of tic-tac-toe) are
+/// draws for everyone, as are games without opponents. Players missing from a [`Score`] finish
+/// behind everyone who has one.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FightCardReport {
+    records: PID<ContenderRecord>,
+    /// Points scored by the first contender against the second, 1 per win and 0.5 per draw
+    head_to_head: BTreeMap<(Player, Player), f64>,
+}
+
+impl FightCardReport {
+    /// Create an empty report for the named contenders
+    pub fn new<'a>(contenders: impl IntoIterator<Item = (Player, &'a str)>) -> Self {
+        let records = contenders
+            .into_iter()
+            .map(|(player, name)| {
+                let record = ContenderRecord {
+                    name: name.to_owned(),
+                    ..ContenderRecord::default()
+                };
+                (player, record)
+            })
+            .collect();
+
+        Self {
+            records,
+            head_to_head: BTreeMap::new(),
+        }
+    }
+
+    /// Add a finished game to the report
+    pub fn record<T: Play>(&mut self, game: &GameProgression<T>) {
+        self.record_seated(game, |seat| seat);
+    }
+
+    /// Add a finished game to the report, crediting the player in each seat to the contender
+    /// `seating(seat)`
+    pub(crate) fn record_seated<T: Play>(
+        &mut self,
+        game: &GameProgression<T>,
+        seating: impl Fn(Player) -> Player,
+    ) {
+        let scores = game.public_info().score();
+        let interpertation = <T::PublicInfo as Score>::score_interpertation();
+        let compare = |a: Player, b: Player| compare(scores.as_ref(), &interpertation, a, b);
+        let players: Vec<Player> = game.players().collect();
+
+        for &player in &players {
+            let record = &mut self.records[seating(player)];
+            record.games += 1;
+
+            let mut outcome = Ordering::Greater;
+            for &other in players.iter().filter(|&&other| other != player) {
+                outcome = outcome.min(compare(player, other));
+            }
+
+            match outcome {
+                Ordering::Greater if players.len() > 1 => record.wins += 1,
+                Ordering::Greater | Ordering::Equal => record.draws += 1,
+                Ordering::Less => record.losses += 1,
+            }
+
+            if let Some(score) = scores.as_ref().and_then(|scores| scores.get(player)) {
+                record.total_score += score;
+                record.scored_games += 1;
+            }
+        }
+
+        for event in game.history() {
+            for (player, response) in event.actions().iter() {
+                match response {
+                    ActionResponse::Timeout => self.records[seating(player)].timeouts += 1,
+                    ActionResponse::Resign => self.records[seating(player)].resignations += 1,
+                    ActionResponse::Response(_) => {}
+                }
+            }
+        }
+
+        for &a in &players {
+            for &b in players.iter().filter(|&&b| b != a) {
+                let points = match compare(a, b) {
+                    Ordering::Greater => 1.0,
+                    Ordering::Equal => 0.5,
+                    Ordering::Less => 0.0,
+                };
+                *self
+                    .head_to_head
+                    .entry((seating(a), seating(b)))
+                    .or_default() += points;
+            }
+        }
+    }
+
+    /// Combine two reports over the same contenders, useful for tallying games in parallel
+    pub fn merge(mut self, other: Self) -> Self {
+        for (player, record) in other.records.iter() {
+            match self.records.get_mut(player) {
+                Some(existing) => existing.merge(record),
+                None => {
+                    self.records.insert(player, record.clone());
+                }
+            }
+        }
+
+        for (pair, points) in other.head_to_head {
+            *self.head_to_head.entry(pair).or_default() += points;
+        }
+
+        self
+    }
+
+    /// Move the results of `player` to `seating(player)`, merging records that land on the same
+    /// contender. Results between a contender and itself are dropped from the head to head
+    pub(crate) fn reseat(self, seating: impl Fn(Player) -> Player) -> Self {
+        let mut reseated = Self::default();
+
+        for (player, record) in self.records {
+            let player = seating(player);
+            match reseated.records.get_mut(player) {
+                Some(existing) => existing.merge(&record),
+                None => {
+                    reseated.records.insert(player, record);
+                }
+            }
+        }
+
+        for ((a, b), points) in self.head_to_head {
+            let (a, b) = (seating(a), seating(b));
+            if a != b {
+                *reseated.head_to_head.entry((a, b)).or_default() += points;
+            }
+        }
+
+        reseated
+    }
+
+    /// The record of a single contender
+    pub fn record_for(&self, player: impl Into<Player>) -> Option<&ContenderRecord> {
+        self.records.get(player)
+    }
+
+    /// The records of every contender
+    pub fn records(&self) -> impl Iterator<Item = (Player, &ContenderRecord)> + '_ {
+        self.records.iter()
+    }
+
+    /// Points scored by `a` in games against `b`, 1 per win and 0.5 per draw
+    pub fn head_to_head(&self, a: impl Into<Player>, b: impl Into<Player>) -> f64 {
+        self.head_to_head
+            .get(&(a.into(), b.into()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Elo style ratings for every contender, averaging [`BASE_RATING`]
+    ///
+    /// Ratings are a Bradley-Terry fit of the head to head results, so unlike incremental Elo
+    /// they don't depend on the order games finished in. Every pair of contenders that met is
+    /// given one extra drawn game, which keeps ratings finite when a contender never loses.
+    pub fn ratings(&self) -> PID<f64> {
+        let players: Vec<Player> = self.records.players().collect();
+        let mut strengths: PID<f64> = players.iter().map(|&player| (player, 1.0)).collect();
+
+        for _ in 0..RATING_ITERATIONS {
+            let mut next: PID<f64> = PID::default();
+
+            for &a in &players {
+                let mut points = 0.0;
+                let mut denominator = 0.0;
+
+                for &b in players.iter().filter(|&&b| b != a) {
+                    let games = self.head_to_head(a, b) + self.head_to_head(b, a);
+
+                    if games > 0.0 {
+                        points += self.head_to_head(a, b) + 0.5;
+                        denominator += (games + 1.0) / (strengths[a] + strengths[b]);
+                    }
+                }
+
+                let strength = if denominator > 0.0 {
+                    points / denominator
+                } else {
+                    1.0
+                };
+                next.insert(a, strength);
+            }
+
+            // Keep the geometric mean at 1 so the ratings average out to the base rating
+            #[allow(clippy::cast_precision_loss)]
+            let mean_log = next.iter().map(|(_, s)| s.ln()).sum::<f64>() / players.len() as f64;
+            strengths = next
+                .into_iter()
+                .map(|(player, s)| (player, s / mean_log.exp()))
+                .collect();
+        }
+
+        strengths
+            .into_iter()
+            .map(|(player, s)| (player, BASE_RATING + 400.0 * s.log10()))
+            .collect()
+    }
+}
+
+fn compare(
+    scores: Option<&PID<i64>>,
+    interpertation: &ScoreInterpertation,
+    a: Player,
+    b: Player,
+) -> Ordering {
+    let Some(scores) = scores else {
+        return Ordering::Equal;
+    };
+
+    match (scores.get(a), scores.get(b)) {
+        (Some(a), Some(b)) => interpertation.compare(*a, *b),
+        (Some(_), None) => Ordering::Greater,
+        (None, Some(_)) => Ordering::Less,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lttcore::examples::{
+        tic_tac_toe::{Action, Position, Settings},
+        TicTacToe,
+    };
+    use lttcore::play::ActionResponse::Response;
+
+    fn play(moves: &[(usize, usize)]) -> GameProgression<TicTacToe> {
+        let mut game: GameProgression<TicTacToe> = GameProgression::from_settings(Settings);
+
+        for &(x, y) in moves {
+            let player = game.which_players_input_needed().next().unwrap();
+            let action = Response(Action::from(Position::new(x, y)));
+            let update = game.resolve([(player, action)].into_iter().collect());
+            game.update(update);
+        }
+
+        game
+    }
+
+    #[test]
+    fn test_tallying_wins_losses_and_draws() {
+        let p0 = Player::new(0);
+        let p1 = Player::new(1);
+        let empty = FightCardReport::new([(p0, "X Bot"), (p1, "O Bot")]);
+
+        let x_wins = play(&[(0, 0), (1, 1), (1, 0), (2, 2), (2, 0)]);
+        let draw = play(&[
+            (0, 0),
+            (1, 1),
+            (2, 2),
+            (0, 1),
+            (2, 1),
+            (2, 0),
+            (0, 2),
+            (1, 2),
+            (1, 0),
+        ]);
+
+        let mut first = empty.clone();
+        first.record(&x_wins);
+        first.record(&x_wins);
+        let mut second = empty;
+        second.record(&draw);
+        let report = first.merge(second);
+
+        let x = report.record_for(p0).unwrap();
+        assert_eq!((x.games, x.wins, x.losses, x.draws), (3, 2, 0, 1));
+        assert_eq!(x.average_score(), Some(1.0));
+
+        let o = report.record_for(p1).unwrap();
+        assert_eq!((o.games, o.wins, o.losses, o.draws), (3, 0, 2, 1));
+        assert_eq!(o.average_score(), Some(0.0));
+
+        assert_eq!(report.head_to_head(p0, p1), 2.5);
+        assert_eq!(report.head_to_head(p1, p0), 0.5);
+
+        let ratings = report.ratings();
+        assert!(ratings[p0] > BASE_RATING);
+        assert!(ratings[p1] < BASE_RATING);
+        assert!((ratings[p0] + ratings[p1] - 2.0 * BASE_RATING).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,82 @@
+/// How a [`FightCard`](crate::FightCard) seats its contenders each iteration
+///
+/// Many games favour one seat (like moving first in tic-tac-toe), so comparing contenders who
+/// always sit in the same seat mixes up the seat's advantage with the contender's strength.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SeatingPolicy {
+    /// Every contender always sits in the seat they were entered with
+    #[default]
+    Fixed,
+    /// Contenders move over one seat every iteration
+    Rotate,
+    /// Cycle through every ordering of the contenders. Each ordering is played equally often
+    /// when the number of iterations is a multiple of the number of orderings
+    AllPermutations,
+}
+
+impl SeatingPolicy {
+    /// The index of the contender sitting in each of `seats` seats during `iteration`
+    pub(crate) fn seating(self, seats: usize, iteration: usize) -> Vec<usize> {
+        let mut seating: Vec<usize> = (0..seats).collect();
+
+        match self {
+            Self::Fixed => {}
+            Self::Rotate if seats > 0 => seating.rotate_left(iteration % seats),
+            Self::Rotate => {}
+            Self::AllPermutations => {
+                // Decode the iteration in the factorial number system (a Lehmer code), where
+                // each digit picks one of the contenders who hasn't been seated yet
+                let mut remaining = seating;
+                let mut index =
+                    factorial(seats).map_or(iteration, |orderings| iteration % orderings);
+                seating = Vec::with_capacity(seats);
+
+                for n in (1..=seats).rev() {
+                    // Blocks too big for a usize are bigger than any index, so the digit is 0
+                    match factorial(n - 1) {
+                        Some(block) => {
+                            seating.push(remaining.remove(index / block));
+                            index %= block;
+                        }
+                        None => seating.push(remaining.remove(0)),
+                    }
+                }
+            }
+        }
+
+        seating
+    }
+}
+
+/// `n!`, or `None` if it doesn't fit in a usize
+fn factorial(n: usize) -> Option<usize> {
+    (1..=n).try_fold(1_usize, |product, k| product.checked_mul(k))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_seating_policies() {
+        assert_eq!(SeatingPolicy::Fixed.seating(3, 7), vec![0, 1, 2]);
+        assert_eq!(SeatingPolicy::Rotate.seating(3, 0), vec![0, 1, 2]);
+        assert_eq!(SeatingPolicy::Rotate.seating(3, 1), vec![1, 2, 0]);
+        assert_eq!(SeatingPolicy::Rotate.seating(3, 5), vec![2, 0, 1]);
+
+        let permutations: HashSet<Vec<usize>> = (0..24)
+            .map(|i| SeatingPolicy::AllPermutations.seating(4, i))
+            .collect();
+        assert_eq!(permutations.len(), 24);
+        assert_eq!(
+            SeatingPolicy::AllPermutations.seating(4, 24),
+            SeatingPolicy::AllPermutations.seating(4, 0)
+        );
+
+        // 21! doesn't fit in a usize, early iterations only reorder the last few seats
+        let seating = SeatingPolicy::AllPermutations.seating(21, 1);
+        assert_eq!(seating[..19], (0..19).collect::<Vec<_>>()[..]);
+        assert_eq!(seating[19..], [20, 19]);
+    }
+}
@@ -0,0 +1,328 @@
+use crate::{FightCardBuilder, FightCardReport};
+use lttcore::{
+    bot::Contender,
+    play::{settings::NumPlayers, Play, Player, SettingsPtr},
+    utilities::PlayerIndexedData as PID,
+};
+use std::time::Duration;
+
+/// How a [`Tournament`] decides who plays who
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TournamentFormat {
+    /// Every group of contenders plays one match, seated in the order they were entered
+    RoundRobin,
+    /// Every group of contenders plays one match in each rotation of the seats, so everyone gets
+    /// a turn in the first seat
+    DoubleRoundRobin,
+    /// Contenders with similar standings are grouped together each round, avoiding rematches
+    /// where possible. Contenders left over in a round get a bye worth a full match of wins
+    Swiss {
+        /// The number of rounds to play
+        rounds: usize,
+    },
+}
+
+/// Schedule and run matches between many [`Contender`]s
+///
+/// Every match is a [`FightCard`](crate::FightCard) of `games_per_match` games between as many
+/// contenders as the game has seats.
+#[derive(Builder, Clone)]
+#[builder(build_fn(validate = "Self::validate"))]
+pub struct Tournament<T: Play> {
+    contenders: Vec<Contender<T>>,
+    #[builder(default, setter(into))]
+    settings: SettingsPtr<T::Settings>,
+    #[builder(default = "TournamentFormat::RoundRobin")]
+    format: TournamentFormat,
+    #[builder(default = "100")]
+    games_per_match: usize,
+    #[builder(default = "Duration::from_millis(500)")]
+    bot_action_duration: Duration,
+}
+
+impl<T: Play> TournamentBuilder<T> {
+    fn validate(&self) -> Result<(), String> {
+        let seats = self
+            .settings
+            .clone()
+            .unwrap_or_default()
+            .number_of_players()
+            .players()
+            .count();
+
+        match &self.contenders {
+            Some(contenders) if contenders.len() < seats => Err(format!(
+                "a tournament needs at least {} contenders, got {}",
+                seats,
+                contenders.len()
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<T: Play> Tournament<T> {
+    /// Play every match of the tournament
+    pub fn run(&self) -> TournamentReport {
+        let seats = self.settings.number_of_players().players().count();
+        let mut report = TournamentReport {
+            results: FightCardReport::new(
+                self.contenders
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, contender)| (contender_player(idx), contender.name())),
+            ),
+            byes: vec![0; self.contenders.len()],
+            games_per_match: self.games_per_match,
+        };
+
+        match self.format {
+            TournamentFormat::RoundRobin => {
+                for group in combinations(self.contenders.len(), seats) {
+                    self.play_match(&group, &mut report);
+                }
+            }
+            TournamentFormat::DoubleRoundRobin => {
+                for mut group in combinations(self.contenders.len(), seats) {
+                    for _ in 0..seats {
+                        self.play_match(&group, &mut report);
+                        group.rotate_left(1);
+                    }
+                }
+            }
+            TournamentFormat::Swiss { rounds } => {
+                for _ in 0..rounds {
+                    let (groups, byes) = swiss_pairings(&report, seats);
+
+                    for group in groups {
+                        self.play_match(&group, &mut report);
+                    }
+
+                    for idx in byes {
+                        report.byes[idx] += 1;
+                    }
+                }
+            }
+        }
+
+        report
+    }
+
+    /// Play a match with `group[seat]` sitting in each seat
+    fn play_match(&self, group: &[usize], report: &mut TournamentReport) {
+        let contenders: PID<Contender<T>> = group
+            .iter()
+            .enumerate()
+            .map(|(seat, &idx)| (Player::new(seat_num(seat)), self.contenders[idx].clone()))
+            .collect();
+
+        let results = FightCardBuilder::default()
+            .contenders(contenders)
+            .settings(self.settings.clone())
+            .iterations(self.games_per_match)
+            .bot_action_duration(self.bot_action_duration)
+            .build()
+            .expect("all fight card fields were provided")
+            .report()
+            .reseat(|seat| contender_player(group[usize::from(seat)]));
+
+        report.results = std::mem::take(&mut report.results).merge(results);
+    }
+}
+
+/// The results of a [`Tournament`]
+///
+/// Contenders are identified by the order they were entered in, the first contender's results
+/// are under `Player::new(0)` in [`TournamentReport::results`] and so on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TournamentReport {
+    results: FightCardReport,
+    byes: Vec<u64>,
+    games_per_match: usize,
+}
+
+/// A contender's place in a [`TournamentReport`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Standing {
+    /// The index the contender was entered into the [`Tournament`] with
+    pub contender: usize,
+    pub name: String,
+    /// 1 per win, 0.5 per draw, and a full match of wins per bye
+    pub points: f64,
+    pub rating: f64,
+}
+
+impl TournamentReport {
+    /// Every game played in the tournament, tallied per contender
+    pub fn results(&self) -> &FightCardReport {
+        &self.results
+    }
+
+    /// Contenders ordered from first place to last, by points and then by rating
+    pub fn standings(&self) -> Vec<Standing> {
+        let ratings = self.results.ratings();
+
+        let mut standings: Vec<Standing> = self
+            .results
+            .records()
+            .map(|(player, record)| {
+                let contender = usize::from(player);
+
+                Standing {
+                    contender,
+                    name: record.name.clone(),
+                    points: self.points(contender),
+                    rating: ratings[player],
+                }
+            })
+            .collect();
+
+        standings.sort_by(|a, b| {
+            b.points
+                .total_cmp(&a.points)
+                .then(b.rating.total_cmp(&a.rating))
+                .then(a.contender.cmp(&b.contender))
+        });
+
+        standings
+    }
+
+    fn points(&self, contender: usize) -> f64 {
+        let record = self
+            .results
+            .record_for(contender_player(contender))
+            .expect("every contender has a record");
+
+        #[allow(clippy::cast_precision_loss)]
+        let byes = (self.byes[contender] * self.games_per_match as u64) as f64;
+        #[allow(clippy::cast_precision_loss)]
+        let points = record.wins as f64 + record.draws as f64 / 2.0;
+
+        points + byes
+    }
+
+    fn have_met(&self, a: usize, b: usize) -> bool {
+        let (a, b) = (contender_player(a), contender_player(b));
+        self.results.head_to_head(a, b) + self.results.head_to_head(b, a) > 0.0
+    }
+}
+
+fn contender_player(idx: usize) -> Player {
+    Player::new(seat_num(idx))
+}
+
+fn seat_num(idx: usize) -> u32 {
+    idx.try_into().expect("fewer than u32::MAX contenders")
+}
+
+/// Every way to choose `k` of `n` contenders, in lexicographic order
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    let mut all = Vec::new();
+
+    if k == 0 || k > n {
+        return all;
+    }
+
+    let mut current: Vec<usize> = (0..k).collect();
+
+    loop {
+        all.push(current.clone());
+
+        // Find the rightmost index that can still be incremented
+        let Some(i) = (0..k).rev().find(|&i| current[i] < n - k + i) else {
+            return all;
+        };
+
+        current[i] += 1;
+        for j in (i + 1)..k {
+            current[j] = current[j - 1] + 1;
+        }
+    }
+}
+
+/// Group contenders with similar standings into matches, returning the groups and the
+/// contenders who sit out this round
+fn swiss_pairings(report: &TournamentReport, seats: usize) -> (Vec<Vec<usize>>, Vec<usize>) {
+    let mut unpaired: Vec<usize> = report
+        .standings()
+        .into_iter()
+        .map(|standing| standing.contender)
+        .collect();
+    let mut groups = Vec::new();
+
+    while unpaired.len() >= seats {
+        let mut group = vec![unpaired.remove(0)];
+
+        while group.len() < seats {
+            // Prefer the best placed contender who hasn't met anyone in the group yet
+            let idx = unpaired
+                .iter()
+                .position(|&candidate| group.iter().all(|&m| !report.have_met(m, candidate)))
+                .unwrap_or(0);
+            group.push(unpaired.remove(idx));
+        }
+
+        groups.push(group);
+    }
+
+    (groups, unpaired)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lttcore::examples::{
+        tic_tac_toe::{
+            bot::prebuilt::{ExpertSkill, TicTacToePanicBot},
+            TicTacToeBot,
+        },
+        TicTacToe,
+    };
+
+    #[test]
+    fn test_combinations() {
+        assert_eq!(
+            combinations(4, 2),
+            vec![
+                vec![0, 1],
+                vec![0, 2],
+                vec![0, 3],
+                vec![1, 2],
+                vec![1, 3],
+                vec![2, 3]
+            ]
+        );
+        assert_eq!(combinations(2, 3), Vec::<Vec<usize>>::new());
+    }
+
+    #[test]
+    fn test_running_tournaments() {
+        let contenders: Vec<Contender<TicTacToe>> = vec![
+            Contender::new(TicTacToePanicBot),
+            Contender::new(ExpertSkill.into_bot()),
+            Contender::new_with_name(TicTacToePanicBot, "Another Panic Bot"),
+        ];
+
+        for (format, games) in [
+            (TournamentFormat::RoundRobin, 6),
+            (TournamentFormat::DoubleRoundRobin, 12),
+            (TournamentFormat::Swiss { rounds: 3 }, 6),
+        ] {
+            let tournament = TournamentBuilder::default()
+                .contenders(contenders.clone())
+                .format(format)
+                .games_per_match(2)
+                .build()
+                .unwrap();
+
+            let report = tournament.run();
+            let standings = report.standings();
+
+            assert_eq!(standings[0].name, "ExpertSkill");
+            assert_eq!(standings[0].contender, 1);
+
+            let total_games: u64 = report.results().records().map(|(_, r)| r.games).sum();
+            assert_eq!(total_games, games * 2, "{:?}", format);
+        }
+    }
+}
@@ -0,0 +1,40 @@
+DROP TABLE IF EXISTS peeps;
+
+CREATE TABLE custom_settings (
+	id TEXT PRIMARY KEY NOT NULL,
+	game_type TEXT NOT NULL,
+	name TEXT,
+	bytes BLOB NOT NULL,
+	owner TEXT,
+	created_at TEXT NOT NULL,
+	updated_at TEXT NOT NULL
+);
+
+CREATE TABLE game_progressions (
+	id TEXT PRIMARY KEY NOT NULL,
+	game_type TEXT NOT NULL,
+	seed BLOB NOT NULL,
+	builtin_settings TEXT,
+	custom_settings_id TEXT REFERENCES custom_settings (id),
+	turn_num INTEGER NOT NULL,
+	initial_state BLOB NOT NULL,
+	owner TEXT,
+	created_at TEXT NOT NULL,
+	updated_at TEXT NOT NULL,
+	CHECK ((builtin_settings IS NULL) <> (custom_settings_id IS NULL))
+);
+
+CREATE TABLE history_events (
+	game_id TEXT NOT NULL REFERENCES game_progressions (id) ON DELETE CASCADE,
+	turn_num INTEGER NOT NULL,
+	PRIMARY KEY (game_id, turn_num)
+);
+
+CREATE TABLE history_event_actions (
+	game_id TEXT NOT NULL,
+	turn_num INTEGER NOT NULL,
+	player INTEGER NOT NULL,
+	action BLOB NOT NULL,
+	PRIMARY KEY (game_id, turn_num, player),
+	FOREIGN KEY (game_id, turn_num) REFERENCES history_events (game_id, turn_num) ON DELETE CASCADE
+);
@@ -0,0 +1,5 @@
+ALTER TABLE game_progressions ADD COLUMN concluded BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE INDEX game_progressions_by_updated_at ON game_progressions (updated_at DESC, id);
+CREATE INDEX game_progressions_by_owner ON game_progressions (owner, updated_at DESC);
+CREATE INDEX game_progressions_by_game_type ON game_progressions (game_type, updated_at DESC);
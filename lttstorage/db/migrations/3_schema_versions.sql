@@ -0,0 +1,2 @@
+ALTER TABLE custom_settings ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE history_events ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
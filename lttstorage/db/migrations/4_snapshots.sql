@@ -0,0 +1,6 @@
+CREATE TABLE snapshots (
+	game_id TEXT NOT NULL REFERENCES game_progressions (id) ON DELETE CASCADE,
+	turn_num INTEGER NOT NULL,
+	game_state BLOB NOT NULL,
+	PRIMARY KEY (game_id, turn_num)
+);
@@ -0,0 +1 @@
+ALTER TABLE history_events ADD COLUMN hash BLOB;
@@ -0,0 +1,16 @@
+CREATE TABLE users (
+	id TEXT PRIMARY KEY NOT NULL,
+	username TEXT NOT NULL UNIQUE,
+	created_at TEXT NOT NULL
+);
+
+CREATE TABLE tokens (
+	id TEXT PRIMARY KEY NOT NULL,
+	hash BLOB NOT NULL UNIQUE,
+	user_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+	name TEXT NOT NULL,
+	created_at TEXT NOT NULL,
+	expires_at TEXT
+);
+
+CREATE INDEX tokens_by_user_id ON tokens (user_id, created_at);
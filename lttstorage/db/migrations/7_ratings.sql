@@ -0,0 +1,8 @@
+CREATE TABLE ratings (
+	user_id TEXT NOT NULL,
+	game_type TEXT NOT NULL,
+	mode TEXT NOT NULL,
+	rating REAL NOT NULL,
+	updated_at TEXT NOT NULL,
+	PRIMARY KEY (user_id, game_type, mode)
+);
@@ -0,0 +1,171 @@
+//! Users and the API tokens they log in with
+//!
+//! Tokens are only ever stored as a [`TokenHash`], so reading the database doesn't let anyone
+//! log in.
+use super::storage::StorageError;
+use async_trait::async_trait;
+use chrono::prelude::*;
+use lttcore::id::{TokenId, UserId};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredUser {
+    pub user_id: UserId,
+    /// Unique across users
+    pub username: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The SHA-256 hash of a token's secret
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct TokenHash(pub [u8; 32]);
+
+impl TokenHash {
+    pub fn of(secret: &[u8]) -> Self {
+        Self(Sha256::digest(secret).into())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredToken {
+    pub id: TokenId,
+    pub hash: TokenHash,
+    pub user_id: UserId,
+    /// Lets the user tell their tokens apart, like "laptop" or "tournament bot"
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    /// Tokens without an expiry are valid until they're revoked
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl StoredToken {
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+#[async_trait]
+pub trait AccountStorage: Send + Sync + 'static {
+    /// Add or update a user, returns [`StorageError::UsernameTaken`] if another user has the same
+    /// username
+    async fn write_user(&self, user: StoredUser) -> Result<(), StorageError>;
+
+    async fn read_user(&self, id: UserId) -> Result<StoredUser, StorageError>;
+
+    async fn read_user_by_username(&self, username: &str) -> Result<StoredUser, StorageError>;
+
+    /// Add a token, returns [`StorageError::NotFound`] if its user doesn't exist
+    async fn write_token(&self, token: StoredToken) -> Result<(), StorageError>;
+
+    async fn read_token_by_hash(&self, hash: &TokenHash) -> Result<StoredToken, StorageError>;
+
+    /// The user's tokens, oldest first
+    async fn list_tokens(&self, user_id: UserId) -> Result<Vec<StoredToken>, StorageError>;
+
+    /// Remove a token so it can't be used anymore
+    async fn delete_token(&self, id: TokenId) -> Result<(), StorageError>;
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::db::hash_map_db::HashMapDB;
+    use chrono::Duration;
+    use lttcore::encoding::Encoding;
+
+    /// Exercise every [`AccountStorage`] method of a backend
+    pub(crate) async fn test_accounts(db: &impl AccountStorage) -> Result<(), StorageError> {
+        let now = Utc::now();
+        let alice = StoredUser {
+            user_id: UserId::new(),
+            username: "alice".to_owned(),
+            created_at: now,
+        };
+        db.write_user(alice.clone()).await?;
+        assert_eq!(db.read_user(alice.user_id).await?, alice);
+        assert_eq!(db.read_user_by_username("alice").await?, alice);
+        assert!(matches!(
+            db.read_user_by_username("bob").await,
+            Err(StorageError::NotFound)
+        ));
+
+        let imposter = StoredUser {
+            user_id: UserId::new(),
+            ..alice.clone()
+        };
+        assert!(matches!(
+            db.write_user(imposter).await,
+            Err(StorageError::UsernameTaken(username)) if username == "alice"
+        ));
+
+        let tokens: Vec<StoredToken> = ["laptop", "bot"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| StoredToken {
+                id: TokenId::new(),
+                hash: TokenHash::of(name.as_bytes()),
+                user_id: alice.user_id,
+                name: name.to_owned(),
+                created_at: now + Duration::seconds(i as i64),
+                expires_at: Some(now + Duration::days(30)),
+            })
+            .collect();
+        for token in &tokens {
+            db.write_token(token.clone()).await?;
+        }
+        assert_eq!(db.read_token_by_hash(&tokens[1].hash).await?, tokens[1]);
+        assert_eq!(db.list_tokens(alice.user_id).await?, tokens);
+
+        let orphan = StoredToken {
+            id: TokenId::new(),
+            hash: TokenHash::of(b"orphan"),
+            user_id: UserId::new(),
+            ..tokens[0].clone()
+        };
+        assert!(matches!(
+            db.write_token(orphan).await,
+            Err(StorageError::NotFound)
+        ));
+
+        db.delete_token(tokens[0].id).await?;
+        assert!(matches!(
+            db.read_token_by_hash(&tokens[0].hash).await,
+            Err(StorageError::NotFound)
+        ));
+        assert_eq!(
+            db.list_tokens(alice.user_id).await?,
+            vec![tokens[1].clone()]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_accounts_in_a_hash_map_db() {
+        test_accounts(&HashMapDB::new(Encoding::Bincode))
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_tokens_expire() {
+        let now = Utc::now();
+        let token = StoredToken {
+            id: TokenId::new(),
+            hash: TokenHash::of(b"secret"),
+            user_id: UserId::new(),
+            name: "laptop".to_owned(),
+            created_at: now,
+            expires_at: None,
+        };
+        assert!(!token.is_expired(now));
+
+        let token = StoredToken {
+            expires_at: Some(now),
+            ..token
+        };
+        assert!(token.is_expired(now));
+        assert!(!token.is_expired(now - Duration::seconds(1)));
+    }
+}
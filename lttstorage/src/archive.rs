@@ -0,0 +1,434 @@
+//! Portable bundles of stored games
+//!
+//! An [`Archive`] holds raw game progressions along with the custom settings they use and their
+//! metadata, so they can be moved between backends or shared as a file.
+//!
+//! ```text
+//! LTT-ARCHIVE {"format_version":1,"encoding":"Bincode"}
+//! <custom settings and games, encoded with the header's encoding>
+//! ```
+//!
+//! The header is always JSON so the archive can be decoded without knowing how it was written.
+//! The settings, states and actions inside the archive are left in the encoding of the backend
+//! they were exported from, which is the one named by the header. Importing into a backend using
+//! a different encoding needs the archive to be [transcoded](Archive::transcode) first.
+use super::query::{GameQuery, Page};
+use super::raw_storage::{
+    RawCustomSettings, RawGameProgression, RawHistoryEvent, RawSnapshot, RawStorage,
+};
+use super::storage::{decode_versioned, MetaData, SettingsType, StorageError};
+use bytes::{BufMut, Bytes, BytesMut};
+use lttcore::{
+    encoding::Encoding,
+    id::{GameId, SettingsId},
+    play::{ActionResponse, GameState, Play},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// The version of the archive layout written by [`Archive::to_bytes`]
+pub const FORMAT_VERSION: u32 = 1;
+
+const MAGIC: &[u8] = b"LTT-ARCHIVE ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Header {
+    format_version: u32,
+    encoding: Encoding,
+}
+
+#[derive(Serialize)]
+struct BodyRef<'a> {
+    custom_settings: &'a [(SettingsId, RawCustomSettings, MetaData)],
+    games: &'a [(GameId, RawGameProgression, MetaData)],
+}
+
+#[derive(Deserialize)]
+struct Body {
+    custom_settings: Vec<(SettingsId, RawCustomSettings, MetaData)>,
+    games: Vec<(GameId, RawGameProgression, MetaData)>,
+}
+
+/// Stored games and the custom settings they point at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Archive {
+    /// The encoding of the settings, states and actions in the archive
+    pub encoding: Encoding,
+    pub custom_settings: Vec<(SettingsId, RawCustomSettings, MetaData)>,
+    pub games: Vec<(GameId, RawGameProgression, MetaData)>,
+}
+
+impl Archive {
+    /// An archive with nothing in it
+    pub fn new(encoding: Encoding) -> Self {
+        Self {
+            encoding,
+            custom_settings: Vec::new(),
+            games: Vec::new(),
+        }
+    }
+
+    /// Export the games with the given ids, and their custom settings
+    pub async fn export(
+        db: &impl RawStorage,
+        ids: impl IntoIterator<Item = GameId>,
+    ) -> Result<Self, StorageError> {
+        let mut archive = Self::new(db.encoding());
+        let mut settings_ids = BTreeSet::new();
+
+        for id in ids {
+            let game = db.read_raw_game_progression(id).await?;
+            if let SettingsType::Custom(settings_id) = game.1.settings {
+                if settings_ids.insert(settings_id) {
+                    let settings = db.read_raw_custom_settings(settings_id).await?;
+                    archive.custom_settings.push(settings);
+                }
+            }
+            archive.games.push(game);
+        }
+
+        Ok(archive)
+    }
+
+    /// Export every game matching the query, from its offset on, following the pages of results
+    pub async fn export_matching(
+        db: &impl RawStorage,
+        query: &GameQuery,
+    ) -> Result<Self, StorageError> {
+        let mut ids = Vec::new();
+        let mut next = Some(query.clone());
+
+        while let Some(query) = next {
+            let Page { games, next: more } = db.list_raw_game_progressions(&query).await?;
+            ids.extend(games.into_iter().map(|listing| listing.id));
+            next = more;
+        }
+
+        Self::export(db, ids).await
+    }
+
+    /// Write everything in the archive to `db`, replacing any records with the same ids
+    ///
+    /// Returns [`StorageError::EncodingMismatch`] if `db` doesn't use the archive's encoding
+    pub async fn import(&self, db: &impl RawStorage) -> Result<(), StorageError> {
+        if db.encoding() != self.encoding {
+            return Err(StorageError::EncodingMismatch {
+                expected: db.encoding(),
+                found: self.encoding,
+            });
+        }
+
+        // Custom settings go first so the games' references to them are valid
+        for settings in &self.custom_settings {
+            db.write_raw_custom_settings(settings.clone()).await?;
+        }
+
+        for game in &self.games {
+            db.write_raw_game_progression(game.clone()).await?;
+        }
+
+        Ok(())
+    }
+
+    /// The game types of the games in the archive
+    pub fn game_types(&self) -> BTreeSet<&str> {
+        self.games
+            .iter()
+            .map(|(_, game, _)| game.game_type.as_str())
+            .collect()
+    }
+
+    /// Re-encode an archive of `T` games with another encoding, upgrading any settings and
+    /// actions stored by older versions of `T` on the way
+    ///
+    /// Returns [`StorageError::WrongGameType`] if the archive holds anything but `T`s
+    pub fn transcode<T: Play>(self, encoding: Encoding) -> Result<Self, StorageError> {
+        let from = self.encoding;
+        let check_game_type = |game_type: &str| {
+            if game_type == T::lib_table_top_identifier() {
+                Ok(())
+            } else {
+                Err(StorageError::WrongGameType {
+                    expected: String::from(T::lib_table_top_identifier()),
+                    found: game_type.to_owned(),
+                })
+            }
+        };
+
+        let custom_settings = self
+            .custom_settings
+            .into_iter()
+            .map(|(id, raw, meta)| {
+                check_game_type(&raw.game_type)?;
+                let settings: T::Settings =
+                    decode_versioned::<T, _>(from, raw.version, &raw.bytes, T::migrate_settings)?;
+                let raw = RawCustomSettings {
+                    bytes: encode(encoding, &settings)?,
+                    version: T::SCHEMA_VERSION,
+                    ..raw
+                };
+                Ok((id, raw, meta))
+            })
+            .collect::<Result<_, StorageError>>()?;
+
+        let games = self
+            .games
+            .into_iter()
+            .map(|(id, raw, meta)| {
+                check_game_type(&raw.game_type)?;
+                let snapshots = raw
+                    .snapshots
+                    .into_iter()
+                    .map(|snapshot| {
+                        Ok(RawSnapshot {
+                            game_state: transcode_game_state::<T>(
+                                &snapshot.game_state,
+                                from,
+                                encoding,
+                            )?,
+                            ..snapshot
+                        })
+                    })
+                    .collect::<Result<_, StorageError>>()?;
+                let history_events = raw
+                    .history_events
+                    .into_iter()
+                    .map(|event| transcode_history_event::<T>(event, from, encoding))
+                    .collect::<Result<_, _>>()?;
+
+                let raw = RawGameProgression {
+                    initial_state: transcode_game_state::<T>(&raw.initial_state, from, encoding)?,
+                    history_events,
+                    snapshots,
+                    ..raw
+                };
+                Ok((id, raw, meta))
+            })
+            .collect::<Result<_, StorageError>>()?;
+
+        Ok(Self {
+            encoding,
+            custom_settings,
+            games,
+        })
+    }
+
+    /// Write the archive in the portable file format
+    pub fn to_bytes(&self) -> Result<Bytes, StorageError> {
+        let header = Encoding::Json
+            .serialize(&Header {
+                format_version: FORMAT_VERSION,
+                encoding: self.encoding,
+            })
+            .map_err(StorageError::EncodingError)?;
+        let body = encode(
+            self.encoding,
+            &BodyRef {
+                custom_settings: &self.custom_settings,
+                games: &self.games,
+            },
+        )?;
+
+        let mut bytes = BytesMut::with_capacity(MAGIC.len() + header.len() + 1 + body.len());
+        bytes.put_slice(MAGIC);
+        bytes.put_slice(&header);
+        bytes.put_u8(b'\n');
+        bytes.put_slice(&body);
+        Ok(bytes.freeze())
+    }
+
+    /// Read an archive written by [`Archive::to_bytes`]
+    pub fn from_bytes(bytes: &Bytes) -> Result<Self, StorageError> {
+        let invalid = |reason: &str| StorageError::InvalidArchive(reason.to_owned());
+
+        if !bytes.starts_with(MAGIC) {
+            return Err(invalid("missing archive header"));
+        }
+        let rest = bytes.slice(MAGIC.len()..);
+        let newline = rest
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .ok_or_else(|| invalid("unterminated archive header"))?;
+
+        let header: Header = Encoding::Json
+            .deserialize(&rest.slice(..newline))
+            .map_err(|_| invalid("unreadable archive header"))?;
+        if header.format_version != FORMAT_VERSION {
+            return Err(StorageError::InvalidArchive(format!(
+                "unsupported archive format version {}",
+                header.format_version
+            )));
+        }
+
+        let body: Body = header
+            .encoding
+            .deserialize(&rest.slice(newline + 1..))
+            .map_err(StorageError::EncodingError)?;
+
+        Ok(Self {
+            encoding: header.encoding,
+            custom_settings: body.custom_settings,
+            games: body.games,
+        })
+    }
+}
+
+fn encode(encoding: Encoding, value: &impl Serialize) -> Result<Bytes, StorageError> {
+    encoding
+        .serialize(value)
+        .map_err(StorageError::EncodingError)
+}
+
+fn transcode_game_state<T: Play>(
+    bytes: &Bytes,
+    from: Encoding,
+    to: Encoding,
+) -> Result<Bytes, StorageError> {
+    let game_state: GameState<T> = from
+        .deserialize(bytes)
+        .map_err(StorageError::EncodingError)?;
+    encode(to, &game_state)
+}
+
+fn transcode_history_event<T: Play>(
+    event: RawHistoryEvent,
+    from: Encoding,
+    to: Encoding,
+) -> Result<RawHistoryEvent, StorageError> {
+    let actions = event
+        .actions
+        .iter()
+        .map(|(player, bytes)| {
+            let action: ActionResponse<T> =
+                decode_versioned::<T, _>(from, event.version, bytes, T::migrate_action_response)?;
+            encode(to, &action).map(|bytes| (player, bytes))
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(RawHistoryEvent {
+        actions,
+        version: T::SCHEMA_VERSION,
+        ..event
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{hash_map_db::HashMapDB, sqlite_db::SqliteDB},
+        storage::Storage,
+    };
+    use chrono::Utc;
+    use lttcore::{
+        examples::{
+            guess_the_number::{Guess, Settings as GuessTheNumberSettings},
+            GuessTheNumber, TicTacToe,
+        },
+        play::{seed::SEED_42, ActionResponse::Response},
+        pov::game_progression::GameProgression,
+    };
+
+    fn meta() -> MetaData {
+        MetaData {
+            owner: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn played_game() -> GameProgression<GuessTheNumber> {
+        let settings = GuessTheNumberSettings::try_from(1..=5).unwrap();
+        let mut game = GameProgression::from_settings_and_seed(settings, SEED_42);
+        let actions = game
+            .which_players_input_needed()
+            .map(|player| (player, Response(Guess(3))))
+            .collect();
+        let update = game.resolve(actions);
+        game.update(update);
+        game
+    }
+
+    #[tokio::test]
+    async fn test_archives_round_trip_between_backends() {
+        let source = HashMapDB::new(Encoding::Bincode);
+        let (first, second) = (GameId::new(), GameId::new());
+        let game = played_game();
+        source
+            .write_game_progression((first, game.clone(), meta()))
+            .await
+            .unwrap();
+        source
+            .write_game_progression((second, game.clone(), meta()))
+            .await
+            .unwrap();
+
+        let archive = Archive::export_matching(&source, &GameQuery::default().limit(1))
+            .await
+            .unwrap();
+        assert_eq!(archive.games.len(), 2);
+        assert_eq!(archive.custom_settings.len(), 2);
+        assert_eq!(archive.game_types(), BTreeSet::from(["GuessTheNumber"]));
+
+        let read = Archive::from_bytes(&archive.to_bytes().unwrap()).unwrap();
+        assert_eq!(read, archive);
+
+        let target = SqliteDB::in_memory(Encoding::Bincode).await.unwrap();
+        read.import(&target).await.unwrap();
+        for id in [first, second] {
+            let (_, imported, _) = Storage::<GuessTheNumber>::read_game_progression(&target, id)
+                .await
+                .unwrap();
+            assert_eq!(imported, game);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transcoding_archives() {
+        let source = HashMapDB::new(Encoding::Bincode);
+        let id = GameId::new();
+        let game = played_game();
+        source
+            .write_game_progression((id, game.clone(), meta()))
+            .await
+            .unwrap();
+        let archive = Archive::export(&source, [id]).await.unwrap();
+
+        let target = HashMapDB::new(Encoding::Json);
+        assert!(matches!(
+            archive.import(&target).await,
+            Err(StorageError::EncodingMismatch {
+                expected: Encoding::Json,
+                found: Encoding::Bincode
+            })
+        ));
+        assert!(matches!(
+            archive.clone().transcode::<TicTacToe>(Encoding::Json),
+            Err(StorageError::WrongGameType { .. })
+        ));
+
+        let transcoded = archive.transcode::<GuessTheNumber>(Encoding::Json).unwrap();
+        transcoded.import(&target).await.unwrap();
+        let (_, imported, _) = Storage::<GuessTheNumber>::read_game_progression(&target, id)
+            .await
+            .unwrap();
+        assert_eq!(imported, game);
+    }
+
+    #[test]
+    fn test_reading_invalid_archives() {
+        let archive = Archive::new(Encoding::Json).to_bytes().unwrap();
+        assert!(archive.starts_with(b"LTT-ARCHIVE {\"format_version\":1,\"encoding\":\"Json\"}\n"));
+
+        for bytes in [
+            Bytes::from_static(b"not an archive"),
+            Bytes::from_static(b"LTT-ARCHIVE {\"format_version\":1"),
+            Bytes::from_static(b"LTT-ARCHIVE {\"format_version\":2,\"encoding\":\"Json\"}\n{}"),
+        ] {
+            assert!(matches!(
+                Archive::from_bytes(&bytes),
+                Err(StorageError::InvalidArchive(_))
+            ));
+        }
+    }
+}
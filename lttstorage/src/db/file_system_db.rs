@@ -0,0 +1,962 @@
+use crate::{
+    accounts::{AccountStorage, StoredToken, StoredUser, TokenHash},
+    hash_chain::HistoryKey,
+    query::{GameListing, GameQuery, Page},
+    ratings::{RatingStorage, StoredRating},
+    raw_storage::{
+        check_append, RawCustomSettings, RawGameProgression, RawHistoryEvent, RawSnapshot,
+        RawStorage, DEFAULT_SNAPSHOT_INTERVAL,
+    },
+    storage::{MetaData, StorageError},
+};
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use lttcore::{
+    encoding::Encoding,
+    id::{GameId, SettingsId, TokenId, UserId},
+    play::TurnNum,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tokio::{fs, io::AsyncWriteExt, sync::Mutex};
+use uuid::Uuid;
+
+const CUSTOM_SETTINGS_DIR: &str = "custom_settings";
+const GAME_PROGRESSIONS_DIR: &str = "game_progressions";
+const INDEX_FILE: &str = "index";
+const ACCOUNTS_FILE: &str = "accounts";
+const RATINGS_FILE: &str = "ratings";
+const LOG_EXTENSION: &str = "log";
+/// Starts the extension of files being written, before they're renamed over the old file
+const TMP_EXTENSION_PREFIX: &str = "tmp-";
+
+/// [`RawStorage`] keeping every record in its own file under a root directory
+///
+/// ```text
+/// root/
+///   index.json
+///   accounts.json
+///   ratings.json
+///   custom_settings/<SettingsId>.json
+///   game_progressions/<GameId>.json
+///   game_progressions/<GameId>.<LogId>.log
+/// ```
+///
+/// Files are written in the configured [`Encoding`] (`.bin` for bincode and `.json`
+/// otherwise) by writing a temporary file and renaming it over the old one, so readers never see
+/// a half written record. Turns and snapshots recorded after a game was written are appended to
+/// the game's log instead, so recording a turn doesn't rewrite the game. Writing the whole game
+/// again starts a new log. Logs hold one JSON record per line with the JSON encodings, and
+/// length prefixed records with bincode. Temporary files left behind by a crash are removed when
+/// opening the database.
+///
+/// The index holds a [`GameListing`] for every game so listings don't need to read every game.
+/// It is kept in memory and only written along with whole games, so when opening the database
+/// every game whose files changed since the index was written is read again. The index can also
+/// be rebuilt from the game files with [`FileSystemDB::rebuild_index`].
+pub struct FileSystemDB {
+    encoding: Encoding,
+    snapshot_interval: u64,
+    history_key: Option<HistoryKey>,
+    root: PathBuf,
+    /// Also serializes writes, so the files and the index always match the last write
+    index: Mutex<BTreeMap<GameId, IndexEntry>>,
+}
+
+/// The stored form of a record, alongside the metadata of the record
+type Record<T> = (T, MetaData);
+
+/// The stored form of a game, `log` names the log of what was recorded after it
+#[derive(Debug, Serialize, Deserialize)]
+struct GameRecord {
+    raw: RawGameProgression,
+    meta: MetaData,
+    log: Uuid,
+}
+
+/// Something recorded about a game after it was written
+#[derive(Debug, Serialize, Deserialize)]
+enum LogEntry {
+    HistoryEvent {
+        event: Box<RawHistoryEvent>,
+        concluded: bool,
+        updated_at: DateTime<Utc>,
+    },
+    Snapshot(RawSnapshot),
+}
+
+/// What the index knows about a game, enough to append to it without reading it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexEntry {
+    listing: GameListing,
+    log: Uuid,
+    turn_num: TurnNum,
+    latest: Option<TurnNum>,
+    /// The latest history event, once it has been read or appended
+    #[serde(skip)]
+    latest_event: Option<RawHistoryEvent>,
+}
+
+/// Every user and token, kept in a single file since there are few of them
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Accounts {
+    users: Vec<StoredUser>,
+    tokens: Vec<StoredToken>,
+}
+
+impl FileSystemDB {
+    /// Open the database under `root`, creating the directory if it doesn't exist yet
+    pub async fn open(root: impl Into<PathBuf>, encoding: Encoding) -> Result<Self, StorageError> {
+        let root = root.into();
+        fs::create_dir_all(root.join(CUSTOM_SETTINGS_DIR))
+            .await
+            .map_err(StorageError::Io)?;
+        fs::create_dir_all(root.join(GAME_PROGRESSIONS_DIR))
+            .await
+            .map_err(StorageError::Io)?;
+
+        let db = Self {
+            encoding,
+            snapshot_interval: DEFAULT_SNAPSHOT_INTERVAL,
+            history_key: None,
+            root,
+            index: Mutex::new(BTreeMap::new()),
+        };
+        db.remove_tmp_files().await?;
+
+        // A missing or unreadable index is rebuilt from the game files
+        let stored: Option<Vec<IndexEntry>> = match db.read_file(&db.index_path()).await {
+            Err(StorageError::EncodingError(_)) => None,
+            result => result?,
+        };
+        let written_at = match stored {
+            Some(_) => modified(&db.index_path()).await?,
+            None => None,
+        };
+        let mut stored: BTreeMap<GameId, IndexEntry> = stored
+            .unwrap_or_default()
+            .into_iter()
+            .map(|entry| (entry.listing.id, entry))
+            .collect();
+
+        let mut index = BTreeMap::new();
+        let mut changed = false;
+        for id in db.game_ids().await? {
+            let entry = match (stored.remove(&id), written_at) {
+                (Some(entry), Some(written_at))
+                    if !db.changed_since(&entry, written_at).await? =>
+                {
+                    Some(entry)
+                }
+                _ => {
+                    changed = true;
+                    db.read_index_entry(id).await?
+                }
+            };
+            index.extend(entry.map(|entry| (id, entry)));
+        }
+
+        if changed || !stored.is_empty() {
+            db.write_index(&index).await?;
+        }
+
+        *db.index.lock().await = index;
+        Ok(db)
+    }
+
+    /// Keep snapshots of games every `turns` turns instead of [`DEFAULT_SNAPSHOT_INTERVAL`], `0`
+    /// for never
+    pub fn with_snapshot_interval(mut self, turns: u64) -> Self {
+        self.snapshot_interval = turns;
+        self
+    }
+
+    /// HMAC the hash chain of games with `key`, see [`hash_chain`](crate::hash_chain)
+    pub fn with_history_key(mut self, key: HistoryKey) -> Self {
+        self.history_key = Some(key);
+        self
+    }
+
+    /// Rebuild the index by reading every stored game, useful after editing files by hand
+    pub async fn rebuild_index(&self) -> Result<(), StorageError> {
+        let mut index = self.index.lock().await;
+        index.clear();
+
+        for id in self.game_ids().await? {
+            if let Some(entry) = self.read_index_entry(id).await? {
+                index.insert(id, entry);
+            }
+        }
+
+        self.write_index(&index).await
+    }
+
+    /// Log entries are one per line with the JSON encodings, so they're never pretty printed
+    fn log_encoding(&self) -> Encoding {
+        match self.encoding {
+            Encoding::PrettyJson => Encoding::Json,
+            encoding => encoding,
+        }
+    }
+
+    /// Split the first entry off a log, `None` if there isn't a whole one
+    fn split_log_entry<'a>(&self, bytes: &'a [u8]) -> Option<(&'a [u8], &'a [u8])> {
+        match self.encoding {
+            Encoding::Bincode => {
+                let (len, rest) = bytes.split_first_chunk::<4>()?;
+                let len = usize::try_from(u32::from_le_bytes(*len)).ok()?;
+                (rest.len() >= len).then(|| rest.split_at(len))
+            }
+            Encoding::Json | Encoding::PrettyJson => {
+                let end = bytes.iter().position(|byte| *byte == b'\n')?;
+                Some((&bytes[..end], &bytes[end + 1..]))
+            }
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self.encoding {
+            Encoding::Bincode => "bin",
+            Encoding::Json | Encoding::PrettyJson => "json",
+        }
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.root.join(INDEX_FILE).with_extension(self.extension())
+    }
+
+    fn accounts_path(&self) -> PathBuf {
+        self.root
+            .join(ACCOUNTS_FILE)
+            .with_extension(self.extension())
+    }
+
+    fn ratings_path(&self) -> PathBuf {
+        self.root
+            .join(RATINGS_FILE)
+            .with_extension(self.extension())
+    }
+
+    async fn read_accounts(&self) -> Result<Accounts, StorageError> {
+        Ok(self
+            .read_file(&self.accounts_path())
+            .await?
+            .unwrap_or_default())
+    }
+
+    fn custom_settings_path(&self, id: SettingsId) -> PathBuf {
+        self.root
+            .join(CUSTOM_SETTINGS_DIR)
+            .join(Uuid::from(id).to_string())
+            .with_extension(self.extension())
+    }
+
+    fn game_progression_path(&self, id: GameId) -> PathBuf {
+        self.root
+            .join(GAME_PROGRESSIONS_DIR)
+            .join(Uuid::from(id).to_string())
+            .with_extension(self.extension())
+    }
+
+    fn log_path(&self, id: GameId, log: Uuid) -> PathBuf {
+        self.root.join(GAME_PROGRESSIONS_DIR).join(format!(
+            "{}.{}.{}",
+            Uuid::from(id),
+            log,
+            LOG_EXTENSION
+        ))
+    }
+
+    /// The ids of every stored game, in order
+    async fn game_ids(&self) -> Result<Vec<GameId>, StorageError> {
+        let mut ids = Vec::new();
+        let mut entries = fs::read_dir(self.root.join(GAME_PROGRESSIONS_DIR))
+            .await
+            .map_err(StorageError::Io)?;
+
+        while let Some(entry) = entries.next_entry().await.map_err(StorageError::Io)? {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(self.extension()) {
+                continue;
+            }
+
+            let id = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| Uuid::parse_str(stem).ok());
+            ids.extend(id.map(GameId::from));
+        }
+
+        ids.sort();
+        Ok(ids)
+    }
+
+    /// Whether the game's files were written since `written_at`. Files written in the same
+    /// instant count as changed, since file times can be coarser than the writes
+    async fn changed_since(
+        &self,
+        entry: &IndexEntry,
+        written_at: SystemTime,
+    ) -> Result<bool, StorageError> {
+        for path in [
+            self.game_progression_path(entry.listing.id),
+            self.log_path(entry.listing.id, entry.log),
+        ] {
+            if modified(&path).await?.is_some_and(|at| at >= written_at) {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Read a game from its file and log, `None` if it doesn't exist
+    async fn read_game(&self, id: GameId) -> Result<Option<GameRecord>, StorageError> {
+        let Some(mut record) = self
+            .read_file::<GameRecord>(&self.game_progression_path(id))
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let log_path = self.log_path(id, record.log);
+        let bytes = match fs::read(&log_path).await {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(StorageError::Io(err)),
+        };
+
+        let mut rest = bytes.as_slice();
+        while let Some((record_bytes, next)) = self.split_log_entry(rest) {
+            match self
+                .log_encoding()
+                .deserialize(&Bytes::copy_from_slice(record_bytes))
+                .map_err(StorageError::EncodingError)?
+            {
+                LogEntry::HistoryEvent {
+                    event,
+                    concluded,
+                    updated_at,
+                } => {
+                    record.raw.history_events.push(*event);
+                    record.raw.concluded = concluded;
+                    record.meta.updated_at = updated_at;
+                }
+                LogEntry::Snapshot(snapshot) => record.raw.insert_snapshot(snapshot),
+            }
+            rest = next;
+        }
+
+        // Drop what's left of an append cut short by a crash, so later appends can be read
+        if !rest.is_empty() {
+            let file = fs::OpenOptions::new()
+                .write(true)
+                .open(&log_path)
+                .await
+                .map_err(StorageError::Io)?;
+            file.set_len((bytes.len() - rest.len()) as u64)
+                .await
+                .map_err(StorageError::Io)?;
+        }
+
+        Ok(Some(record))
+    }
+
+    async fn read_index_entry(&self, id: GameId) -> Result<Option<IndexEntry>, StorageError> {
+        Ok(self.read_game(id).await?.map(|record| {
+            let latest_event = record.raw.history_events.last().cloned();
+            IndexEntry {
+                listing: listing(id, &record.raw, record.meta),
+                log: record.log,
+                turn_num: record.raw.turn_num,
+                latest: latest_event.as_ref().map(|event| event.turn_num),
+                latest_event,
+            }
+        }))
+    }
+
+    /// Append an entry to the game's log, the game must be in the index
+    async fn append_log(
+        &self,
+        entry: &IndexEntry,
+        log_entry: &LogEntry,
+    ) -> Result<(), StorageError> {
+        let bytes = self
+            .log_encoding()
+            .serialize(log_entry)
+            .map_err(StorageError::EncodingError)?;
+
+        let mut frame = Vec::with_capacity(bytes.len() + 4);
+        match self.encoding {
+            Encoding::Bincode => {
+                let len = u32::try_from(bytes.len()).map_err(|err| {
+                    StorageError::Io(std::io::Error::new(ErrorKind::InvalidInput, err))
+                })?;
+                frame.extend_from_slice(&len.to_le_bytes());
+                frame.extend_from_slice(&bytes);
+            }
+            Encoding::Json | Encoding::PrettyJson => {
+                frame.extend_from_slice(&bytes);
+                frame.push(b'\n');
+            }
+        }
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.log_path(entry.listing.id, entry.log))
+            .await
+            .map_err(StorageError::Io)?;
+        file.write_all(&frame).await.map_err(StorageError::Io)?;
+        file.sync_data().await.map_err(StorageError::Io)
+    }
+
+    async fn write_index(&self, index: &BTreeMap<GameId, IndexEntry>) -> Result<(), StorageError> {
+        let entries: Vec<&IndexEntry> = index.values().collect();
+        self.write_file(&self.index_path(), &entries).await
+    }
+
+    /// Read and decode a file, returning `None` if it doesn't exist
+    async fn read_file<T: DeserializeOwned>(&self, path: &Path) -> Result<Option<T>, StorageError> {
+        match fs::read(path).await {
+            Ok(bytes) => self
+                .encoding
+                .deserialize(&bytes.into())
+                .map(Some)
+                .map_err(StorageError::EncodingError),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(StorageError::Io(err)),
+        }
+    }
+
+    /// Atomically replace the file at `path` with the encoded value
+    async fn write_file<T: Serialize>(&self, path: &Path, value: &T) -> Result<(), StorageError> {
+        let bytes = self
+            .encoding
+            .serialize(value)
+            .map_err(StorageError::EncodingError)?;
+
+        let tmp_path = path.with_extension(format!(
+            "{}.{}{}",
+            self.extension(),
+            TMP_EXTENSION_PREFIX,
+            Uuid::new_v4()
+        ));
+        let result = async {
+            let mut file = fs::File::create(&tmp_path).await?;
+            file.write_all(&bytes).await?;
+            file.sync_all().await?;
+            drop(file);
+            fs::rename(&tmp_path, path).await
+        }
+        .await;
+
+        if result.is_err() {
+            let _maybe_never_created = fs::remove_file(&tmp_path).await;
+        }
+        result.map_err(StorageError::Io)
+    }
+
+    /// Remove temporary files left behind by writes cut short by a crash
+    async fn remove_tmp_files(&self) -> Result<(), StorageError> {
+        for dir in [
+            self.root.clone(),
+            self.root.join(CUSTOM_SETTINGS_DIR),
+            self.root.join(GAME_PROGRESSIONS_DIR),
+        ] {
+            let mut entries = fs::read_dir(dir).await.map_err(StorageError::Io)?;
+            while let Some(entry) = entries.next_entry().await.map_err(StorageError::Io)? {
+                let path = entry.path();
+                let is_tmp = path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| ext.starts_with(TMP_EXTENSION_PREFIX));
+                if is_tmp {
+                    fs::remove_file(&path).await.map_err(StorageError::Io)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// When the file at `path` was last modified, `None` if it doesn't exist
+async fn modified(path: &Path) -> Result<Option<SystemTime>, StorageError> {
+    match fs::metadata(path).await {
+        Ok(metadata) => metadata.modified().map(Some).map_err(StorageError::Io),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(StorageError::Io(err)),
+    }
+}
+
+fn listing(id: GameId, raw: &RawGameProgression, meta: MetaData) -> GameListing {
+    GameListing {
+        id,
+        game_type: raw.game_type.clone(),
+        concluded: raw.concluded,
+        meta,
+    }
+}
+
+#[async_trait]
+impl RawStorage for FileSystemDB {
+    fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    async fn read_raw_custom_settings(
+        &self,
+        id: SettingsId,
+    ) -> Result<(SettingsId, RawCustomSettings, MetaData), StorageError> {
+        self.read_file::<Record<RawCustomSettings>>(&self.custom_settings_path(id))
+            .await?
+            .map(|(raw, meta)| (id, raw, meta))
+            .ok_or(StorageError::NotFound)
+    }
+
+    async fn read_raw_game_progression(
+        &self,
+        id: GameId,
+    ) -> Result<(GameId, RawGameProgression, MetaData), StorageError> {
+        // Hold the index lock so the log isn't read halfway through an append
+        let _index = self.index.lock().await;
+        self.read_game(id)
+            .await?
+            .map(|record| (id, record.raw, record.meta))
+            .ok_or(StorageError::NotFound)
+    }
+
+    async fn write_raw_custom_settings(
+        &self,
+        (id, raw, meta): (SettingsId, RawCustomSettings, MetaData),
+    ) -> Result<(), StorageError> {
+        let _index = self.index.lock().await;
+        self.write_file(&self.custom_settings_path(id), &(raw, meta))
+            .await
+    }
+
+    async fn write_raw_game_progression(
+        &self,
+        (id, raw, meta): (GameId, RawGameProgression, MetaData),
+    ) -> Result<(), StorageError> {
+        let mut index = self.index.lock().await;
+        let record = GameRecord {
+            raw,
+            meta,
+            log: Uuid::new_v4(),
+        };
+        self.write_file(&self.game_progression_path(id), &record)
+            .await?;
+
+        let latest_event = record.raw.history_events.last().cloned();
+        let entry = IndexEntry {
+            listing: listing(id, &record.raw, record.meta),
+            log: record.log,
+            turn_num: record.raw.turn_num,
+            latest: latest_event.as_ref().map(|event| event.turn_num),
+            latest_event,
+        };
+
+        if let Some(old) = index.insert(id, entry) {
+            match fs::remove_file(self.log_path(id, old.log)).await {
+                Err(err) if err.kind() != ErrorKind::NotFound => return Err(StorageError::Io(err)),
+                _ => {}
+            }
+        }
+
+        self.write_index(&index).await
+    }
+
+    async fn append_raw_history_event(
+        &self,
+        id: GameId,
+        event: RawHistoryEvent,
+        concluded: bool,
+    ) -> Result<(), StorageError> {
+        let mut index = self.index.lock().await;
+        let entry = index.get_mut(&id).ok_or(StorageError::NotFound)?;
+        check_append(entry.turn_num, entry.latest, &event)?;
+
+        let updated_at = Utc::now();
+        let log_entry = LogEntry::HistoryEvent {
+            event: Box::new(event.clone()),
+            concluded,
+            updated_at,
+        };
+        self.append_log(entry, &log_entry).await?;
+
+        entry.latest = Some(event.turn_num);
+        entry.latest_event = Some(event);
+        entry.listing.concluded = concluded;
+        entry.listing.meta.updated_at = updated_at;
+        Ok(())
+    }
+
+    async fn read_latest_raw_history_event(
+        &self,
+        id: GameId,
+    ) -> Result<Option<RawHistoryEvent>, StorageError> {
+        let mut index = self.index.lock().await;
+        let entry = index.get(&id).ok_or(StorageError::NotFound)?;
+        if entry.latest.is_none() || entry.latest_event.is_some() {
+            return Ok(entry.latest_event.clone());
+        }
+
+        let entry = self
+            .read_index_entry(id)
+            .await?
+            .ok_or(StorageError::NotFound)?;
+        let latest_event = entry.latest_event.clone();
+        index.insert(id, entry);
+        Ok(latest_event)
+    }
+
+    async fn write_raw_snapshot(
+        &self,
+        id: GameId,
+        snapshot: RawSnapshot,
+    ) -> Result<(), StorageError> {
+        let index = self.index.lock().await;
+        let entry = index.get(&id).ok_or(StorageError::NotFound)?;
+        self.append_log(entry, &LogEntry::Snapshot(snapshot)).await
+    }
+
+    fn snapshot_interval(&self) -> u64 {
+        self.snapshot_interval
+    }
+
+    fn history_key(&self) -> Option<&HistoryKey> {
+        self.history_key.as_ref()
+    }
+
+    async fn list_raw_game_progressions(&self, query: &GameQuery) -> Result<Page, StorageError> {
+        let index = self.index.lock().await;
+        Ok(query.apply(index.values().map(|entry| entry.listing.clone())))
+    }
+}
+
+#[async_trait]
+impl AccountStorage for FileSystemDB {
+    async fn write_user(&self, user: StoredUser) -> Result<(), StorageError> {
+        let _index = self.index.lock().await;
+        let mut accounts = self.read_accounts().await?;
+        if accounts
+            .users
+            .iter()
+            .any(|other| other.username == user.username && other.user_id != user.user_id)
+        {
+            return Err(StorageError::UsernameTaken(user.username));
+        }
+
+        accounts.users.retain(|other| other.user_id != user.user_id);
+        accounts.users.push(user);
+        self.write_file(&self.accounts_path(), &accounts).await
+    }
+
+    async fn read_user(&self, id: UserId) -> Result<StoredUser, StorageError> {
+        self.read_accounts()
+            .await?
+            .users
+            .into_iter()
+            .find(|user| user.user_id == id)
+            .ok_or(StorageError::NotFound)
+    }
+
+    async fn read_user_by_username(&self, username: &str) -> Result<StoredUser, StorageError> {
+        self.read_accounts()
+            .await?
+            .users
+            .into_iter()
+            .find(|user| user.username == username)
+            .ok_or(StorageError::NotFound)
+    }
+
+    async fn write_token(&self, token: StoredToken) -> Result<(), StorageError> {
+        let _index = self.index.lock().await;
+        let mut accounts = self.read_accounts().await?;
+        if !accounts
+            .users
+            .iter()
+            .any(|user| user.user_id == token.user_id)
+        {
+            return Err(StorageError::NotFound);
+        }
+
+        accounts.tokens.retain(|other| other.id != token.id);
+        accounts.tokens.push(token);
+        self.write_file(&self.accounts_path(), &accounts).await
+    }
+
+    async fn read_token_by_hash(&self, hash: &TokenHash) -> Result<StoredToken, StorageError> {
+        self.read_accounts()
+            .await?
+            .tokens
+            .into_iter()
+            .find(|token| token.hash == *hash)
+            .ok_or(StorageError::NotFound)
+    }
+
+    async fn list_tokens(&self, user_id: UserId) -> Result<Vec<StoredToken>, StorageError> {
+        let mut tokens: Vec<StoredToken> = self
+            .read_accounts()
+            .await?
+            .tokens
+            .into_iter()
+            .filter(|token| token.user_id == user_id)
+            .collect();
+        tokens.sort_by_key(|token| token.created_at);
+        Ok(tokens)
+    }
+
+    async fn delete_token(&self, id: TokenId) -> Result<(), StorageError> {
+        let _index = self.index.lock().await;
+        let mut accounts = self.read_accounts().await?;
+        let count = accounts.tokens.len();
+        accounts.tokens.retain(|token| token.id != id);
+        if accounts.tokens.len() == count {
+            return Err(StorageError::NotFound);
+        }
+
+        self.write_file(&self.accounts_path(), &accounts).await
+    }
+}
+
+#[async_trait]
+impl RatingStorage for FileSystemDB {
+    async fn write_ratings(&self, ratings: Vec<StoredRating>) -> Result<(), StorageError> {
+        let _index = self.index.lock().await;
+        let mut stored = self.read_ratings().await?;
+        stored.retain(|stored| !ratings.iter().any(|rating| rating.is_same_rating(stored)));
+        stored.extend(ratings);
+        self.write_file(&self.ratings_path(), &stored).await
+    }
+
+    async fn read_ratings(&self) -> Result<Vec<StoredRating>, StorageError> {
+        Ok(self
+            .read_file(&self.ratings_path())
+            .await?
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+    use lttcore::{
+        examples::{
+            tic_tac_toe::{Action, Position},
+            TicTacToe,
+        },
+        id::UserId,
+        play::{seed::SEED_42, ActionResponse::Response, SettingsPtr},
+        pov::game_progression::GameProgression,
+    };
+    use std::io::Write;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            Self(std::env::temp_dir().join(format!("lttstorage-{}", Uuid::new_v4())))
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_games_are_saved_to_disk() {
+        let dir = TempDir::new();
+        let db = FileSystemDB::open(&dir.0, Encoding::PrettyJson)
+            .await
+            .unwrap();
+        let id = GameId::new();
+        let meta = MetaData {
+            owner: Some(UserId::new()),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        let mut game: GameProgression<TicTacToe> =
+            GameProgression::from_settings_and_seed(SettingsPtr::default(), SEED_42);
+        db.write_game_progression((id, game.clone(), meta))
+            .await
+            .unwrap();
+
+        let player = game.which_players_input_needed().next().unwrap();
+        let action = Response(Action::from(Position::new(1, 1)));
+        let update = game.resolve([(player, action)].into_iter().collect());
+        game.update(update);
+        db.append_latest_turn(id, &game).await.unwrap();
+
+        let path = dir
+            .0
+            .join(GAME_PROGRESSIONS_DIR)
+            .join(format!("{}.json", Uuid::from(id)));
+        assert!(path.exists());
+
+        // Reopening loads the index back from disk
+        drop(db);
+        let db = FileSystemDB::open(&dir.0, Encoding::PrettyJson)
+            .await
+            .unwrap();
+        let (_, read, _) = Storage::<TicTacToe>::read_game_progression(&db, id)
+            .await
+            .unwrap();
+        assert_eq!(read, game);
+
+        let page = db
+            .list_raw_game_progressions(&GameQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(page.games.len(), 1);
+        assert_eq!(page.games[0].id, id);
+
+        // A game file copied in by hand is picked up when reopening
+        let copied = GameId::new();
+        std::fs::copy(
+            &path,
+            path.with_file_name(format!("{}.json", Uuid::from(copied))),
+        )
+        .unwrap();
+        drop(db);
+        let db = FileSystemDB::open(&dir.0, Encoding::PrettyJson)
+            .await
+            .unwrap();
+        let page = db
+            .list_raw_game_progressions(&GameQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(page.games.len(), 2);
+        assert!(page.games.iter().any(|game| game.id == copied));
+    }
+
+    #[tokio::test]
+    async fn test_games_are_brought_up_to_date_when_reopening() {
+        let dir = TempDir::new();
+        let db = FileSystemDB::open(&dir.0, Encoding::Json).await.unwrap();
+        let id = GameId::new();
+        let meta = MetaData {
+            owner: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        let mut game: GameProgression<TicTacToe> =
+            GameProgression::from_settings_and_seed(SettingsPtr::default(), SEED_42);
+        db.write_game_progression((id, game.clone(), meta))
+            .await
+            .unwrap();
+
+        let play = |game: &mut GameProgression<TicTacToe>, position| {
+            let player = game.which_players_input_needed().next().unwrap();
+            let action = Response(Action::from(position));
+            let update = game.resolve([(player, action)].into_iter().collect());
+            game.update(update);
+        };
+        play(&mut game, Position::new(1, 1));
+        db.append_latest_turn(id, &game).await.unwrap();
+        let listings = db
+            .list_raw_game_progressions(&GameQuery::default())
+            .await
+            .unwrap();
+
+        // Appending doesn't write the index, so it's behind the game's log when reopening
+        drop(db);
+        let db = FileSystemDB::open(&dir.0, Encoding::Json).await.unwrap();
+        let reopened = db
+            .list_raw_game_progressions(&GameQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(reopened, listings);
+
+        // Logs are JSON lines that can be read with normal tools
+        let log = std::fs::read_dir(dir.0.join(GAME_PROGRESSIONS_DIR))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .find(|path| path.extension().is_some_and(|ext| ext == LOG_EXTENSION))
+            .unwrap();
+        let contents = std::fs::read_to_string(&log).unwrap();
+        assert!(contents.ends_with('\n'));
+        for line in contents.lines() {
+            let line = Bytes::copy_from_slice(line.as_bytes());
+            Encoding::Json.deserialize::<LogEntry>(&line).unwrap();
+        }
+
+        // What's left of an append cut short by a crash is dropped, along with files that
+        // were being written
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&log)
+            .unwrap()
+            .write_all(br#"{"HistoryEv"#)
+            .unwrap();
+        let tmp = dir.0.join("index.json.tmp-0");
+        std::fs::write(&tmp, b"{").unwrap();
+        drop(db);
+        let db = FileSystemDB::open(&dir.0, Encoding::Json).await.unwrap();
+        assert!(!tmp.exists());
+
+        play(&mut game, Position::new(0, 0));
+        db.append_latest_turn(id, &game).await.unwrap();
+        let (_, read, _) = Storage::<TicTacToe>::read_game_progression(&db, id)
+            .await
+            .unwrap();
+        assert_eq!(read, game);
+    }
+
+    #[tokio::test]
+    async fn test_listing_games() {
+        let dir = TempDir::new();
+        let db = FileSystemDB::open(&dir.0, Encoding::Bincode).await.unwrap();
+        crate::query::tests::test_listing_games(&db).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_snapshots() {
+        // Logs are framed differently in binary and text encodings
+        for encoding in [Encoding::Json, Encoding::Bincode] {
+            let dir = TempDir::new();
+            let db = FileSystemDB::open(&dir.0, encoding)
+                .await
+                .unwrap()
+                .with_snapshot_interval(2);
+            crate::storage::tests::test_snapshots(&db).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_accounts() {
+        let dir = TempDir::new();
+        let db = FileSystemDB::open(&dir.0, Encoding::Json).await.unwrap();
+        crate::accounts::tests::test_accounts(&db).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_ratings() {
+        let dir = TempDir::new();
+        let db = FileSystemDB::open(&dir.0, Encoding::Json).await.unwrap();
+        crate::ratings::tests::test_ratings(&db).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_missing_records() {
+        let dir = TempDir::new();
+        let db = FileSystemDB::open(&dir.0, Encoding::Json).await.unwrap();
+
+        let result = Storage::<TicTacToe>::read_custom_settings(&db, SettingsId::new()).await;
+        assert!(matches!(result, Err(StorageError::NotFound)));
+
+        let result = Storage::<TicTacToe>::read_game_progression(&db, GameId::new()).await;
+        assert!(matches!(result, Err(StorageError::NotFound)));
+    }
+}
@@ -1,19 +1,60 @@
 use crate::{
-    raw_storage::{RawCustomSettings, RawGameProgression, RawStorage},
+    accounts::{AccountStorage, StoredToken, StoredUser, TokenHash},
+    hash_chain::HistoryKey,
+    query::{GameListing, GameQuery, Page},
+    ratings::{RatingStorage, StoredRating},
+    raw_storage::{
+        check_append, RawCustomSettings, RawGameProgression, RawHistoryEvent, RawSnapshot,
+        RawStorage, DEFAULT_SNAPSHOT_INTERVAL,
+    },
     storage::{MetaData, StorageError},
 };
 use async_trait::async_trait;
+use chrono::Utc;
 use lttcore::{
     encoding::Encoding,
-    id::{GameId, SettingsId},
+    id::{GameId, SettingsId, TokenId, UserId},
 };
 use std::collections::HashMap;
 use std::sync::RwLock;
 
 pub struct HashMapDB {
     encoding: Encoding,
+    snapshot_interval: u64,
+    history_key: Option<HistoryKey>,
     custom_settings: RwLock<HashMap<SettingsId, (RawCustomSettings, MetaData)>>,
     game_progression: RwLock<HashMap<GameId, (RawGameProgression, MetaData)>>,
+    users: RwLock<HashMap<UserId, StoredUser>>,
+    tokens: RwLock<HashMap<TokenId, StoredToken>>,
+    ratings: RwLock<Vec<StoredRating>>,
+}
+
+impl HashMapDB {
+    pub fn new(encoding: Encoding) -> Self {
+        Self {
+            encoding,
+            snapshot_interval: DEFAULT_SNAPSHOT_INTERVAL,
+            history_key: None,
+            custom_settings: RwLock::new(HashMap::new()),
+            game_progression: RwLock::new(HashMap::new()),
+            users: RwLock::new(HashMap::new()),
+            tokens: RwLock::new(HashMap::new()),
+            ratings: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Keep snapshots of games every `turns` turns instead of [`DEFAULT_SNAPSHOT_INTERVAL`], `0`
+    /// for never
+    pub fn with_snapshot_interval(mut self, turns: u64) -> Self {
+        self.snapshot_interval = turns;
+        self
+    }
+
+    /// HMAC the hash chain of games with `key`, see [`hash_chain`](crate::hash_chain)
+    pub fn with_history_key(mut self, key: HistoryKey) -> Self {
+        self.history_key = Some(key);
+        self
+    }
 }
 
 #[async_trait]
@@ -69,4 +110,172 @@ impl RawStorage for HashMapDB {
 
         Ok(())
     }
+
+    async fn append_raw_history_event(
+        &self,
+        id: GameId,
+        event: RawHistoryEvent,
+        concluded: bool,
+    ) -> Result<(), StorageError> {
+        let mut game_progression = self.game_progression.write().expect("rwlock isn't dead");
+        let (raw, meta) = game_progression
+            .get_mut(&id)
+            .ok_or(StorageError::NotFound)?;
+
+        let latest = raw.history_events.last().map(|event| event.turn_num);
+        check_append(raw.turn_num, latest, &event)?;
+        raw.history_events.push(event);
+        raw.concluded = concluded;
+        meta.updated_at = Utc::now();
+
+        Ok(())
+    }
+
+    async fn read_latest_raw_history_event(
+        &self,
+        id: GameId,
+    ) -> Result<Option<RawHistoryEvent>, StorageError> {
+        self.game_progression
+            .read()
+            .expect("rwlock isn't dead")
+            .get(&id)
+            .map(|(raw, _)| raw.history_events.last().cloned())
+            .ok_or(StorageError::NotFound)
+    }
+
+    async fn write_raw_snapshot(
+        &self,
+        id: GameId,
+        snapshot: RawSnapshot,
+    ) -> Result<(), StorageError> {
+        self.game_progression
+            .write()
+            .expect("rwlock isn't dead")
+            .get_mut(&id)
+            .ok_or(StorageError::NotFound)?
+            .0
+            .insert_snapshot(snapshot);
+
+        Ok(())
+    }
+
+    fn snapshot_interval(&self) -> u64 {
+        self.snapshot_interval
+    }
+
+    fn history_key(&self) -> Option<&HistoryKey> {
+        self.history_key.as_ref()
+    }
+
+    async fn list_raw_game_progressions(&self, query: &GameQuery) -> Result<Page, StorageError> {
+        let game_progression = self.game_progression.read().expect("rwlock isn't dead");
+        let listings = game_progression
+            .iter()
+            .map(|(id, (raw, meta))| GameListing {
+                id: *id,
+                game_type: raw.game_type.clone(),
+                concluded: raw.concluded,
+                meta: meta.clone(),
+            });
+
+        Ok(query.apply(listings))
+    }
+}
+
+#[async_trait]
+impl AccountStorage for HashMapDB {
+    async fn write_user(&self, user: StoredUser) -> Result<(), StorageError> {
+        let mut users = self.users.write().expect("rwlock isn't dead");
+        if users
+            .values()
+            .any(|other| other.username == user.username && other.user_id != user.user_id)
+        {
+            return Err(StorageError::UsernameTaken(user.username));
+        }
+
+        users.insert(user.user_id, user);
+        Ok(())
+    }
+
+    async fn read_user(&self, id: UserId) -> Result<StoredUser, StorageError> {
+        self.users
+            .read()
+            .expect("rwlock isn't dead")
+            .get(&id)
+            .cloned()
+            .ok_or(StorageError::NotFound)
+    }
+
+    async fn read_user_by_username(&self, username: &str) -> Result<StoredUser, StorageError> {
+        self.users
+            .read()
+            .expect("rwlock isn't dead")
+            .values()
+            .find(|user| user.username == username)
+            .cloned()
+            .ok_or(StorageError::NotFound)
+    }
+
+    async fn write_token(&self, token: StoredToken) -> Result<(), StorageError> {
+        if !self
+            .users
+            .read()
+            .expect("rwlock isn't dead")
+            .contains_key(&token.user_id)
+        {
+            return Err(StorageError::NotFound);
+        }
+
+        self.tokens
+            .write()
+            .expect("rwlock isn't dead")
+            .insert(token.id, token);
+        Ok(())
+    }
+
+    async fn read_token_by_hash(&self, hash: &TokenHash) -> Result<StoredToken, StorageError> {
+        self.tokens
+            .read()
+            .expect("rwlock isn't dead")
+            .values()
+            .find(|token| token.hash == *hash)
+            .cloned()
+            .ok_or(StorageError::NotFound)
+    }
+
+    async fn list_tokens(&self, user_id: UserId) -> Result<Vec<StoredToken>, StorageError> {
+        let mut tokens: Vec<StoredToken> = self
+            .tokens
+            .read()
+            .expect("rwlock isn't dead")
+            .values()
+            .filter(|token| token.user_id == user_id)
+            .cloned()
+            .collect();
+        tokens.sort_by_key(|token| token.created_at);
+        Ok(tokens)
+    }
+
+    async fn delete_token(&self, id: TokenId) -> Result<(), StorageError> {
+        self.tokens
+            .write()
+            .expect("rwlock isn't dead")
+            .remove(&id)
+            .map(drop)
+            .ok_or(StorageError::NotFound)
+    }
+}
+
+#[async_trait]
+impl RatingStorage for HashMapDB {
+    async fn write_ratings(&self, ratings: Vec<StoredRating>) -> Result<(), StorageError> {
+        let mut stored = self.ratings.write().expect("rwlock isn't dead");
+        stored.retain(|stored| !ratings.iter().any(|rating| rating.is_same_rating(stored)));
+        stored.extend(ratings);
+        Ok(())
+    }
+
+    async fn read_ratings(&self) -> Result<Vec<StoredRating>, StorageError> {
+        Ok(self.ratings.read().expect("rwlock isn't dead").clone())
+    }
 }
@@ -1 +1,3 @@
+pub mod file_system_db;
 pub mod hash_map_db;
+pub mod sqlite_db;
@@ -0,0 +1,897 @@
+use crate::{
+    accounts::{AccountStorage, StoredToken, StoredUser, TokenHash},
+    hash_chain::HistoryKey,
+    query::{GameListing, GameQuery, Page},
+    ratings::{RatingStorage, StoredRating},
+    raw_storage::{
+        check_append, RawCustomSettings, RawGameProgression, RawHistoryEvent, RawSnapshot,
+        RawStorage, DEFAULT_SNAPSHOT_INTERVAL,
+    },
+    storage::{MetaData, SettingsType, StorageError},
+};
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, SecondsFormat, Utc};
+use lttcore::{
+    encoding::Encoding,
+    id::{GameId, SettingsId, TokenId, UserId},
+    play::{Player, Seed, TurnNum},
+    utilities::PlayerIndexedData as PID,
+};
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions, SqliteRow},
+    Row, Sqlite, Transaction,
+};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// [`RawStorage`] backed by a SQLite database, either a local file or in memory
+///
+/// The schema lives in `db/migrations` and is brought up to date when connecting.
+pub struct SqliteDB {
+    encoding: Encoding,
+    snapshot_interval: u64,
+    history_key: Option<HistoryKey>,
+    pool: SqlitePool,
+}
+
+impl SqliteDB {
+    /// Connect to the database at `url`, which is either a path, a `sqlite://` url or
+    /// `:memory:`. Database files are created if they don't exist yet.
+    pub async fn connect(url: &str, encoding: Encoding) -> Result<Self, StorageError> {
+        let options = SqliteConnectOptions::from_str(url)
+            .map_err(StorageError::Database)?
+            .create_if_missing(true)
+            .foreign_keys(true);
+
+        // In memory databases disappear with their last connection, so the pool must always keep
+        // one open. Sharing it between connections also shares its locks, so only open one.
+        let is_in_memory = url
+            .trim_start_matches("sqlite://")
+            .trim_start_matches("sqlite:")
+            .starts_with(":memory:");
+        let pool_options = if is_in_memory {
+            SqlitePoolOptions::new().max_connections(1)
+        } else {
+            SqlitePoolOptions::new()
+        };
+
+        let pool = pool_options
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(options)
+            .await
+            .map_err(StorageError::Database)?;
+
+        sqlx::migrate!("./db/migrations")
+            .run(&pool)
+            .await
+            .map_err(|err| StorageError::Database(err.into()))?;
+
+        Ok(Self {
+            encoding,
+            snapshot_interval: DEFAULT_SNAPSHOT_INTERVAL,
+            history_key: None,
+            pool,
+        })
+    }
+
+    /// Connect to a new, empty, in memory database
+    pub async fn in_memory(encoding: Encoding) -> Result<Self, StorageError> {
+        Self::connect(":memory:", encoding).await
+    }
+
+    /// Read the history of a game in turn order, or just the event of `turn_num` if given
+    async fn read_history_events(
+        &self,
+        id: GameId,
+        turn_num: Option<i64>,
+    ) -> Result<Vec<RawHistoryEvent>, StorageError> {
+        let mut history: BTreeMap<i64, RawHistoryEvent> = BTreeMap::new();
+
+        let events = sqlx::query(
+            "SELECT turn_num, version, hash FROM history_events
+             WHERE game_id = ?1 AND (?2 IS NULL OR turn_num = ?2)",
+        )
+        .bind(uuid_to_sql(id))
+        .bind(turn_num)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(StorageError::Database)?;
+        for event in events {
+            let hash = get::<Option<Vec<u8>>>(&event, "hash")?
+                .map(|hash| hash.as_slice().try_into().map_err(decode_error))
+                .transpose()?;
+            let turn_num = get(&event, "turn_num")?;
+            history.insert(
+                turn_num,
+                RawHistoryEvent {
+                    turn_num: turn_num_from_sql(turn_num)?,
+                    actions: PID::default(),
+                    version: get(&event, "version")?,
+                    hash,
+                },
+            );
+        }
+
+        let actions = sqlx::query(
+            "SELECT turn_num, player, action FROM history_event_actions
+             WHERE game_id = ?1 AND (?2 IS NULL OR turn_num = ?2)",
+        )
+        .bind(uuid_to_sql(id))
+        .bind(turn_num)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(StorageError::Database)?;
+        for action in actions {
+            let player = u32::try_from(get::<i64>(&action, "player")?).map_err(decode_error)?;
+            if let Some(event) = history.get_mut(&get(&action, "turn_num")?) {
+                event.actions.insert(
+                    Player::new(player),
+                    Bytes::from(get::<Vec<u8>>(&action, "action")?),
+                );
+            }
+        }
+
+        Ok(history.into_values().collect())
+    }
+
+    /// Keep snapshots of games every `turns` turns instead of [`DEFAULT_SNAPSHOT_INTERVAL`], `0`
+    /// for never
+    pub fn with_snapshot_interval(mut self, turns: u64) -> Self {
+        self.snapshot_interval = turns;
+        self
+    }
+
+    /// HMAC the hash chain of games with `key`, see [`hash_chain`](crate::hash_chain)
+    pub fn with_history_key(mut self, key: HistoryKey) -> Self {
+        self.history_key = Some(key);
+        self
+    }
+}
+
+#[async_trait]
+impl RawStorage for SqliteDB {
+    fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    async fn read_raw_custom_settings(
+        &self,
+        id: SettingsId,
+    ) -> Result<(SettingsId, RawCustomSettings, MetaData), StorageError> {
+        let row = sqlx::query(
+            "SELECT game_type, name, bytes, version, owner, created_at, updated_at
+             FROM custom_settings WHERE id = ?",
+        )
+        .bind(uuid_to_sql(id))
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(StorageError::Database)?
+        .ok_or(StorageError::NotFound)?;
+
+        let raw = RawCustomSettings {
+            game_type: get(&row, "game_type")?,
+            name: get(&row, "name")?,
+            bytes: Bytes::from(get::<Vec<u8>>(&row, "bytes")?),
+            version: get(&row, "version")?,
+        };
+
+        Ok((id, raw, meta_data_from_row(&row)?))
+    }
+
+    async fn read_raw_game_progression(
+        &self,
+        id: GameId,
+    ) -> Result<(GameId, RawGameProgression, MetaData), StorageError> {
+        let row = sqlx::query(
+            "SELECT game_type, seed, builtin_settings, custom_settings_id, turn_num,
+                    initial_state, concluded, owner, created_at, updated_at
+             FROM game_progressions WHERE id = ?",
+        )
+        .bind(uuid_to_sql(id))
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(StorageError::Database)?
+        .ok_or(StorageError::NotFound)?;
+
+        let settings = match get::<Option<String>>(&row, "builtin_settings")? {
+            Some(name) => SettingsType::Builtin(name),
+            None => {
+                SettingsType::Custom(uuid_from_sql(&get::<String>(&row, "custom_settings_id")?)?)
+            }
+        };
+
+        let seed: [u8; 32] = get::<Vec<u8>>(&row, "seed")?
+            .as_slice()
+            .try_into()
+            .map_err(decode_error)?;
+
+        let history_events = self.read_history_events(id, None).await?;
+
+        let snapshots = sqlx::query(
+            "SELECT turn_num, game_state FROM snapshots WHERE game_id = ? ORDER BY turn_num",
+        )
+        .bind(uuid_to_sql(id))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(StorageError::Database)?
+        .iter()
+        .map(|snapshot| {
+            Ok(RawSnapshot {
+                turn_num: turn_num_from_sql(get(snapshot, "turn_num")?)?,
+                game_state: Bytes::from(get::<Vec<u8>>(snapshot, "game_state")?),
+            })
+        })
+        .collect::<Result<_, StorageError>>()?;
+
+        let raw = RawGameProgression {
+            game_type: get(&row, "game_type")?,
+            seed: Seed::from(seed),
+            settings,
+            turn_num: turn_num_from_sql(get(&row, "turn_num")?)?,
+            initial_state: Bytes::from(get::<Vec<u8>>(&row, "initial_state")?),
+            history_events,
+            concluded: get(&row, "concluded")?,
+            snapshots,
+        };
+
+        Ok((id, raw, meta_data_from_row(&row)?))
+    }
+
+    async fn write_raw_custom_settings(
+        &self,
+        (id, raw, meta): (SettingsId, RawCustomSettings, MetaData),
+    ) -> Result<(), StorageError> {
+        sqlx::query(
+            "INSERT INTO custom_settings (id, game_type, name, bytes, version, owner, created_at,
+                updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT (id) DO UPDATE SET
+                game_type = excluded.game_type,
+                name = excluded.name,
+                bytes = excluded.bytes,
+                version = excluded.version,
+                owner = excluded.owner,
+                created_at = excluded.created_at,
+                updated_at = excluded.updated_at",
+        )
+        .bind(uuid_to_sql(id))
+        .bind(raw.game_type)
+        .bind(raw.name)
+        .bind(raw.bytes.to_vec())
+        .bind(raw.version)
+        .bind(meta.owner.map(uuid_to_sql))
+        .bind(timestamp_to_sql(meta.created_at))
+        .bind(timestamp_to_sql(meta.updated_at))
+        .execute(&self.pool)
+        .await
+        .map_err(StorageError::Database)?;
+
+        Ok(())
+    }
+
+    async fn write_raw_game_progression(
+        &self,
+        (id, raw, meta): (GameId, RawGameProgression, MetaData),
+    ) -> Result<(), StorageError> {
+        let (builtin_settings, custom_settings_id) = match raw.settings {
+            SettingsType::Builtin(name) => (Some(name), None),
+            SettingsType::Custom(settings_id) => (None, Some(uuid_to_sql(settings_id))),
+        };
+
+        let mut transaction = self.pool.begin().await.map_err(StorageError::Database)?;
+
+        sqlx::query(
+            "INSERT INTO game_progressions (id, game_type, seed, builtin_settings,
+                custom_settings_id, turn_num, initial_state, concluded, owner, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT (id) DO UPDATE SET
+                game_type = excluded.game_type,
+                seed = excluded.seed,
+                builtin_settings = excluded.builtin_settings,
+                custom_settings_id = excluded.custom_settings_id,
+                turn_num = excluded.turn_num,
+                initial_state = excluded.initial_state,
+                concluded = excluded.concluded,
+                owner = excluded.owner,
+                created_at = excluded.created_at,
+                updated_at = excluded.updated_at",
+        )
+        .bind(uuid_to_sql(id))
+        .bind(raw.game_type)
+        .bind(raw.seed.bytes().to_vec())
+        .bind(builtin_settings)
+        .bind(custom_settings_id)
+        .bind(turn_num_to_sql(raw.turn_num))
+        .bind(raw.initial_state.to_vec())
+        .bind(raw.concluded)
+        .bind(meta.owner.map(uuid_to_sql))
+        .bind(timestamp_to_sql(meta.created_at))
+        .bind(timestamp_to_sql(meta.updated_at))
+        .execute(&mut transaction)
+        .await
+        .map_err(StorageError::Database)?;
+
+        sqlx::query("DELETE FROM history_events WHERE game_id = ?")
+            .bind(uuid_to_sql(id))
+            .execute(&mut transaction)
+            .await
+            .map_err(StorageError::Database)?;
+
+        for event in &raw.history_events {
+            insert_history_event(&mut transaction, id, event).await?;
+        }
+
+        sqlx::query("DELETE FROM snapshots WHERE game_id = ?")
+            .bind(uuid_to_sql(id))
+            .execute(&mut transaction)
+            .await
+            .map_err(StorageError::Database)?;
+
+        for snapshot in &raw.snapshots {
+            sqlx::query("INSERT INTO snapshots (game_id, turn_num, game_state) VALUES (?, ?, ?)")
+                .bind(uuid_to_sql(id))
+                .bind(turn_num_to_sql(snapshot.turn_num))
+                .bind(snapshot.game_state.to_vec())
+                .execute(&mut transaction)
+                .await
+                .map_err(StorageError::Database)?;
+        }
+
+        transaction.commit().await.map_err(StorageError::Database)
+    }
+
+    async fn append_raw_history_event(
+        &self,
+        id: GameId,
+        event: RawHistoryEvent,
+        concluded: bool,
+    ) -> Result<(), StorageError> {
+        let mut transaction = self.pool.begin().await.map_err(StorageError::Database)?;
+
+        let row = sqlx::query(
+            "SELECT turn_num,
+                (SELECT MAX(turn_num) FROM history_events WHERE game_id = game_progressions.id) AS latest
+             FROM game_progressions WHERE id = ?",
+        )
+        .bind(uuid_to_sql(id))
+        .fetch_optional(&mut transaction)
+        .await
+        .map_err(StorageError::Database)?
+        .ok_or(StorageError::NotFound)?;
+
+        let latest = get::<Option<i64>>(&row, "latest")?
+            .map(turn_num_from_sql)
+            .transpose()?;
+        check_append(turn_num_from_sql(get(&row, "turn_num")?)?, latest, &event)?;
+
+        insert_history_event(&mut transaction, id, &event).await?;
+
+        sqlx::query("UPDATE game_progressions SET concluded = ?, updated_at = ? WHERE id = ?")
+            .bind(concluded)
+            .bind(timestamp_to_sql(Utc::now()))
+            .bind(uuid_to_sql(id))
+            .execute(&mut transaction)
+            .await
+            .map_err(StorageError::Database)?;
+
+        transaction.commit().await.map_err(StorageError::Database)
+    }
+
+    async fn read_latest_raw_history_event(
+        &self,
+        id: GameId,
+    ) -> Result<Option<RawHistoryEvent>, StorageError> {
+        let row = sqlx::query(
+            "SELECT (SELECT MAX(turn_num) FROM history_events WHERE game_id = game_progressions.id)
+                AS latest
+             FROM game_progressions WHERE id = ?",
+        )
+        .bind(uuid_to_sql(id))
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(StorageError::Database)?
+        .ok_or(StorageError::NotFound)?;
+
+        match get::<Option<i64>>(&row, "latest")? {
+            Some(latest) => Ok(self.read_history_events(id, Some(latest)).await?.pop()),
+            None => Ok(None),
+        }
+    }
+
+    async fn write_raw_snapshot(
+        &self,
+        id: GameId,
+        snapshot: RawSnapshot,
+    ) -> Result<(), StorageError> {
+        let result = sqlx::query(
+            "INSERT INTO snapshots (game_id, turn_num, game_state)
+             SELECT id, ?, ? FROM game_progressions WHERE id = ?
+             ON CONFLICT (game_id, turn_num) DO UPDATE SET game_state = excluded.game_state",
+        )
+        .bind(turn_num_to_sql(snapshot.turn_num))
+        .bind(snapshot.game_state.to_vec())
+        .bind(uuid_to_sql(id))
+        .execute(&self.pool)
+        .await
+        .map_err(StorageError::Database)?;
+
+        if result.rows_affected() == 0 {
+            Err(StorageError::NotFound)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn snapshot_interval(&self) -> u64 {
+        self.snapshot_interval
+    }
+
+    fn history_key(&self) -> Option<&HistoryKey> {
+        self.history_key.as_ref()
+    }
+
+    async fn list_raw_game_progressions(&self, query: &GameQuery) -> Result<Page, StorageError> {
+        let limit = i64::try_from(query.limit).unwrap_or(i64::MAX);
+        let offset = i64::try_from(query.offset).unwrap_or(i64::MAX);
+
+        // Fetch one extra row to find out if there is another page
+        let rows = sqlx::query(
+            "SELECT id, game_type, concluded, owner, created_at, updated_at
+             FROM game_progressions
+             WHERE (?1 IS NULL OR owner = ?1)
+                AND (?2 IS NULL OR game_type = ?2)
+                AND (?3 IS NULL OR created_at >= ?3)
+                AND (?4 IS NULL OR created_at < ?4)
+                AND (?5 IS NULL OR updated_at >= ?5)
+                AND (?6 IS NULL OR updated_at < ?6)
+                AND (?7 IS NULL OR concluded = ?7)
+             ORDER BY updated_at DESC, id
+             LIMIT ?8 OFFSET ?9",
+        )
+        .bind(query.owner.map(uuid_to_sql))
+        .bind(query.game_type.as_deref())
+        .bind(query.created_after.map(timestamp_to_sql))
+        .bind(query.created_before.map(timestamp_to_sql))
+        .bind(query.updated_after.map(timestamp_to_sql))
+        .bind(query.updated_before.map(timestamp_to_sql))
+        .bind(query.concluded)
+        .bind(limit.saturating_add(1))
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(StorageError::Database)?;
+
+        let has_more = rows.len() > query.limit;
+        let games = rows
+            .iter()
+            .take(query.limit)
+            .map(|row| {
+                Ok(GameListing {
+                    id: uuid_from_sql(&get::<String>(row, "id")?)?,
+                    game_type: get(row, "game_type")?,
+                    concluded: get(row, "concluded")?,
+                    meta: meta_data_from_row(row)?,
+                })
+            })
+            .collect::<Result<_, StorageError>>()?;
+
+        Ok(Page::new(query, games, has_more))
+    }
+}
+
+#[async_trait]
+impl AccountStorage for SqliteDB {
+    async fn write_user(&self, user: StoredUser) -> Result<(), StorageError> {
+        let mut transaction = self.pool.begin().await.map_err(StorageError::Database)?;
+
+        let taken = sqlx::query("SELECT id FROM users WHERE username = ? AND id != ?")
+            .bind(&user.username)
+            .bind(uuid_to_sql(user.user_id))
+            .fetch_optional(&mut transaction)
+            .await
+            .map_err(StorageError::Database)?;
+        if taken.is_some() {
+            return Err(StorageError::UsernameTaken(user.username));
+        }
+
+        sqlx::query(
+            "INSERT INTO users (id, username, created_at) VALUES (?, ?, ?)
+             ON CONFLICT (id) DO UPDATE SET
+                username = excluded.username,
+                created_at = excluded.created_at",
+        )
+        .bind(uuid_to_sql(user.user_id))
+        .bind(&user.username)
+        .bind(timestamp_to_sql(user.created_at))
+        .execute(&mut transaction)
+        .await
+        .map_err(StorageError::Database)?;
+
+        transaction.commit().await.map_err(StorageError::Database)
+    }
+
+    async fn read_user(&self, id: UserId) -> Result<StoredUser, StorageError> {
+        let row = sqlx::query("SELECT id, username, created_at FROM users WHERE id = ?")
+            .bind(uuid_to_sql(id))
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(StorageError::Database)?
+            .ok_or(StorageError::NotFound)?;
+
+        user_from_row(&row)
+    }
+
+    async fn read_user_by_username(&self, username: &str) -> Result<StoredUser, StorageError> {
+        let row = sqlx::query("SELECT id, username, created_at FROM users WHERE username = ?")
+            .bind(username)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(StorageError::Database)?
+            .ok_or(StorageError::NotFound)?;
+
+        user_from_row(&row)
+    }
+
+    async fn write_token(&self, token: StoredToken) -> Result<(), StorageError> {
+        let result = sqlx::query(
+            "INSERT INTO tokens (id, hash, user_id, name, created_at, expires_at)
+             SELECT ?, ?, id, ?, ?, ? FROM users WHERE id = ?
+             ON CONFLICT (id) DO UPDATE SET
+                hash = excluded.hash,
+                user_id = excluded.user_id,
+                name = excluded.name,
+                created_at = excluded.created_at,
+                expires_at = excluded.expires_at",
+        )
+        .bind(uuid_to_sql(token.id))
+        .bind(token.hash.0.to_vec())
+        .bind(token.name)
+        .bind(timestamp_to_sql(token.created_at))
+        .bind(token.expires_at.map(timestamp_to_sql))
+        .bind(uuid_to_sql(token.user_id))
+        .execute(&self.pool)
+        .await
+        .map_err(StorageError::Database)?;
+
+        if result.rows_affected() == 0 {
+            Err(StorageError::NotFound)
+        } else {
+            Ok(())
+        }
+    }
+
+    async fn read_token_by_hash(&self, hash: &TokenHash) -> Result<StoredToken, StorageError> {
+        let row = sqlx::query(
+            "SELECT id, hash, user_id, name, created_at, expires_at FROM tokens WHERE hash = ?",
+        )
+        .bind(hash.0.to_vec())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(StorageError::Database)?
+        .ok_or(StorageError::NotFound)?;
+
+        token_from_row(&row)
+    }
+
+    async fn list_tokens(&self, user_id: UserId) -> Result<Vec<StoredToken>, StorageError> {
+        sqlx::query(
+            "SELECT id, hash, user_id, name, created_at, expires_at FROM tokens
+             WHERE user_id = ? ORDER BY created_at, id",
+        )
+        .bind(uuid_to_sql(user_id))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(StorageError::Database)?
+        .iter()
+        .map(token_from_row)
+        .collect()
+    }
+
+    async fn delete_token(&self, id: TokenId) -> Result<(), StorageError> {
+        let result = sqlx::query("DELETE FROM tokens WHERE id = ?")
+            .bind(uuid_to_sql(id))
+            .execute(&self.pool)
+            .await
+            .map_err(StorageError::Database)?;
+
+        if result.rows_affected() == 0 {
+            Err(StorageError::NotFound)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[async_trait]
+impl RatingStorage for SqliteDB {
+    async fn write_ratings(&self, ratings: Vec<StoredRating>) -> Result<(), StorageError> {
+        let mut transaction = self.pool.begin().await.map_err(StorageError::Database)?;
+
+        for rating in ratings {
+            sqlx::query(
+                "INSERT INTO ratings (user_id, game_type, mode, rating, updated_at)
+                 VALUES (?, ?, ?, ?, ?)
+                 ON CONFLICT (user_id, game_type, mode) DO UPDATE SET
+                    rating = excluded.rating,
+                    updated_at = excluded.updated_at",
+            )
+            .bind(uuid_to_sql(rating.user_id))
+            .bind(rating.game_type)
+            .bind(rating.mode)
+            .bind(rating.rating)
+            .bind(timestamp_to_sql(rating.updated_at))
+            .execute(&mut transaction)
+            .await
+            .map_err(StorageError::Database)?;
+        }
+
+        transaction.commit().await.map_err(StorageError::Database)
+    }
+
+    async fn read_ratings(&self) -> Result<Vec<StoredRating>, StorageError> {
+        sqlx::query("SELECT user_id, game_type, mode, rating, updated_at FROM ratings")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(StorageError::Database)?
+            .iter()
+            .map(rating_from_row)
+            .collect()
+    }
+}
+
+async fn insert_history_event(
+    transaction: &mut Transaction<'_, Sqlite>,
+    id: GameId,
+    event: &RawHistoryEvent,
+) -> Result<(), StorageError> {
+    let turn_num = turn_num_to_sql(event.turn_num);
+
+    sqlx::query(
+        "INSERT INTO history_events (game_id, turn_num, version, hash) VALUES (?, ?, ?, ?)",
+    )
+    .bind(uuid_to_sql(id))
+    .bind(turn_num)
+    .bind(event.version)
+    .bind(event.hash.map(|hash| hash.to_vec()))
+    .execute(&mut *transaction)
+    .await
+    .map_err(StorageError::Database)?;
+
+    for (player, action) in event.actions.iter() {
+        sqlx::query(
+            "INSERT INTO history_event_actions (game_id, turn_num, player, action)
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(uuid_to_sql(id))
+        .bind(turn_num)
+        .bind(i64::from(u32::from(player)))
+        .bind(action.to_vec())
+        .execute(&mut *transaction)
+        .await
+        .map_err(StorageError::Database)?;
+    }
+
+    Ok(())
+}
+
+fn decode_error(err: impl std::error::Error + Send + Sync + 'static) -> StorageError {
+    StorageError::Database(sqlx::Error::Decode(Box::new(err)))
+}
+
+fn get<'r, T>(row: &'r SqliteRow, column: &str) -> Result<T, StorageError>
+where
+    T: sqlx::Decode<'r, sqlx::Sqlite> + sqlx::Type<sqlx::Sqlite>,
+{
+    row.try_get(column).map_err(StorageError::Database)
+}
+
+fn uuid_to_sql(id: impl Into<Uuid>) -> String {
+    id.into().to_string()
+}
+
+fn uuid_from_sql<Id: From<Uuid>>(id: &str) -> Result<Id, StorageError> {
+    Uuid::parse_str(id).map(Id::from).map_err(decode_error)
+}
+
+fn turn_num_to_sql(turn_num: TurnNum) -> i64 {
+    i64::try_from(u64::from(turn_num)).expect("games don't last i64::MAX turns")
+}
+
+fn turn_num_from_sql(turn_num: i64) -> Result<TurnNum, StorageError> {
+    u64::try_from(turn_num)
+        .map(TurnNum::from)
+        .map_err(decode_error)
+}
+
+/// Timestamps are stored in a fixed width format so they sort correctly as text
+fn timestamp_to_sql(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+fn timestamp_from_sql(timestamp: &str) -> Result<DateTime<Utc>, StorageError> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .map_err(decode_error)
+}
+
+fn meta_data_from_row(row: &SqliteRow) -> Result<MetaData, StorageError> {
+    let owner: Option<UserId> = get::<Option<String>>(row, "owner")?
+        .map(|owner| uuid_from_sql(&owner))
+        .transpose()?;
+
+    Ok(MetaData {
+        owner,
+        created_at: timestamp_from_sql(&get::<String>(row, "created_at")?)?,
+        updated_at: timestamp_from_sql(&get::<String>(row, "updated_at")?)?,
+    })
+}
+
+fn user_from_row(row: &SqliteRow) -> Result<StoredUser, StorageError> {
+    Ok(StoredUser {
+        user_id: uuid_from_sql(&get::<String>(row, "id")?)?,
+        username: get(row, "username")?,
+        created_at: timestamp_from_sql(&get::<String>(row, "created_at")?)?,
+    })
+}
+
+fn token_from_row(row: &SqliteRow) -> Result<StoredToken, StorageError> {
+    let hash: [u8; 32] = get::<Vec<u8>>(row, "hash")?
+        .as_slice()
+        .try_into()
+        .map_err(decode_error)?;
+
+    Ok(StoredToken {
+        id: uuid_from_sql(&get::<String>(row, "id")?)?,
+        hash: TokenHash(hash),
+        user_id: uuid_from_sql(&get::<String>(row, "user_id")?)?,
+        name: get(row, "name")?,
+        created_at: timestamp_from_sql(&get::<String>(row, "created_at")?)?,
+        expires_at: get::<Option<String>>(row, "expires_at")?
+            .map(|expires_at| timestamp_from_sql(&expires_at))
+            .transpose()?,
+    })
+}
+
+fn rating_from_row(row: &SqliteRow) -> Result<StoredRating, StorageError> {
+    Ok(StoredRating {
+        user_id: uuid_from_sql(&get::<String>(row, "user_id")?)?,
+        game_type: get(row, "game_type")?,
+        mode: get(row, "mode")?,
+        rating: get(row, "rating")?,
+        updated_at: timestamp_from_sql(&get::<String>(row, "updated_at")?)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+    use lttcore::{
+        examples::{
+            guess_the_number::{Guess, Settings},
+            GuessTheNumber,
+        },
+        play::{seed::SEED_42, ActionResponse::Response},
+        pov::game_progression::GameProgression,
+    };
+
+    fn meta() -> MetaData {
+        MetaData {
+            owner: Some(UserId::new()),
+            created_at: DateTime::parse_from_rfc3339("2021-10-01T12:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn play(game: &mut GameProgression<GuessTheNumber>, guess: u32) {
+        let actions = game
+            .which_players_input_needed()
+            .map(|player| (player, Response(Guess(guess))))
+            .collect();
+        let update = game.resolve(actions);
+        game.update(update);
+    }
+
+    #[tokio::test]
+    async fn test_round_tripping_games_in_memory() {
+        let db = SqliteDB::in_memory(Encoding::Bincode).await.unwrap();
+        let id = GameId::new();
+        let meta = meta();
+
+        let settings = Settings::try_from(1..=100).unwrap();
+        let mut game: GameProgression<GuessTheNumber> =
+            GameProgression::from_settings_and_seed(settings, SEED_42);
+
+        db.write_game_progression((id, game.clone(), meta.clone()))
+            .await
+            .unwrap();
+        play(&mut game, 50);
+        db.write_game_progression((id, game.clone(), meta.clone()))
+            .await
+            .unwrap();
+
+        let (_, read, read_meta) = Storage::<GuessTheNumber>::read_game_progression(&db, id)
+            .await
+            .unwrap();
+        assert_eq!(read, game);
+        assert_eq!(read_meta, meta);
+
+        let mut appended: GameProgression<GuessTheNumber> =
+            GameProgression::from_settings_and_seed(Settings::default(), SEED_42);
+        let appended_id = GameId::new();
+        db.write_game_progression((appended_id, appended.clone(), meta.clone()))
+            .await
+            .unwrap();
+        play(&mut appended, 7);
+        db.append_latest_turn(appended_id, &appended).await.unwrap();
+
+        let (_, read, read_meta) =
+            Storage::<GuessTheNumber>::read_game_progression(&db, appended_id)
+                .await
+                .unwrap();
+        assert_eq!(read, appended);
+        assert!(read_meta.updated_at > meta.updated_at);
+
+        let missing = Storage::<GuessTheNumber>::read_game_progression(&db, GameId::new()).await;
+        assert!(matches!(missing, Err(StorageError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_listing_games() {
+        let db = SqliteDB::in_memory(Encoding::Bincode).await.unwrap();
+        crate::query::tests::test_listing_games(&db).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_snapshots() {
+        let db = SqliteDB::in_memory(Encoding::Bincode)
+            .await
+            .unwrap()
+            .with_snapshot_interval(2);
+        crate::storage::tests::test_snapshots(&db).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_accounts() {
+        let db = SqliteDB::in_memory(Encoding::Bincode).await.unwrap();
+        crate::accounts::tests::test_accounts(&db).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_ratings() {
+        let db = SqliteDB::in_memory(Encoding::Bincode).await.unwrap();
+        crate::ratings::tests::test_ratings(&db).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_games_survive_reconnecting_to_a_file() {
+        let path = std::env::temp_dir().join(format!("lttstorage-{}.sqlite", Uuid::new_v4()));
+        let url = path.to_str().unwrap();
+        let id = GameId::new();
+
+        let mut game: GameProgression<GuessTheNumber> =
+            GameProgression::from_settings_and_seed(lttcore::play::SettingsPtr::default(), SEED_42);
+        play(&mut game, 1);
+
+        {
+            let db = SqliteDB::connect(url, Encoding::Json).await.unwrap();
+            db.write_game_progression((id, game.clone(), meta()))
+                .await
+                .unwrap();
+        }
+
+        let db = SqliteDB::connect(url, Encoding::Json).await.unwrap();
+        let (_, read, _) = Storage::<GuessTheNumber>::read_game_progression(&db, id)
+            .await
+            .unwrap();
+        assert_eq!(read, game);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
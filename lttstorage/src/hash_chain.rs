@@ -0,0 +1,157 @@
+//! Tamper-evident hash chain over stored game history
+//!
+//! Every stored [`RawHistoryEvent`](super::raw_storage::RawHistoryEvent) carries a SHA-256 hash
+//! of the hash before it, its turn, its actions and the public info of the game after it. The
+//! chain starts from a hash of the game's type, [`Seed`], starting turn and initial state, so
+//! editing any part of a stored game after the fact breaks the chain from that point on.
+//! [`Storage::verify_history`](super::storage::Storage::verify_history) walks the chain and
+//! reports every break.
+//!
+//! Backends given a [`HistoryKey`] HMAC the chain with it, so nobody without the key can rewrite
+//! history and hash it again. Without a key the chain only catches edits by anyone who didn't
+//! also hash the history again. Changing the key breaks the chain of every game hashed before. The
+//! chain ends on the hash of the latest event, so dropping events off the end of a history only
+//! shows up by checking that hash against a copy of it kept somewhere else.
+//!
+//! Values are hashed in their bincode encoding rather than the stored bytes, so hashes survive
+//! re-encoding the game with a different [`Encoding`]. Upgrading stored actions to a new
+//! [`Play::SCHEMA_VERSION`] does change them, so games need
+//! writing again after a migration to be verifiable.
+use super::storage::StorageError;
+use hmac::{Hmac, Mac};
+use lttcore::{
+    encoding::Encoding,
+    play::{ActionResponse, GameState, Play, Seed, TurnNum},
+    utilities::PlayerIndexedData as PID,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// A link in the hash chain
+pub type HistoryHash = [u8; 32];
+
+/// Distinguishes the hashes from any other use of SHA-256 over the same bytes
+const DOMAIN: &[u8] = b"lttstorage history v1";
+
+/// A secret key to HMAC the hash chain with
+#[derive(Clone, PartialEq, Eq)]
+pub struct HistoryKey(Vec<u8>);
+
+impl HistoryKey {
+    pub fn new(key: &[u8]) -> Self {
+        Self(key.to_vec())
+    }
+}
+
+impl fmt::Debug for HistoryKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("HistoryKey(..)")
+    }
+}
+
+/// Where a stored history doesn't match its hash chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainBreak {
+    /// The event was stored without a hash
+    Unhashed(TurnNum),
+    /// The event's hash doesn't match its contents and the hash before it
+    Mismatch(TurnNum),
+}
+
+/// The hash the chain of a game starting from `initial_state` at `turn_num` starts from
+pub(crate) fn genesis_hash<T: Play>(
+    key: Option<&HistoryKey>,
+    seed: &Seed,
+    turn_num: TurnNum,
+    initial_state: &GameState<T>,
+) -> Result<HistoryHash, StorageError> {
+    let mut hasher = LinkHasher::new(key);
+    hasher.update(DOMAIN);
+    hasher.update_with_bytes(T::lib_table_top_identifier().as_bytes());
+    hasher.update(seed.bytes());
+    hasher.update(&u64::from(turn_num).to_be_bytes());
+    hasher.update_with_value(initial_state)?;
+    Ok(hasher.finish())
+}
+
+/// The hash of the event of `turn_num` following the event hashed as `previous`
+pub(crate) fn event_hash<T: Play>(
+    key: Option<&HistoryKey>,
+    previous: &HistoryHash,
+    turn_num: TurnNum,
+    actions: &PID<ActionResponse<T>>,
+    public_info: &T::PublicInfo,
+) -> Result<HistoryHash, StorageError> {
+    let mut hasher = LinkHasher::new(key);
+    hasher.update(DOMAIN);
+    hasher.update(previous);
+    hasher.update(&u64::from(turn_num).to_be_bytes());
+    hasher.update_with_value(actions)?;
+    hasher.update_with_value(public_info)?;
+    Ok(hasher.finish())
+}
+
+/// Hashes a link with SHA-256, or HMAC-SHA256 for keyed chains
+enum LinkHasher {
+    Plain(Sha256),
+    Keyed(Hmac<Sha256>),
+}
+
+impl LinkHasher {
+    fn new(key: Option<&HistoryKey>) -> Self {
+        match key {
+            Some(key) => {
+                Self::Keyed(Hmac::new_from_slice(&key.0).expect("HMAC takes keys of any length"))
+            }
+            None => Self::Plain(Sha256::new()),
+        }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Self::Plain(hasher) => hasher.update(bytes),
+            Self::Keyed(mac) => mac.update(bytes),
+        }
+    }
+
+    fn update_with_value(&mut self, value: &impl Serialize) -> Result<(), StorageError> {
+        let bytes = Encoding::Bincode
+            .serialize(value)
+            .map_err(StorageError::EncodingError)?;
+        self.update_with_bytes(&bytes);
+        Ok(())
+    }
+
+    /// Length prefix variable length values so adjacent values can't be shifted into each other
+    fn update_with_bytes(&mut self, bytes: &[u8]) {
+        self.update(&(bytes.len() as u64).to_be_bytes());
+        self.update(bytes);
+    }
+
+    fn finish(self) -> HistoryHash {
+        match self {
+            Self::Plain(hasher) => hasher.finalize().into(),
+            Self::Keyed(mac) => mac.finalize().into_bytes().into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyed_links_are_hmac_sha256() {
+        // Test case 2 of RFC 4231
+        let key = HistoryKey::new(b"Jefe");
+        let mut hasher = LinkHasher::new(Some(&key));
+        hasher.update(b"what do ya want for nothing?");
+        let expected = [
+            0x5b, 0xdc, 0xc1, 0x46, 0xbf, 0x60, 0x75, 0x4e, 0x6a, 0x04, 0x24, 0x26, 0x08, 0x95,
+            0x75, 0xc7, 0x5a, 0x00, 0x3f, 0x08, 0x9d, 0x27, 0x39, 0x83, 0x9d, 0xec, 0x58, 0xb9,
+            0x64, 0xec, 0x38, 0x43,
+        ];
+        assert_eq!(hasher.finish(), expected);
+    }
+}
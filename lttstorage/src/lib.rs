@@ -1,5 +1,10 @@
 #![allow(dead_code)]
 
+pub mod accounts;
+pub mod archive;
 pub mod db;
+pub mod hash_chain;
+pub mod query;
+pub mod ratings;
 pub mod raw_storage;
 pub mod storage;
@@ -0,0 +1,254 @@
+use super::storage::MetaData;
+use chrono::prelude::*;
+use lttcore::{id::GameId, id::UserId, LibTableTopIdentifier};
+use serde::{Deserialize, Serialize};
+
+/// The default number of games in a [`Page`]
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Filters and pagination for listing stored games
+///
+/// Every filter that is set must match. Matching games are ordered by most recently updated
+/// first.
+///
+/// ```
+/// use lttcore::examples::TicTacToe;
+/// use lttcore::id::UserId;
+/// use lttstorage::query::GameQuery;
+///
+/// // My last 50 games of TicTacToe
+/// let query = GameQuery::default()
+///     .owner(UserId::new())
+///     .game_type::<TicTacToe>()
+///     .limit(50);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameQuery {
+    pub owner: Option<UserId>,
+    pub game_type: Option<String>,
+    /// Inclusive lower bound on [`MetaData::created_at`]
+    pub created_after: Option<DateTime<Utc>>,
+    /// Exclusive upper bound on [`MetaData::created_at`]
+    pub created_before: Option<DateTime<Utc>>,
+    /// Inclusive lower bound on [`MetaData::updated_at`]
+    pub updated_after: Option<DateTime<Utc>>,
+    /// Exclusive upper bound on [`MetaData::updated_at`]
+    pub updated_before: Option<DateTime<Utc>>,
+    pub concluded: Option<bool>,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl Default for GameQuery {
+    fn default() -> Self {
+        Self {
+            owner: None,
+            game_type: None,
+            created_after: None,
+            created_before: None,
+            updated_after: None,
+            updated_before: None,
+            concluded: None,
+            offset: 0,
+            limit: DEFAULT_PAGE_SIZE,
+        }
+    }
+}
+
+impl GameQuery {
+    pub fn owner(mut self, owner: UserId) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    pub fn game_type<T: LibTableTopIdentifier>(mut self) -> Self {
+        self.game_type = Some(String::from(T::lib_table_top_identifier()));
+        self
+    }
+
+    pub fn created_between(mut self, after: DateTime<Utc>, before: DateTime<Utc>) -> Self {
+        self.created_after = Some(after);
+        self.created_before = Some(before);
+        self
+    }
+
+    pub fn updated_between(mut self, after: DateTime<Utc>, before: DateTime<Utc>) -> Self {
+        self.updated_after = Some(after);
+        self.updated_before = Some(before);
+        self
+    }
+
+    pub fn concluded(mut self, concluded: bool) -> Self {
+        self.concluded = Some(concluded);
+        self
+    }
+
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Whether the listing passes every filter of the query, ignoring pagination
+    pub fn matches(&self, listing: &GameListing) -> bool {
+        let meta = &listing.meta;
+
+        self.owner.is_none_or(|owner| meta.owner == Some(owner))
+            && self
+                .game_type
+                .as_ref()
+                .is_none_or(|game_type| &listing.game_type == game_type)
+            && self.created_after.is_none_or(|t| meta.created_at >= t)
+            && self.created_before.is_none_or(|t| meta.created_at < t)
+            && self.updated_after.is_none_or(|t| meta.updated_at >= t)
+            && self.updated_before.is_none_or(|t| meta.updated_at < t)
+            && self
+                .concluded
+                .is_none_or(|concluded| listing.concluded == concluded)
+    }
+
+    /// Filter, order and paginate listings, for backends that can't do it themselves
+    pub fn apply(&self, listings: impl IntoIterator<Item = GameListing>) -> Page {
+        let mut listings: Vec<GameListing> = listings
+            .into_iter()
+            .filter(|listing| self.matches(listing))
+            .collect();
+
+        listings.sort_by(|a, b| {
+            b.meta
+                .updated_at
+                .cmp(&a.meta.updated_at)
+                .then(a.id.cmp(&b.id))
+        });
+
+        let has_more = listings.len() > self.offset.saturating_add(self.limit);
+        let games = listings
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit)
+            .collect();
+
+        Page::new(self, games, has_more)
+    }
+}
+
+/// A stored game, without its settings, state or history
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameListing {
+    pub id: GameId,
+    pub game_type: String,
+    pub concluded: bool,
+    pub meta: MetaData,
+}
+
+/// One page of the games matching a [`GameQuery`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+    pub games: Vec<GameListing>,
+    /// The query for the following page, if there are more matching games
+    pub next: Option<GameQuery>,
+}
+
+impl Page {
+    /// Build a page of `games` returned for `query`
+    pub fn new(query: &GameQuery, games: Vec<GameListing>, has_more: bool) -> Self {
+        let next = has_more.then(|| query.clone().offset(query.offset + query.limit));
+        Self { games, next }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        db::hash_map_db::HashMapDB,
+        raw_storage::RawStorage,
+        storage::{Storage, StorageError},
+    };
+    use chrono::Duration;
+    use lttcore::{
+        encoding::Encoding,
+        examples::{guess_the_number::Guess, GuessTheNumber, TicTacToe},
+        play::{ActionResponse::Response, SettingsPtr},
+        pov::game_progression::GameProgression,
+    };
+
+    /// Store a handful of games and check the backend lists them like [`GameQuery::apply`]
+    pub(crate) async fn test_listing_games(db: &impl RawStorage) -> Result<(), StorageError> {
+        let me = UserId::new();
+        let someone_else = UserId::new();
+        let start = Utc::now();
+        let mut ids = Vec::new();
+
+        for i in 0..6 {
+            let id = GameId::new();
+            let meta = MetaData {
+                owner: Some(if i % 3 == 0 { someone_else } else { me }),
+                created_at: start + Duration::minutes(i),
+                updated_at: start + Duration::minutes(i),
+            };
+
+            if i % 2 == 0 {
+                let game: GameProgression<TicTacToe> =
+                    GameProgression::from_settings(SettingsPtr::default());
+                db.write_game_progression((id, game, meta)).await?;
+            } else {
+                let mut game: GameProgression<GuessTheNumber> =
+                    GameProgression::from_settings(SettingsPtr::default());
+                let actions = game
+                    .which_players_input_needed()
+                    .map(|player| (player, Response(Guess(1))))
+                    .collect();
+                let update = game.resolve(actions);
+                game.update(update);
+                db.write_game_progression((id, game, meta)).await?;
+            }
+
+            ids.push(id);
+        }
+
+        let listed = |page: Page| page.games.iter().map(|game| game.id).collect::<Vec<_>>();
+
+        let everything = db.list_raw_game_progressions(&GameQuery::default()).await?;
+        assert_eq!(
+            listed(everything.clone()),
+            ids.iter().rev().copied().collect::<Vec<_>>()
+        );
+        assert!(everything.next.is_none());
+
+        let mine = GameQuery::default().owner(me);
+        let first = db
+            .list_raw_game_progressions(&mine.clone().limit(3))
+            .await?;
+        assert_eq!(listed(first.clone()), vec![ids[5], ids[4], ids[2]]);
+        let second = db.list_raw_game_progressions(&first.next.unwrap()).await?;
+        assert_eq!(listed(second.clone()), vec![ids[1]]);
+        assert!(second.next.is_none());
+
+        let tic_tac_toe = GameQuery::default().game_type::<TicTacToe>();
+        let page = db.list_raw_game_progressions(&tic_tac_toe).await?;
+        assert_eq!(listed(page), vec![ids[4], ids[2], ids[0]]);
+
+        let concluded = GameQuery::default().concluded(true);
+        let page = db.list_raw_game_progressions(&concluded).await?;
+        assert_eq!(listed(page), vec![ids[5], ids[3], ids[1]]);
+
+        let window = GameQuery::default()
+            .created_between(start + Duration::minutes(1), start + Duration::minutes(3))
+            .updated_between(start, start + Duration::minutes(10));
+        let page = db.list_raw_game_progressions(&window).await?;
+        assert_eq!(listed(page), vec![ids[2], ids[1]]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_listing_games_in_a_hash_map_db() {
+        let db = HashMapDB::new(Encoding::Bincode);
+        test_listing_games(&db).await.unwrap();
+    }
+}
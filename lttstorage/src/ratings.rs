@@ -0,0 +1,94 @@
+//! Users' skill ratings, so they survive restarts
+//!
+//! Ratings are kept per game type and settings mode, what they mean is up to whoever records
+//! them.
+use super::storage::StorageError;
+use async_trait::async_trait;
+use chrono::prelude::*;
+use lttcore::id::UserId;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredRating {
+    pub user_id: UserId,
+    pub game_type: String,
+    pub mode: String,
+    pub rating: f64,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl StoredRating {
+    fn key(&self) -> (UserId, &str, &str) {
+        (self.user_id, &self.game_type, &self.mode)
+    }
+
+    /// Whether both ratings are of the same user, game type and mode
+    pub fn is_same_rating(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+#[async_trait]
+pub trait RatingStorage: Send + Sync + 'static {
+    /// Add or replace ratings, either all of them are written or none are
+    async fn write_ratings(&self, ratings: Vec<StoredRating>) -> Result<(), StorageError>;
+
+    /// Every stored rating, in no particular order
+    async fn read_ratings(&self) -> Result<Vec<StoredRating>, StorageError>;
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::db::hash_map_db::HashMapDB;
+    use lttcore::encoding::Encoding;
+
+    /// Exercise every [`RatingStorage`] method of a backend
+    pub(crate) async fn test_ratings(db: &impl RatingStorage) -> Result<(), StorageError> {
+        assert_eq!(db.read_ratings().await?, vec![]);
+
+        let now = Utc::now();
+        let alice = UserId::new();
+        let rating = |user_id, mode: &str, rating| StoredRating {
+            user_id,
+            game_type: "guess_the_number".to_owned(),
+            mode: mode.to_owned(),
+            rating,
+            updated_at: now,
+        };
+
+        let ratings = vec![
+            rating(alice, "default", 1516.0),
+            rating(alice, "custom", 1484.0),
+        ];
+        db.write_ratings(ratings.clone()).await?;
+        let mut stored = db.read_ratings().await?;
+        stored.sort_by(|a, b| a.mode.cmp(&b.mode));
+        assert_eq!(stored, vec![ratings[1].clone(), ratings[0].clone()]);
+
+        // Writing a rating again replaces it
+        let bob = UserId::new();
+        let updated = vec![
+            rating(alice, "default", 1530.5),
+            rating(bob, "default", 1470.0),
+        ];
+        db.write_ratings(updated.clone()).await?;
+        let stored = db.read_ratings().await?;
+        assert_eq!(stored.len(), 3);
+        for rating in updated.iter().chain([&ratings[1]]) {
+            assert_eq!(
+                stored.iter().find(|stored| stored.is_same_rating(rating)),
+                Some(rating)
+            );
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ratings_in_a_hash_map_db() {
+        test_ratings(&HashMapDB::new(Encoding::Bincode))
+            .await
+            .unwrap();
+    }
+}
@@ -1,3 +1,5 @@
+use super::hash_chain::{HistoryHash, HistoryKey};
+use super::query::{GameQuery, Page};
 use super::storage::{MetaData, SettingsType, StorageError};
 use async_trait::async_trait;
 use bytes::Bytes;
@@ -9,27 +11,114 @@ use lttcore::{
     play::{Seed, TurnNum},
     utilities::PlayerIndexedData,
 };
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RawCustomSettings {
     pub name: Option<String>,
     pub bytes: Bytes,
     pub game_type: String,
+    /// The [`Play::SCHEMA_VERSION`](lttcore::play::Play::SCHEMA_VERSION) `bytes` were written with
+    #[serde(default)]
+    pub version: u32,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RawHistoryEvent {
-    turn_num: TurnNum,
-    actions: PlayerIndexedData<Bytes>,
+    pub turn_num: TurnNum,
+    pub actions: PlayerIndexedData<Bytes>,
+    /// The [`Play::SCHEMA_VERSION`](lttcore::play::Play::SCHEMA_VERSION) `actions` were written
+    /// with
+    #[serde(default)]
+    pub version: u32,
+    /// The event's link in the game's [hash chain](super::hash_chain), `None` for events stored
+    /// before history was hashed
+    #[serde(default)]
+    pub hash: Option<HistoryHash>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// The game state at the start of `turn_num`, so loading the game can skip replaying the turns
+/// before it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RawSnapshot {
+    pub turn_num: TurnNum,
+    pub game_state: Bytes,
+}
+
+/// How many turns apart [`RawStorage`] backends keep [`RawSnapshot`]s by default
+pub const DEFAULT_SNAPSHOT_INTERVAL: u64 = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RawGameProgression {
-    seed: Seed,
-    settings: SettingsType,
-    turn_num: TurnNum,
-    initial_state: Bytes,
-    history_events: Vec<RawHistoryEvent>,
+    pub game_type: String,
+    pub seed: Seed,
+    pub settings: SettingsType,
+    /// The turn the game started from, which `initial_state` is the game state of
+    pub turn_num: TurnNum,
+    pub initial_state: Bytes,
+    pub history_events: Vec<RawHistoryEvent>,
+    /// Whether the game had concluded as of its latest history event
+    pub concluded: bool,
+    /// Snapshots of the game after `turn_num`, in turn order
+    #[serde(default)]
+    pub snapshots: Vec<RawSnapshot>,
+}
+
+impl RawGameProgression {
+    /// The turn the game is on after its recorded history
+    pub fn latest_turn_num(&self) -> TurnNum {
+        self.history_events
+            .last()
+            .map_or(self.turn_num, |event| event.turn_num.next())
+    }
+
+    /// Add a snapshot, replacing any snapshot of the same turn
+    pub fn insert_snapshot(&mut self, snapshot: RawSnapshot) {
+        match self
+            .snapshots
+            .binary_search_by_key(&snapshot.turn_num, |snapshot| snapshot.turn_num)
+        {
+            Ok(i) => self.snapshots[i] = snapshot,
+            Err(i) => self.snapshots.insert(i, snapshot),
+        }
+    }
+
+    /// The game restarted from the latest snapshot at or before `turn_num` (or the latest turn
+    /// for `None`), with only the history between the snapshot and `turn_num`
+    ///
+    /// Returns [`StorageError::InvalidHistory`] if the game's history doesn't cover `turn_num`
+    pub fn from_nearest_snapshot(&self, turn_num: Option<TurnNum>) -> Result<Self, StorageError> {
+        let latest = self.latest_turn_num();
+        let turn_num = turn_num.unwrap_or(latest);
+        if turn_num < self.turn_num || turn_num > latest {
+            return Err(StorageError::InvalidHistory(turn_num));
+        }
+
+        let (start, initial_state) = self
+            .snapshots
+            .iter()
+            .rfind(|snapshot| snapshot.turn_num > self.turn_num && snapshot.turn_num <= turn_num)
+            .map_or((self.turn_num, &self.initial_state), |snapshot| {
+                (snapshot.turn_num, &snapshot.game_state)
+            });
+
+        Ok(Self {
+            game_type: self.game_type.clone(),
+            seed: self.seed,
+            settings: self.settings.clone(),
+            turn_num: start,
+            initial_state: initial_state.clone(),
+            history_events: self
+                .history_events
+                .iter()
+                .filter(|event| event.turn_num >= start && event.turn_num < turn_num)
+                .cloned()
+                .collect(),
+            // Games only conclude on their latest turn
+            concluded: self.concluded && turn_num == latest,
+            snapshots: Vec::new(),
+        })
+    }
 }
 
 #[async_trait]
@@ -55,4 +144,61 @@ pub trait RawStorage: Send + Sync + 'static {
         &self,
         insert: (GameId, RawGameProgression, MetaData),
     ) -> Result<(), StorageError>;
+
+    /// Record one more turn of an already written game without rewriting the rest of it,
+    /// `concluded` is whether the game is over after the event
+    ///
+    /// Returns [`StorageError::NotFound`] for unknown games and
+    /// [`StorageError::InvalidHistory`] unless the event is the turn right after the last one
+    /// stored
+    async fn append_raw_history_event(
+        &self,
+        id: GameId,
+        event: RawHistoryEvent,
+        concluded: bool,
+    ) -> Result<(), StorageError>;
+
+    /// Read the last recorded history event of a game, `None` if it has no history yet
+    ///
+    /// Returns [`StorageError::NotFound`] for unknown games
+    async fn read_latest_raw_history_event(
+        &self,
+        id: GameId,
+    ) -> Result<Option<RawHistoryEvent>, StorageError>;
+
+    /// Record a snapshot of an already written game, replacing any snapshot of the same turn
+    ///
+    /// Returns [`StorageError::NotFound`] for unknown games
+    async fn write_raw_snapshot(
+        &self,
+        id: GameId,
+        snapshot: RawSnapshot,
+    ) -> Result<(), StorageError>;
+
+    /// How many turns apart [`Storage`](super::storage::Storage) keeps snapshots of games, `0`
+    /// for never
+    fn snapshot_interval(&self) -> u64;
+
+    /// The key [`Storage`](super::storage::Storage) HMACs the [hash chain](super::hash_chain) of
+    /// games with, `None` to hash them without one
+    fn history_key(&self) -> Option<&HistoryKey>;
+
+    /// List the stored games matching the query
+    async fn list_raw_game_progressions(&self, query: &GameQuery) -> Result<Page, StorageError>;
+}
+
+/// Check that `event` is the turn right after the ones recorded so far, for a game that started
+/// at `starting_turn_num` and has recorded up to `latest`
+pub(crate) fn check_append(
+    starting_turn_num: TurnNum,
+    latest: Option<TurnNum>,
+    event: &RawHistoryEvent,
+) -> Result<(), StorageError> {
+    let expected = latest.map_or(starting_turn_num, |latest| latest.next());
+
+    if event.turn_num == expected {
+        Ok(())
+    } else {
+        Err(StorageError::InvalidHistory(event.turn_num))
+    }
 }
@@ -1,28 +1,69 @@
 use async_trait::async_trait;
 
-use super::raw_storage::{RawCustomSettings, RawStorage};
+use super::hash_chain::{event_hash, genesis_hash, ChainBreak, HistoryHash};
+use super::query::{GameQuery, Page};
+use super::raw_storage::{
+    RawCustomSettings, RawGameProgression, RawHistoryEvent, RawSnapshot, RawStorage,
+};
+use bytes::Bytes;
 use chrono::prelude::*;
 use lttcore::{
-    encoding::EncodingError,
+    encoding::{Encoding, EncodingError},
     id::{GameId, SettingsId, UserId},
-    play::{settings::Custom, Play},
-    pov::game_progression::GameProgression,
+    play::{
+        settings::{Custom, VerifiedBuiltin},
+        ActionResponse, GameState, MigrationError, Play, SettingsPtr, Stored, TurnNum,
+    },
+    pov::game_progression::{GameProgression, GameProgressionBuilder, HistoryEvent},
+    utilities::PlayerIndexedData as PID,
 };
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::borrow::Cow;
+use std::str::FromStr;
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub enum StorageError {
     NotFound,
     EncodingError(EncodingError),
+    /// The stored record belongs to a different game than the one it was read as
+    WrongGameType {
+        expected: String,
+        found: String,
+    },
+    /// The stored settings name a builtin game mode the game doesn't have (anymore)
+    UnknownBuiltin(String),
+    /// The stored history doesn't replay, starting from the given turn
+    InvalidHistory(TurnNum),
+    /// The stored settings or actions were written by a version of the game they can't be
+    /// upgraded from
+    Migration(MigrationError),
+    /// An archive was written with a different encoding than the backend it's imported into
+    EncodingMismatch {
+        expected: Encoding,
+        found: Encoding,
+    },
+    /// The bytes aren't an archive this version can read
+    InvalidArchive(String),
+    /// Another user already has the username
+    UsernameTaken(String),
+    Database(sqlx::Error),
+    Io(std::io::Error),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SettingsType {
     Builtin(String),
     Custom(SettingsId),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+impl SettingsType {
+    pub fn is_custom(&self) -> bool {
+        matches!(self, SettingsType::Custom(_))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MetaData {
     pub owner: Option<UserId>,
     pub created_at: DateTime<Utc>,
@@ -41,10 +82,50 @@ pub trait Storage<T: Play>: Send + Sync + 'static {
         settings: (SettingsId, Custom<T::Settings>, MetaData),
     ) -> Result<(), StorageError>;
 
+    /// Read a game with its whole history, which means replaying all of it. Use
+    /// [`Storage::read_game_progression_at`] to replay from the nearest snapshot instead
     async fn read_game_progression(
         &self,
         id: GameId,
     ) -> Result<(GameId, GameProgression<T>, MetaData), StorageError>;
+
+    /// Read a game as it was at the start of `turn_num`, or at its latest turn for `None`,
+    /// replaying its history from the nearest snapshot before that turn
+    ///
+    /// The history before the snapshot isn't loaded, so the game starts from the snapshot's turn.
+    /// Writing it with [`Storage::write_game_progression`] keeps the stored history before the
+    /// snapshot.
+    async fn read_game_progression_at(
+        &self,
+        id: GameId,
+        turn_num: Option<TurnNum>,
+    ) -> Result<(GameId, GameProgression<T>, MetaData), StorageError>;
+
+    /// Write a whole game. Games that are already stored keep their stored history, hashes and
+    /// snapshots, with the turns of `game` after them added on. History written by an older
+    /// [`Play::SCHEMA_VERSION`] is migrated if `game` has all of it.
+    ///
+    /// Returns [`StorageError::InvalidHistory`] if `game` doesn't start within the stored history,
+    /// has fewer turns or disagrees with it about any turn
+    async fn write_game_progression(
+        &self,
+        game: (GameId, GameProgression<T>, MetaData),
+    ) -> Result<(), StorageError>;
+
+    /// Record the latest turn of a game that was already written with
+    /// [`Storage::write_game_progression`], see [`RawStorage::append_raw_history_event`]
+    async fn append_latest_turn(
+        &self,
+        id: GameId,
+        game: &GameProgression<T>,
+    ) -> Result<(), StorageError>;
+
+    /// List the stored games of this game type matching the query
+    async fn list_game_progressions(&self, query: &GameQuery) -> Result<Page, StorageError>;
+
+    /// Replay a stored game and check its history against its [hash chain](crate::hash_chain),
+    /// returning every break in the chain
+    async fn verify_history(&self, id: GameId) -> Result<Vec<ChainBreak>, StorageError>;
 }
 
 #[async_trait]
@@ -56,14 +137,16 @@ impl<T: Play, Raw: RawStorage> Storage<T> for Raw {
         self.read_raw_custom_settings(id)
             .await
             .and_then(|(id, raw, meta)| {
-                let settings = self
-                    .encoding()
-                    .deserialize(&raw.bytes)
-                    .map_err(StorageError::EncodingError)?;
+                let settings = decode_versioned::<T, _>(
+                    self.encoding(),
+                    raw.version,
+                    &raw.bytes,
+                    T::migrate_settings,
+                )?;
 
                 let custom = Custom {
                     name: raw.name.map(Cow::Owned),
-                    settings,
+                    settings: Arc::new(settings),
                 };
                 Ok((id, custom, meta))
             })
@@ -71,9 +154,124 @@ impl<T: Play, Raw: RawStorage> Storage<T> for Raw {
 
     async fn read_game_progression(
         &self,
-        _id: GameId,
+        id: GameId,
+    ) -> Result<(GameId, GameProgression<T>, MetaData), StorageError> {
+        let (id, raw, meta) = self.read_raw_game_progression(id).await?;
+        let settings = read_settings::<T>(self, &raw).await?;
+        let game = from_raw_game_progression(self.encoding(), raw, settings)?;
+        Ok((id, game, meta))
+    }
+
+    async fn read_game_progression_at(
+        &self,
+        id: GameId,
+        turn_num: Option<TurnNum>,
     ) -> Result<(GameId, GameProgression<T>, MetaData), StorageError> {
-        todo!()
+        let (id, raw, meta) = self.read_raw_game_progression(id).await?;
+        let settings = read_settings::<T>(self, &raw).await?;
+        let raw = raw.from_nearest_snapshot(turn_num)?;
+        let game = from_raw_game_progression(self.encoding(), raw, settings)?;
+        Ok((id, game, meta))
+    }
+
+    async fn write_game_progression(
+        &self,
+        (id, game, meta): (GameId, GameProgression<T>, MetaData),
+    ) -> Result<(), StorageError> {
+        let stored = match self.read_raw_game_progression(id).await {
+            Ok((_, raw, _)) => Some(raw),
+            Err(StorageError::NotFound) => None,
+            Err(err) => return Err(err),
+        };
+
+        let settings = match game.settings_ptr() {
+            SettingsPtr::Builtin(_) => SettingsType::Builtin(
+                SettingsPtr::name(game.settings_ptr())
+                    .expect("builtin game modes are always named")
+                    .to_owned(),
+            ),
+            SettingsPtr::Custom(custom) => match &stored {
+                // Keep pointing at the custom settings written the first time the game was saved
+                Some(RawGameProgression { settings, .. }) if settings.is_custom() => {
+                    settings.clone()
+                }
+                _ => {
+                    let settings_id = SettingsId::new();
+                    Storage::<T>::write_custom_settings(
+                        self,
+                        (settings_id, custom.clone(), meta.clone()),
+                    )
+                    .await?;
+                    SettingsType::Custom(settings_id)
+                }
+            },
+        };
+
+        let raw = match stored {
+            Some(stored) => extend_raw_game_progression(self, stored, &game, settings)?,
+            None => to_raw_game_progression(self, &game, settings)?,
+        };
+        self.write_raw_game_progression((id, raw, meta)).await
+    }
+
+    async fn append_latest_turn(
+        &self,
+        id: GameId,
+        game: &GameProgression<T>,
+    ) -> Result<(), StorageError> {
+        let event = game
+            .history()
+            .last()
+            .ok_or_else(|| StorageError::InvalidHistory(game.turn_num()))?;
+
+        // Chain onto the last stored event, or start the chain if there isn't one yet
+        let previous = match self.read_latest_raw_history_event(id).await? {
+            Some(RawHistoryEvent {
+                hash: Some(hash), ..
+            }) => hash,
+            Some(latest) => return Err(StorageError::InvalidHistory(latest.turn_num)),
+            None => {
+                let starting_turn_num = game.starting_turn_num();
+                let start = game
+                    .branch_at(starting_turn_num)
+                    .expect("a game can always be rewound to its starting turn");
+                genesis_hash(
+                    self.history_key(),
+                    game.seed(),
+                    starting_turn_num,
+                    start.game_state(),
+                )?
+            }
+        };
+        let hash = event_hash(
+            self.history_key(),
+            &previous,
+            event.turn_num(),
+            event.actions(),
+            game.public_info(),
+        )?;
+
+        let raw = to_raw_history_event(self.encoding(), event, hash)?;
+        self.append_raw_history_event(id, raw, game.is_concluded())
+            .await?;
+
+        if let Some(snapshot) = due_snapshot(self.encoding(), game, self.snapshot_interval())? {
+            self.write_raw_snapshot(id, snapshot).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn list_game_progressions(&self, query: &GameQuery) -> Result<Page, StorageError> {
+        let query = query.clone().game_type::<T>();
+        self.list_raw_game_progressions(&query).await
+    }
+
+    async fn verify_history(&self, id: GameId) -> Result<Vec<ChainBreak>, StorageError> {
+        let (_, raw, _) = self.read_raw_game_progression(id).await?;
+        let settings = read_settings::<T>(self, &raw).await?;
+        let (_, breaks) = replay_hash_chain::<T>(self, &raw, settings)?;
+        Ok(breaks)
     }
 
     async fn write_custom_settings(
@@ -87,7 +285,833 @@ impl<T: Play, Raw: RawStorage> Storage<T> for Raw {
                 .encoding()
                 .serialize(&custom.settings)
                 .map_err(StorageError::EncodingError)?,
+            version: T::SCHEMA_VERSION,
         };
         self.write_raw_custom_settings((id, raw, meta)).await
     }
 }
+
+/// Check the game type of a stored game and look up its settings
+async fn read_settings<T: Play>(
+    db: &impl RawStorage,
+    raw: &RawGameProgression,
+) -> Result<SettingsPtr<T::Settings>, StorageError> {
+    if raw.game_type != T::lib_table_top_identifier() {
+        return Err(StorageError::WrongGameType {
+            expected: String::from(T::lib_table_top_identifier()),
+            found: raw.game_type.clone(),
+        });
+    }
+
+    match &raw.settings {
+        SettingsType::Builtin(name) => VerifiedBuiltin::from_str(name)
+            .map(SettingsPtr::from)
+            .map_err(|_| StorageError::UnknownBuiltin(name.clone())),
+        SettingsType::Custom(settings_id) => {
+            let (_, custom, _) = Storage::<T>::read_custom_settings(db, *settings_id).await?;
+            Ok(SettingsPtr::Custom(custom))
+        }
+    }
+}
+
+fn to_raw_game_progression<T: Play>(
+    db: &impl RawStorage,
+    game: &GameProgression<T>,
+    settings: SettingsType,
+) -> Result<RawGameProgression, StorageError> {
+    let encoding = db.encoding();
+    let starting_turn_num = game.starting_turn_num();
+    let mut replay = game
+        .branch_at(starting_turn_num)
+        .expect("a game can always be rewound to its starting turn");
+    let initial_state = encoding
+        .serialize(replay.game_state())
+        .map_err(StorageError::EncodingError)?;
+
+    // Replay the history to hash it and snapshot it along the way
+    let mut hash = genesis_hash(
+        db.history_key(),
+        game.seed(),
+        starting_turn_num,
+        replay.game_state(),
+    )?;
+    let mut history_events = Vec::new();
+    let mut snapshots = Vec::new();
+
+    for event in game.history() {
+        let update = replay.resolve(event.actions().clone());
+        replay.update(update);
+
+        hash = event_hash(
+            db.history_key(),
+            &hash,
+            event.turn_num(),
+            event.actions(),
+            replay.public_info(),
+        )?;
+        history_events.push(to_raw_history_event(encoding, event, hash)?);
+
+        snapshots.extend(due_snapshot(encoding, &replay, db.snapshot_interval())?);
+    }
+
+    Ok(RawGameProgression {
+        game_type: String::from(T::lib_table_top_identifier()),
+        seed: *game.seed(),
+        settings,
+        turn_num: starting_turn_num,
+        initial_state,
+        history_events,
+        concluded: game.is_concluded(),
+        snapshots,
+    })
+}
+
+/// Add the turns of `game` after the stored history to it, keeping the stored history and its
+/// hashes and snapshots as they are unless they need migrating
+fn extend_raw_game_progression<T: Play>(
+    db: &impl RawStorage,
+    mut stored: RawGameProgression,
+    game: &GameProgression<T>,
+    settings: SettingsType,
+) -> Result<RawGameProgression, StorageError> {
+    let encoding = db.encoding();
+    let stored_latest = stored.latest_turn_num();
+    let starting_turn_num = game.starting_turn_num();
+    if starting_turn_num < stored.turn_num || starting_turn_num > stored_latest {
+        return Err(StorageError::InvalidHistory(starting_turn_num));
+    }
+
+    if game.turn_num() < stored_latest {
+        return Err(StorageError::InvalidHistory(game.turn_num()));
+    }
+
+    let overlap = stored
+        .history_events
+        .iter()
+        .skip_while(|event| event.turn_num < starting_turn_num);
+    for (event, stored_event) in game.history().zip(overlap) {
+        if decode_actions::<T>(encoding, stored_event)? != *event.actions() {
+            return Err(StorageError::InvalidHistory(event.turn_num()));
+        }
+    }
+
+    // Migrating events written by an older schema version changes them, so they're encoded and
+    // hashed again along with the rest of the history, as long as the stored hashes hold up
+    let outdated = stored
+        .history_events
+        .iter()
+        .any(|event| event.version != T::SCHEMA_VERSION);
+    if outdated && starting_turn_num == stored.turn_num {
+        let (_, breaks) = replay_hash_chain::<T>(db, &stored, game.settings_ptr().clone())?;
+        if let Some(ChainBreak::Mismatch(turn_num)) = breaks
+            .into_iter()
+            .find(|chain_break| matches!(chain_break, ChainBreak::Mismatch(_)))
+        {
+            return Err(StorageError::InvalidHistory(turn_num));
+        }
+
+        return to_raw_game_progression(db, game, settings);
+    }
+
+    let mut new_events = game
+        .history()
+        .skip_while(|event| event.turn_num() < stored_latest)
+        .peekable();
+    if new_events.peek().is_some() {
+        // Pick up from the stored latest turn, replaying from the nearest snapshot
+        let latest = stored.from_nearest_snapshot(None)?;
+        let mut replay = from_raw_game_progression(encoding, latest, game.settings_ptr().clone())?;
+        let mut previous = match stored.history_events.last() {
+            Some(RawHistoryEvent {
+                hash: Some(hash), ..
+            }) => *hash,
+            Some(_) => {
+                let (head, _) = replay_hash_chain::<T>(db, &stored, game.settings_ptr().clone())?;
+                head
+            }
+            None => genesis_hash(
+                db.history_key(),
+                &stored.seed,
+                replay.turn_num(),
+                replay.game_state(),
+            )?,
+        };
+
+        for event in new_events {
+            replay_actions(&mut replay, event.turn_num(), event.actions().clone())?;
+            previous = event_hash(
+                db.history_key(),
+                &previous,
+                event.turn_num(),
+                event.actions(),
+                replay.public_info(),
+            )?;
+            stored
+                .history_events
+                .push(to_raw_history_event(encoding, event, previous)?);
+
+            if let Some(snapshot) = due_snapshot(encoding, &replay, db.snapshot_interval())? {
+                stored.insert_snapshot(snapshot);
+            }
+        }
+    }
+
+    stored.settings = settings;
+    stored.concluded = game.is_concluded();
+    Ok(stored)
+}
+
+/// A snapshot of the game if its turn is one kept every `snapshot_interval` turns
+fn due_snapshot<T: Play>(
+    encoding: Encoding,
+    game: &GameProgression<T>,
+    snapshot_interval: u64,
+) -> Result<Option<RawSnapshot>, StorageError> {
+    if snapshot_interval == 0 || !u64::from(game.turn_num()).is_multiple_of(snapshot_interval) {
+        return Ok(None);
+    }
+
+    Ok(Some(RawSnapshot {
+        turn_num: game.turn_num(),
+        game_state: encoding
+            .serialize(game.game_state())
+            .map_err(StorageError::EncodingError)?,
+    }))
+}
+
+/// Replay a stored game and walk its hash chain, returning the hash its history ends on and
+/// every break in the chain
+///
+/// The chain follows the stored hashes so one bad hash doesn't break every event after it, and
+/// continues from the hash unhashed events should have had.
+fn replay_hash_chain<T: Play>(
+    db: &impl RawStorage,
+    raw: &RawGameProgression,
+    settings: SettingsPtr<T::Settings>,
+) -> Result<(HistoryHash, Vec<ChainBreak>), StorageError> {
+    let encoding = db.encoding();
+    let key = db.history_key();
+    let mut game = starting_game_progression::<T>(encoding, raw, settings)?;
+    let mut previous = genesis_hash(key, &raw.seed, game.turn_num(), game.game_state())?;
+    let mut breaks = Vec::new();
+
+    for event in &raw.history_events {
+        let actions = decode_actions(encoding, event)?;
+        replay_actions(&mut game, event.turn_num, actions)?;
+        let actions = game
+            .history()
+            .last()
+            .expect("the event was just replayed")
+            .actions();
+        let expected = event_hash(key, &previous, event.turn_num, actions, game.public_info())?;
+
+        previous = match event.hash {
+            Some(hash) => {
+                if hash != expected {
+                    breaks.push(ChainBreak::Mismatch(event.turn_num));
+                }
+                hash
+            }
+            None => {
+                breaks.push(ChainBreak::Unhashed(event.turn_num));
+                expected
+            }
+        };
+    }
+
+    Ok((previous, breaks))
+}
+
+fn to_raw_history_event<T: Play>(
+    encoding: Encoding,
+    event: &HistoryEvent<T>,
+    hash: HistoryHash,
+) -> Result<RawHistoryEvent, StorageError> {
+    let actions = event
+        .actions()
+        .iter()
+        .map(|(player, action)| encoding.serialize(action).map(|bytes| (player, bytes)))
+        .collect::<Result<_, _>>()
+        .map_err(StorageError::EncodingError)?;
+
+    Ok(RawHistoryEvent {
+        turn_num: event.turn_num(),
+        actions,
+        version: T::SCHEMA_VERSION,
+        hash: Some(hash),
+    })
+}
+
+fn from_raw_game_progression<T: Play>(
+    encoding: Encoding,
+    raw: RawGameProgression,
+    settings: SettingsPtr<T::Settings>,
+) -> Result<GameProgression<T>, StorageError> {
+    let mut game = starting_game_progression(encoding, &raw, settings)?;
+
+    for event in &raw.history_events {
+        let actions = decode_actions(encoding, event)?;
+        replay_actions(&mut game, event.turn_num, actions)?;
+    }
+
+    Ok(game)
+}
+
+/// The game as it was before any of its stored history
+fn starting_game_progression<T: Play>(
+    encoding: Encoding,
+    raw: &RawGameProgression,
+    settings: SettingsPtr<T::Settings>,
+) -> Result<GameProgression<T>, StorageError> {
+    let initial_state: GameState<T> = encoding
+        .deserialize(&raw.initial_state)
+        .map_err(StorageError::EncodingError)?;
+
+    let mut builder = GameProgressionBuilder::default();
+    builder
+        .seed(raw.seed)
+        .settings(settings)
+        .turn_num(raw.turn_num);
+
+    // Games that started from the state their settings and seed produce don't store it
+    let game = builder
+        .build()
+        .expect("game progression builders always build");
+    if game.game_state() == &initial_state {
+        return Ok(game);
+    }
+
+    Ok(builder
+        .initial_game_state(Arc::new(initial_state))
+        .build()
+        .expect("game progression builders always build"))
+}
+
+fn decode_actions<T: Play>(
+    encoding: Encoding,
+    event: &RawHistoryEvent,
+) -> Result<PID<ActionResponse<T>>, StorageError> {
+    event
+        .actions
+        .iter()
+        .map(|(player, bytes)| {
+            decode_versioned::<T, _>(encoding, event.version, bytes, T::migrate_action_response)
+                .map(|action| (player, action))
+        })
+        .collect()
+}
+
+/// Play the stored actions of `turn_num`, checking they're the turn the game is on and come from
+/// the players it's waiting on
+fn replay_actions<T: Play>(
+    game: &mut GameProgression<T>,
+    turn_num: TurnNum,
+    actions: PID<ActionResponse<T>>,
+) -> Result<(), StorageError> {
+    if turn_num != game.turn_num() || !game.which_players_input_needed().eq(actions.players()) {
+        return Err(StorageError::InvalidHistory(turn_num));
+    }
+
+    let update = game.resolve(actions);
+    game.update(update);
+    Ok(())
+}
+
+/// Decode a value written by `version` of `T`'s schema, upgrading it with `migrate` if it's older
+/// than the current one
+pub(crate) fn decode_versioned<T: Play, V: DeserializeOwned>(
+    encoding: Encoding,
+    version: u32,
+    bytes: &Bytes,
+    migrate: impl FnOnce(Stored<'_>) -> Result<V, MigrationError>,
+) -> Result<V, StorageError> {
+    use std::cmp::Ordering;
+
+    match version.cmp(&T::SCHEMA_VERSION) {
+        Ordering::Equal => encoding
+            .deserialize(bytes)
+            .map_err(StorageError::EncodingError),
+        Ordering::Less => migrate(Stored {
+            version,
+            encoding,
+            bytes,
+        })
+        .map_err(StorageError::Migration),
+        Ordering::Greater => Err(StorageError::Migration(MigrationError::unsupported::<T>(
+            version,
+        ))),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::db::hash_map_db::HashMapDB;
+    use crate::hash_chain::HistoryKey;
+    use lttcore::{
+        encoding::Encoding,
+        examples::{
+            guess_the_number::{
+                ActionError, GameSecretInfo, Guess, PublicInfo, Settings as GuessTheNumberSettings,
+            },
+            tic_tac_toe::{Action, Position},
+            GuessTheNumber, TicTacToe,
+        },
+        play::{
+            seed::SEED_42,
+            view::NoSecretPlayerInfo,
+            ActionResponse::{Resign, Response, Timeout},
+            GameStateUpdate,
+        },
+        LibTableTopIdentifier,
+    };
+
+    fn meta() -> MetaData {
+        MetaData {
+            owner: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Play a game of [`TicTacToe`] turn by turn on a backend keeping snapshots every 2 turns,
+    /// and check reads start from the right snapshot
+    pub(crate) async fn test_snapshots(db: &impl RawStorage) -> Result<(), StorageError> {
+        assert_eq!(db.snapshot_interval(), 2);
+        let id = GameId::new();
+
+        let mut game: GameProgression<TicTacToe> =
+            GameProgression::from_settings_and_seed(SettingsPtr::default(), SEED_42);
+        db.write_game_progression((id, game.clone(), meta()))
+            .await?;
+
+        for (row, col) in [(0, 0), (1, 1), (2, 2), (0, 1), (0, 2)] {
+            let player = game.which_players_input_needed().next().unwrap();
+            let action = Response(Action::from(Position::new(row, col)));
+            let update = game.resolve([(player, action)].into_iter().collect());
+            game.update(update);
+            db.append_latest_turn(id, &game).await?;
+        }
+
+        let snapshot_turns = |raw: RawGameProgression| {
+            raw.snapshots
+                .iter()
+                .map(|snapshot| u64::from(snapshot.turn_num))
+                .collect::<Vec<_>>()
+        };
+        let (_, raw, _) = db.read_raw_game_progression(id).await?;
+        assert_eq!(snapshot_turns(raw), vec![2, 4]);
+
+        let (_, at_3, _) =
+            Storage::<TicTacToe>::read_game_progression_at(db, id, Some(3.into())).await?;
+        assert_eq!(at_3.starting_turn_num(), TurnNum::from(2));
+        assert_eq!(at_3.game_state(), game.branch_at(3).unwrap().game_state());
+
+        let (_, latest, _) = Storage::<TicTacToe>::read_game_progression_at(db, id, None).await?;
+        assert_eq!(latest.starting_turn_num(), TurnNum::from(4));
+        assert_eq!(latest.turn_num(), game.turn_num());
+        assert_eq!(latest.game_state(), game.game_state());
+
+        let result = Storage::<TicTacToe>::read_game_progression_at(db, id, Some(6.into())).await;
+        assert!(matches!(result, Err(StorageError::InvalidHistory(_))));
+
+        // Writing the whole game snapshots it the same way as appending turn by turn
+        db.write_game_progression((id, game.clone(), meta()))
+            .await?;
+        let (_, raw, _) = db.read_raw_game_progression(id).await?;
+        assert_eq!(snapshot_turns(raw), vec![2, 4]);
+        let (_, read, _) = Storage::<TicTacToe>::read_game_progression(db, id).await?;
+        assert_eq!(read, game);
+        assert_eq!(Storage::<TicTacToe>::verify_history(db, id).await?, vec![]);
+
+        // Writing a game read from a snapshot keeps the history before the snapshot
+        let (_, mut latest, _) =
+            Storage::<TicTacToe>::read_game_progression_at(db, id, None).await?;
+        for game in [&mut latest, &mut game] {
+            let player = game.which_players_input_needed().next().unwrap();
+            let action = Response(Action::from(Position::new(1, 0)));
+            let update = game.resolve([(player, action)].into_iter().collect());
+            game.update(update);
+        }
+        db.write_game_progression((id, latest, meta())).await?;
+        let (_, read, _) = Storage::<TicTacToe>::read_game_progression(db, id).await?;
+        assert_eq!(read, game);
+        assert_eq!(Storage::<TicTacToe>::verify_history(db, id).await?, vec![]);
+
+        // Games that would drop or change stored turns aren't written
+        let result = db
+            .write_game_progression((id, game.branch_at(3).unwrap(), meta()))
+            .await;
+        assert!(matches!(result, Err(StorageError::InvalidHistory(_))));
+
+        let mut branch = game.branch_at(5).unwrap();
+        let player = branch.which_players_input_needed().next().unwrap();
+        let action = Response(Action::from(Position::new(2, 0)));
+        let update = branch.resolve([(player, action)].into_iter().collect());
+        branch.update(update);
+        let result = db.write_game_progression((id, branch, meta())).await;
+        assert!(matches!(result, Err(StorageError::InvalidHistory(_))));
+
+        // Reading a concluded game before its latest turn reads an unfinished game
+        let player = game.which_players_input_needed().next().unwrap();
+        let action = Response(Action::from(Position::new(1, 2)));
+        let update = game.resolve([(player, action)].into_iter().collect());
+        game.update(update);
+        assert!(game.is_concluded());
+        db.append_latest_turn(id, &game).await?;
+
+        let (_, raw, _) = db.read_raw_game_progression(id).await?;
+        assert!(raw.from_nearest_snapshot(None)?.concluded);
+        assert!(!raw.from_nearest_snapshot(Some(6.into()))?.concluded);
+        let (_, at_6, _) =
+            Storage::<TicTacToe>::read_game_progression_at(db, id, Some(6.into())).await?;
+        assert!(!at_6.is_concluded());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_snapshots_in_a_hash_map_db() {
+        let db = HashMapDB::new(Encoding::Bincode).with_snapshot_interval(2);
+        test_snapshots(&db).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_verifying_history_hash_chains() {
+        let db = HashMapDB::new(Encoding::Json);
+        let (appended, written) = (GameId::new(), GameId::new());
+
+        let mut game: GameProgression<TicTacToe> =
+            GameProgression::from_settings_and_seed(SettingsPtr::default(), SEED_42);
+        db.write_game_progression((appended, game.clone(), meta()))
+            .await
+            .unwrap();
+        for position in [
+            Position::new(0, 0),
+            Position::new(1, 1),
+            Position::new(2, 2),
+        ] {
+            let player = game.which_players_input_needed().next().unwrap();
+            let action = Response(Action::from(position));
+            let update = game.resolve([(player, action)].into_iter().collect());
+            game.update(update);
+            db.append_latest_turn(appended, &game).await.unwrap();
+        }
+        db.write_game_progression((written, game.clone(), meta()))
+            .await
+            .unwrap();
+
+        // Appending turn by turn and writing the whole game hash the same way
+        let (_, mut raw, meta) = db.read_raw_game_progression(appended).await.unwrap();
+        let (_, written_raw, _) = db.read_raw_game_progression(written).await.unwrap();
+        assert_eq!(raw.history_events, written_raw.history_events);
+        for id in [appended, written] {
+            let breaks = Storage::<TicTacToe>::verify_history(&db, id).await.unwrap();
+            assert_eq!(breaks, vec![]);
+        }
+
+        // Move the O somewhere else and drop the hash of the last turn
+        let moved = Response::<TicTacToe>(Action::from(Position::new(0, 2)));
+        for (_, bytes) in raw.history_events[1].actions.iter_mut() {
+            *bytes = Encoding::Json.serialize(&moved).unwrap();
+        }
+        raw.history_events.last_mut().unwrap().hash = None;
+        db.write_raw_game_progression((appended, raw, meta.clone()))
+            .await
+            .unwrap();
+
+        let breaks = Storage::<TicTacToe>::verify_history(&db, appended)
+            .await
+            .unwrap();
+        assert_eq!(
+            breaks,
+            vec![
+                ChainBreak::Mismatch(TurnNum::from(1)),
+                ChainBreak::Unhashed(TurnNum::from(2))
+            ]
+        );
+
+        // Appends can't chain onto a history that isn't hashed
+        let player = game.which_players_input_needed().next().unwrap();
+        let action = Response(Action::from(Position::new(2, 0)));
+        let update = game.resolve([(player, action)].into_iter().collect());
+        game.update(update);
+        let result = db.append_latest_turn(appended, &game).await;
+        assert!(matches!(
+            result,
+            Err(StorageError::InvalidHistory(turn_num)) if turn_num == TurnNum::from(2)
+        ));
+
+        // Nor can writing the whole game hash the history again
+        let result = db.write_game_progression((appended, game, meta)).await;
+        assert!(matches!(
+            result,
+            Err(StorageError::InvalidHistory(turn_num)) if turn_num == TurnNum::from(1)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_keyed_hash_chains() {
+        let key = HistoryKey::new(b"a secret");
+        let db = HashMapDB::new(Encoding::Bincode).with_history_key(key.clone());
+        let id = GameId::new();
+
+        let mut game: GameProgression<TicTacToe> =
+            GameProgression::from_settings_and_seed(SettingsPtr::default(), SEED_42);
+        let player = game.which_players_input_needed().next().unwrap();
+        let action = Response(Action::from(Position::new(1, 1)));
+        let update = game.resolve([(player, action)].into_iter().collect());
+        game.update(update);
+        db.write_game_progression((id, game, meta())).await.unwrap();
+        let breaks = Storage::<TicTacToe>::verify_history(&db, id).await.unwrap();
+        assert_eq!(breaks, vec![]);
+
+        // The chain only holds up with the key it was hashed with
+        let raw = db.read_raw_game_progression(id).await.unwrap();
+        for other in [
+            HashMapDB::new(Encoding::Bincode),
+            HashMapDB::new(Encoding::Bincode).with_history_key(HistoryKey::new(b"another")),
+        ] {
+            other.write_raw_game_progression(raw.clone()).await.unwrap();
+            let breaks = Storage::<TicTacToe>::verify_history(&other, id)
+                .await
+                .unwrap();
+            assert_eq!(breaks, vec![ChainBreak::Mismatch(TurnNum::from(0))]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_game_progressions_round_trip() {
+        let db = HashMapDB::new(Encoding::Bincode);
+        let id = GameId::new();
+
+        let mut game: GameProgression<TicTacToe> =
+            GameProgression::from_settings_and_seed(SettingsPtr::default(), SEED_42);
+        for position in [
+            Position::new(0, 0),
+            Position::new(1, 1),
+            Position::new(2, 2),
+        ] {
+            let player = game.which_players_input_needed().next().unwrap();
+            let action = Response(Action::from(position));
+            let update = game.resolve([(player, action)].into_iter().collect());
+            game.update(update);
+        }
+
+        db.write_game_progression((id, game.clone(), meta()))
+            .await
+            .unwrap();
+        let (_, raw, _) = db.read_raw_game_progression(id).await.unwrap();
+        assert_eq!(raw.settings, SettingsType::Builtin("default".to_owned()));
+        assert_eq!(raw.history_events.len(), 3);
+
+        let (read_id, read, _) = Storage::<TicTacToe>::read_game_progression(&db, id)
+            .await
+            .unwrap();
+        assert_eq!(read_id, id);
+        assert_eq!(read, game);
+
+        let result = Storage::<GuessTheNumber>::read_game_progression(&db, id).await;
+        assert!(matches!(result, Err(StorageError::WrongGameType { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_appending_history_events() {
+        let db = HashMapDB::new(Encoding::Bincode);
+        let id = GameId::new();
+
+        let mut game: GameProgression<TicTacToe> =
+            GameProgression::from_settings_and_seed(SettingsPtr::default(), SEED_42);
+        db.write_game_progression((id, game.clone(), meta()))
+            .await
+            .unwrap();
+
+        for position in [Position::new(0, 0), Position::new(1, 1)] {
+            let player = game.which_players_input_needed().next().unwrap();
+            let action = Response(Action::from(position));
+            let update = game.resolve([(player, action)].into_iter().collect());
+            game.update(update);
+
+            db.append_latest_turn(id, &game).await.unwrap();
+        }
+
+        let (_, read, _) = Storage::<TicTacToe>::read_game_progression(&db, id)
+            .await
+            .unwrap();
+        assert_eq!(read, game);
+
+        let result = db.append_latest_turn(id, &game).await;
+        assert!(matches!(result, Err(StorageError::InvalidHistory(_))));
+
+        // Skipping a turn leaves a gap in the history
+        for position in [Position::new(2, 2), Position::new(0, 1)] {
+            let player = game.which_players_input_needed().next().unwrap();
+            let action = Response(Action::from(position));
+            let update = game.resolve([(player, action)].into_iter().collect());
+            game.update(update);
+        }
+        let result = db.append_latest_turn(id, &game).await;
+        assert!(matches!(result, Err(StorageError::InvalidHistory(_))));
+
+        let result = db.append_latest_turn(GameId::new(), &game).await;
+        assert!(matches!(result, Err(StorageError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_game_progressions_with_custom_settings() {
+        let db = HashMapDB::new(Encoding::Json);
+        let id = GameId::new();
+
+        let settings = GuessTheNumberSettings::try_from(1..=5).unwrap();
+        let mut game: GameProgression<GuessTheNumber> =
+            GameProgression::from_settings_and_seed(settings, SEED_42);
+
+        db.write_game_progression((id, game.clone(), meta()))
+            .await
+            .unwrap();
+        let (_, first, _) = db.read_raw_game_progression(id).await.unwrap();
+        assert!(first.settings.is_custom());
+
+        let actions = game
+            .which_players_input_needed()
+            .map(|player| (player, Response(Guess(3))))
+            .collect();
+        let update = game.resolve(actions);
+        game.update(update);
+
+        db.write_game_progression((id, game.clone(), meta()))
+            .await
+            .unwrap();
+
+        let (_, second, _) = db.read_raw_game_progression(id).await.unwrap();
+        assert_eq!(first.settings, second.settings);
+
+        let (_, read, _) = Storage::<GuessTheNumber>::read_game_progression(&db, id)
+            .await
+            .unwrap();
+        assert_eq!(read, game);
+    }
+
+    /// [`GuessTheNumber`] after bumping its schema version, without changing how anything is
+    /// serialized
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    struct GuessTheNumberV1;
+
+    impl LibTableTopIdentifier for GuessTheNumberV1 {
+        fn lib_table_top_identifier() -> &'static str {
+            GuessTheNumber::lib_table_top_identifier()
+        }
+    }
+
+    impl Play for GuessTheNumberV1 {
+        type Action = Guess;
+        type ActionError = ActionError;
+        type PublicInfo = PublicInfo;
+        type Settings = GuessTheNumberSettings;
+        type PlayerSecretInfo = NoSecretPlayerInfo;
+        type GameSecretInfo = GameSecretInfo;
+
+        const SCHEMA_VERSION: u32 = 1;
+
+        fn initial_state_for_settings(
+            settings: &Self::Settings,
+            rng: &mut impl rand::Rng,
+        ) -> GameState<Self> {
+            let state = GuessTheNumber::initial_state_for_settings(settings, rng);
+            GameState {
+                player_secret_info: state.player_secret_info,
+                game_secret_info: state.game_secret_info,
+                public_info: state.public_info,
+                action_requests: state.action_requests,
+            }
+        }
+
+        fn resolve(
+            game_state: &GameState<Self>,
+            settings: &Self::Settings,
+            actions: Cow<'_, PID<ActionResponse<Self>>>,
+            rng: &mut impl rand::Rng,
+        ) -> GameStateUpdate<Self> {
+            let game_state = GameState::<GuessTheNumber> {
+                player_secret_info: game_state.player_secret_info.clone(),
+                game_secret_info: game_state.game_secret_info.clone(),
+                public_info: game_state.public_info.clone(),
+                action_requests: game_state.action_requests.clone(),
+            };
+            let actions = actions
+                .iter()
+                .map(|(player, response)| {
+                    let response = match response {
+                        Response(guess) => Response(*guess),
+                        Timeout => Timeout,
+                        Resign => Resign,
+                    };
+                    (player, response)
+                })
+                .collect();
+
+            let update = GuessTheNumber::resolve(&game_state, settings, Cow::Owned(actions), rng);
+            GameStateUpdate {
+                player_secret_info_updates: update.player_secret_info_updates,
+                game_secret_info_update: update.game_secret_info_update,
+                public_info_update: update.public_info_update,
+                action_requests: update.action_requests,
+                debug_msgs: update.debug_msgs,
+            }
+        }
+
+        fn migrate_settings(stored: Stored<'_>) -> Result<Self::Settings, MigrationError> {
+            stored.decode()
+        }
+
+        fn migrate_action_response(
+            stored: Stored<'_>,
+        ) -> Result<ActionResponse<Self>, MigrationError> {
+            stored.decode_action_response::<Self, Guess>(Ok)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reading_games_written_by_older_schema_versions() {
+        let db = HashMapDB::new(Encoding::Bincode);
+        let id = GameId::new();
+
+        let settings = GuessTheNumberSettings::try_from(1..=5).unwrap();
+        let mut game: GameProgression<GuessTheNumber> =
+            GameProgression::from_settings_and_seed(settings, SEED_42);
+        let actions = game
+            .which_players_input_needed()
+            .map(|player| (player, Response(Guess(3))))
+            .collect();
+        let update = game.resolve(actions);
+        game.update(update);
+        db.write_game_progression((id, game.clone(), meta()))
+            .await
+            .unwrap();
+
+        let (_, migrated, _) = Storage::<GuessTheNumberV1>::read_game_progression(&db, id)
+            .await
+            .unwrap();
+        assert_eq!(migrated.settings(), game.settings());
+        assert_eq!(migrated.turn_num(), game.turn_num());
+        assert!(migrated.is_concluded());
+
+        // Games written by the newer version can't be read by the older one
+        db.write_game_progression((id, migrated, meta()))
+            .await
+            .unwrap();
+        let (_, raw, _) = db.read_raw_game_progression(id).await.unwrap();
+        assert!(raw.history_events.iter().all(|event| event.version == 1));
+
+        let result = Storage::<GuessTheNumber>::read_game_progression(&db, id).await;
+        assert!(matches!(
+            result,
+            Err(StorageError::Migration(
+                MigrationError::UnsupportedVersion {
+                    version: 1,
+                    current: 0
+                }
+            ))
+        ));
+    }
+}